@@ -8,10 +8,12 @@ use lazy_static::lazy_static;
 use syntect::highlighting::Theme as SyntaxTheme;
 use syntect::parsing::SyntaxSet;
 
+use crate::color::ColorDepth;
 use crate::config::delta_unreachable;
 use crate::env::DeltaEnv;
 use crate::git_config::GitConfig;
 use crate::options;
+use crate::options::theme::SyntaxThemeColors;
 use crate::utils;
 use crate::utils::bat::output::PagingMode;
 
@@ -243,6 +245,33 @@ pub struct Opt {
     /// See: (https://docs.rs/chrono/latest/chrono/format/strftime/index.html)
     pub blame_timestamp_output_format: Option<String>,
 
+    #[arg(long = "check-contrast")]
+    /// Check the contrast of the foreground and background colors of all styles.
+    ///
+    /// Computes the WCAG contrast ratio of the foreground and background colors of each style,
+    /// including each foreground color of the syntax theme over the backgrounds of
+    /// syntax-highlighted styles such as plus-emph-style, and reports the pairs below 4.5:1. The
+    /// terminal's default colors are assumed to be those of the syntax theme. Exits with status 1
+    /// if any pair fails. Combine it with other options, e.g. --colorblind or --light, to check
+    /// them.
+    pub check_contrast: bool,
+
+    #[arg(long = "collapse-files", default_value = "", value_name = "GLOBS")]
+    /// Show only the file header, with the numbers of added and removed lines, for matching files.
+    ///
+    /// A space-separated list of glob patterns, e.g. "Cargo.lock package-lock.json *.pb.go".
+    /// Patterns without a slash match the file name in any directory, others match the path
+    /// relative to the repository root. Useful for lockfiles and generated code, whose changes
+    /// are rarely worth reading. See also --collapse-gitattributes.
+    pub collapse_files: String,
+
+    #[arg(long = "collapse-gitattributes")]
+    /// Collapse files according to .gitattributes, like --collapse-files.
+    ///
+    /// Files are collapsed if they have the linguist-generated, linguist-vendored or
+    /// delta-collapse attribute set, or the diff attribute unset (-diff).
+    pub collapse_gitattributes: bool,
+
     #[arg(long = "color-only")]
     /// Do not alter the input structurally in any way.
     ///
@@ -250,6 +279,28 @@ pub struct Opt {
     /// intended for other tools that use delta.
     pub color_only: bool,
 
+    #[arg(long = "colorblind")]
+    /// Use blue and orange instead of green and red for added and removed lines.
+    ///
+    /// Unlike the default colors, the backgrounds of added and removed lines and the line numbers
+    /// remain distinguishable with red-green color blindness (deuteranopia and protanopia). There
+    /// are palettes for light and dark terminal backgrounds. See also --check-contrast.
+    pub colorblind: bool,
+
+    #[arg(
+        long = "color-depth",
+        default_value = "auto",
+        value_name = "auto|16|256|truecolor"
+    )]
+    /// The number of colors that the terminal can display.
+    ///
+    /// All colors, whether from styles, the blame palette, or the syntax theme, are converted to
+    /// the perceptually closest color available: one of the 16 ANSI colors, one of the 256 colors
+    /// of the xterm palette, or any 24-bit color. "auto" means 24-bit color if --true-color
+    /// enables it, and otherwise 16 colors if the environment variable TERM names a terminal
+    /// such as "linux" or "vt100", or ends in "-16color" or "-8color", and 256 colors if not.
+    pub color_depth: String,
+
     #[arg(long = "config", default_value = "", value_name = "PATH")]
     /// Load the config file at PATH instead of ~/.gitconfig.
     pub config: String,
@@ -293,6 +344,21 @@ pub struct Opt {
     /// set this in per-repository git config (.git/config)
     pub default_language: Option<String>,
 
+    #[arg(
+        long = "detect-dark-light",
+        default_value = "auto",
+        value_name = "auto|always|never"
+    )]
+    /// Whether to ask the terminal for its background color, to choose light or dark mode.
+    ///
+    /// If neither --light, --dark, --syntax-theme nor the BAT_THEME environment variable is given,
+    /// delta queries the background color of the terminal and uses light mode, with the default
+    /// light syntax theme, if the background is light. "auto" means that the terminal is queried
+    /// only if the output is written to a terminal, and "always" that it is queried even if not,
+    /// e.g. when delta is used in a pipeline. The terminal is queried through /dev/tty, and delta
+    /// waits at most 100 milliseconds for its response.
+    pub detect_dark_light: String,
+
     #[arg(long = "diff-highlight")]
     /// Emulate diff-highlight.
     ///
@@ -350,6 +416,20 @@ pub struct Opt {
     /// 'ul' (underline), 'ol' (overline), or the combination 'ul ol'.
     pub file_decoration_style: String,
 
+    #[arg(long = "file-header-format", default_value = "", value_name = "FMT")]
+    /// Format string for the text of the file header.
+    ///
+    /// If empty, the file header is the file path with a label for the file event (see
+    /// --file-added-label etc.), followed by any change of file mode. Otherwise it is made up of
+    /// the text of the format string, with placeholders {file} (the file path with its label),
+    /// {added} and {removed} (the numbers of lines added and removed in the file), {mode} (the old
+    /// and new file mode, if changed), {similarity} (the similarity index of a renamed or copied
+    /// file) and {symlink} (the old and new target of a symbolic link), which take a format spec
+    /// as in --line-numbers-left-format. For example "{file} (+{added}/-{removed}) {mode}".
+    /// {added}, {removed} and {symlink} are known only at the end of the file, so the output for a
+    /// file is held back until then if they are used.
+    pub file_header_format: String,
+
     #[arg(
         long = "file-modified-label",
         default_value = "",
@@ -390,6 +470,41 @@ pub struct Opt {
     /// Sed-style command transforming file paths for display.
     pub file_regex_replacement: Option<String>,
 
+    #[arg(long = "fold-unchanged-context", default_value = "3", value_name = "N")]
+    /// Number of unchanged lines kept next to each change when folding.
+    ///
+    /// See --fold-unchanged-lines.
+    pub fold_unchanged_context: usize,
+
+    #[arg(
+        long = "fold-unchanged-label",
+        default_value = "⋯",
+        value_name = "STRING"
+    )]
+    /// Text to display before and after the number of folded unchanged lines.
+    ///
+    /// Used in the default value of navigate-regex, so that folded regions can be found with n/N.
+    pub fold_unchanged_label: String,
+
+    #[arg(long = "fold-unchanged-lines", default_value = "0", value_name = "N")]
+    /// Fold runs of more than N unchanged lines into a single line.
+    ///
+    /// The lines next to each change are kept (see --fold-unchanged-context), and the rest of the
+    /// run is replaced by a line such as "⋯ 143 unchanged lines ⋯". This is useful with large
+    /// amounts of context, e.g. `git diff -U50` or `git show --function-context`. 0 disables
+    /// folding.
+    pub fold_unchanged_lines: usize,
+
+    #[arg(
+        long = "fold-unchanged-style",
+        default_value = "inline-hint-style",
+        value_name = "STYLE"
+    )]
+    /// Style string for the line replacing folded unchanged lines.
+    ///
+    /// See STYLES section.
+    pub fold_unchanged_style: String,
+
     #[arg(long = "grep-context-line-style", value_name = "STYLE")]
     /// Style string for non-matching lines of grep output.
     ///
@@ -432,6 +547,53 @@ pub struct Opt {
     /// lines: set this option to "keep" to keep the original separator symbols.
     pub grep_separator_symbol: String,
 
+    #[arg(long = "highlight-full-files")]
+    /// Syntax-highlight the full files of a git diff, instead of each hunk by itself.
+    ///
+    /// Highlighting starts at the first line of each hunk, so a hunk which starts inside a
+    /// multi-line string or comment is highlighted incorrectly. With this option, delta loads both
+    /// versions of each file from the git repository, using the blob ids of the diff's "index"
+    /// line, and highlights them from the start. Files which cannot be loaded, or which are larger
+    /// than --highlight-full-files-max-size, are highlighted hunk by hunk.
+    pub highlight_full_files: bool,
+
+    #[arg(
+        long = "highlight-full-files-max-size",
+        default_value = "1000000",
+        value_name = "N"
+    )]
+    /// Largest file, in bytes, highlighted in full by --highlight-full-files.
+    pub highlight_full_files_max_size: usize,
+
+    #[arg(
+        long = "hunk-header-breadcrumb-separator",
+        default_value = " › ",
+        value_name = "STRING"
+    )]
+    /// String joining the scopes of a hunk-header breadcrumb.
+    ///
+    /// See --hunk-header-breadcrumbs.
+    pub hunk_header_breadcrumb_separator: String,
+
+    #[arg(
+        long = "hunk-header-breadcrumb-style",
+        default_value = "syntax",
+        value_name = "STYLE"
+    )]
+    /// Style string for the hunk-header breadcrumb.
+    ///
+    /// See STYLES section and --hunk-header-breadcrumbs.
+    pub hunk_header_breadcrumb_style: String,
+
+    #[arg(long = "hunk-header-breadcrumbs")]
+    /// Show the scopes enclosing a hunk in its header, if git does not provide a code fragment.
+    ///
+    /// The scopes, e.g. "impl StateMachine › fn handle_hunk_line", are the definitions of
+    /// functions, classes, modules etc. found by syntax highlighting the context lines at the start
+    /// of the hunk, which enclose the first changed line according to their indentation. Git's code
+    /// fragment (see the diff.<driver>.xfuncname git config) is shown instead if present.
+    pub hunk_header_breadcrumbs: bool,
+
     #[arg(
         long = "hunk-header-decoration-style",
         default_value = "blue box",
@@ -443,6 +605,19 @@ pub struct Opt {
     /// 'ul' (underline), 'ol' (overline), or the combination 'ul ol'.
     pub hunk_header_decoration_style: String,
 
+    #[arg(long = "hunk-header-format", default_value = "", value_name = "FMT")]
+    /// Format string for the file path and line number part of the hunk header.
+    ///
+    /// If empty, the part is given by the 'file' and 'line-number' attributes of
+    /// --hunk-header-style. Otherwise it is made up of the text of the format string, with
+    /// placeholders {hunk_index} and {hunk_count} (the position of the hunk among the hunks of its
+    /// file), {added} and {removed} (the numbers of lines added and removed in the hunk),
+    /// {file_path} and {line_number}, which take a format spec as in --line-numbers-left-format.
+    /// For example "{file_path}:{line_number} ({hunk_index}/{hunk_count})". {hunk_count}, {added}
+    /// and {removed} are known only at the end of the file, so the output for a file is held back
+    /// until then if they are used.
+    pub hunk_header_format: String,
+
     #[arg(
         long = "hunk-header-file-style",
         default_value = "blue",
@@ -663,12 +838,35 @@ pub struct Opt {
     /// List available syntax-highlighting color themes.
     pub list_syntax_themes: bool,
 
+    #[arg(long = "map-languages", value_name = "LANGUAGES_MAP")]
+    /// Syntax-highlight files matching glob patterns as the given languages.
+    ///
+    /// An example is --map-languages='Jenkinsfile => Groovy, *.inc => PHP'. Patterns are matched
+    /// like those of --collapse-files, and the languages are those of --list-languages, named or
+    /// given by a file extension. This takes precedence over the file extension, but the
+    /// delta-language and linguist-language gitattributes take precedence over it. Files whose
+    /// language is still unknown are recognized by a shebang on their first line, or by a vim or
+    /// emacs modeline, if these appear in the diff.
+    pub map_languages: Option<String>,
+
     #[arg(long = "map-styles", value_name = "STYLES_MAP")]
     /// Map styles encountered in raw input to desired output styles.
     ///
     /// An example is --map-styles='bold purple => red "#eeeeee", bold cyan => syntax "#eeeeee"'
     pub map_styles: Option<String>,
 
+    #[arg(
+        long = "markdown-collapse-min-lines",
+        default_value = "0",
+        value_name = "N"
+    )]
+    /// Collapse files with at least N changed lines in Markdown output.
+    ///
+    /// With --output-format=markdown, the diff block of such a file is written in a <details>
+    /// element, whose summary shows the file and its numbers of added and removed lines. The
+    /// default value of 0 means that no file is collapsed.
+    pub markdown_collapse_min_lines: usize,
+
     #[arg(long = "max-line-distance", default_value = "0.6", value_name = "DIST")]
     /// Maximum line pair distance parameter in within-line diff algorithm.
     ///
@@ -790,6 +988,16 @@ pub struct Opt {
     /// See STYLES section.
     pub minus_style: String,
 
+    #[arg(long = "monochrome")]
+    /// Write output without colors or other ANSI escape sequences, using only ASCII characters.
+    ///
+    /// Removed and added lines keep their -/+ markers (see --keep-plus-minus-markers), emphasized
+    /// sections of them are marked as [-removed-] and {+added+}, decorations are drawn with ASCII
+    /// characters, and the side-by-side view is not used. This is
+    /// also activated by setting the NO_COLOR environment variable to a non-empty value. Use it
+    /// e.g. for CI logs, or when sending output by email.
+    pub monochrome: bool,
+
     #[arg(long = "navigate")]
     /// Activate diff navigation.
     ///
@@ -807,6 +1015,28 @@ pub struct Opt {
     /// See GIT CONFIG section.
     pub no_gitconfig: bool,
 
+    #[arg(
+        long = "output-format",
+        default_value = "ansi",
+        value_name = "ansi|html|json|markdown|svg"
+    )]
+    /// The format of delta's output.
+    ///
+    /// With "ansi", the output is styled with ANSI escape sequences for display in a terminal.
+    /// With "html", delta writes a self-contained HTML page showing the same styles, with
+    /// hyperlinks as links, suitable for pasting into review documents and wikis. Each hunk line
+    /// is an element whose classes give its kind (removed, added or unchanged), and with
+    /// --side-by-side the lines are laid out in the two columns of a table. The background and
+    /// foreground colors of the page are those of the syntax theme. With "json", delta writes the
+    /// parsed diff as JSON Lines instead of painted text: one JSON object per commit, file, hunk,
+    /// unchanged line, and group of removed and added lines, with line numbers, the alignment of
+    /// removed and added lines, and the edited sections within them. With "markdown", delta
+    /// writes a Markdown document, e.g. for a pull request comment, with a heading for each
+    /// commit and file, and the hunks of each file in a fenced diff block (see
+    /// --markdown-collapse-min-lines). With "svg", delta writes an SVG image of its output on a
+    /// grid of monospace character cells, for use as a screenshot in documentation.
+    pub output_format: String,
+
     #[arg(long = "pager", value_name = "CMD")]
     /// Which pager to use.
     ///
@@ -825,6 +1055,15 @@ pub struct Opt {
     /// Options are: auto, always, and never.
     pub paging_mode: String,
 
+    #[arg(long = "paint-threads", default_value = "0", value_name = "N")]
+    /// Number of threads painting the lines of hunks.
+    ///
+    /// Syntax highlighting and the rest of the painting of hunk lines is done in parallel on N
+    /// threads, while the input is read; the output is the same as with a single thread. With 0,
+    /// delta uses one thread per CPU, up to 8. With 1, lines are painted as they are read, which
+    /// may suit slowly produced input best.
+    pub paint_threads: usize,
+
     #[arg(long = "parse-ansi")]
     /// Display ANSI color escape sequences in human-readable form.
     ///
@@ -923,10 +1162,93 @@ pub struct Opt {
     /// shown, use --dark or --light, or both, on the command line together with this option.
     pub show_themes: bool,
 
+    #[arg(long = "show-whitespace")]
+    /// Make tabs, spaces, carriage returns and invisible characters visible.
+    ///
+    /// Tabs are shown with --whitespace-tab-symbol followed by spaces, carriage returns with
+    /// --whitespace-cr-symbol, non-breaking spaces with --whitespace-nbsp-symbol, and zero-width
+    /// and other invisible characters with --whitespace-invisible-symbol. Spaces are shown with
+    /// --whitespace-space-symbol in the changed regions of removed and added lines only. The
+    /// symbols are painted in --whitespace-style. Syntax highlighting and the detection of
+    /// within-line edits are unaffected by the symbols.
+    pub show_whitespace: bool,
+
     #[arg(short = 's', long = "side-by-side")]
     /// Display diffs in side-by-side layout.
     pub side_by_side: bool,
 
+    #[arg(
+        long = "side-by-side-min-code-width",
+        default_value = "60",
+        value_name = "N"
+    )]
+    /// Minimum width available for code in each panel when side-by-side-mode is auto.
+    ///
+    /// The width is what remains of a panel after line numbers and +/- markers. See
+    /// --side-by-side-mode.
+    pub side_by_side_min_code_width: usize,
+
+    #[arg(
+        long = "side-by-side-mode",
+        default_value = "always",
+        value_name = "always|auto"
+    )]
+    /// Whether side-by-side layout is used unconditionally.
+    ///
+    /// Has no effect unless side-by-side is enabled. Options are always and auto. "auto" means that
+    /// delta uses side-by-side layout only if each panel leaves at least
+    /// --side-by-side-min-code-width columns for code, and falls back to unified layout otherwise.
+    /// Furthermore, a hunk containing lines too long to fit in a panel even after wrapping (see
+    /// --wrap-max-lines) is displayed in unified layout, as a whole.
+    pub side_by_side_mode: String,
+
+    #[arg(
+        long = "side-by-side-ratio",
+        default_value = "50%",
+        value_name = "N%|auto"
+    )]
+    /// How the width is split between the panels in side-by-side layout.
+    ///
+    /// Either the percentage of the width taken up by the left panel (between 10% and 90%), or
+    /// "auto". With "auto" the split is chosen for each hunk, in proportion to the width of the
+    /// longest removed and added lines of its first block of changes.
+    pub side_by_side_ratio: String,
+
+    #[arg(
+        long = "side-by-side-separator",
+        default_value = "",
+        value_name = "STRING"
+    )]
+    /// String placed between the left and the right panel in side-by-side layout.
+    ///
+    /// The panels shrink by its width. For example "│" or " " to separate the panels by a line or
+    /// a space.
+    pub side_by_side_separator: String,
+
+    #[arg(long = "summary")]
+    /// Write a summary of the diff at the end of the output.
+    ///
+    /// The summary gives the numbers of commits, files, hunks, and lines added and removed, and
+    /// lists the files with the most lines changed.
+    pub summary: bool,
+
+    #[arg(
+        long = "summary-decoration-style",
+        default_value = "box",
+        value_name = "STYLE"
+    )]
+    /// Style string for the summary decoration.
+    ///
+    /// See STYLES section. The style string should contain one of the special attributes 'box',
+    /// 'ul' (underline), 'ol' (overline), or the combination 'ul ol'.
+    pub summary_decoration_style: String,
+
+    #[arg(long = "summary-style", default_value = "bold", value_name = "STYLE")]
+    /// Style string for the summary written by --summary.
+    ///
+    /// See STYLES section.
+    pub summary_style: String,
+
     #[arg(long = "syntax-theme", value_name = "SYNTAX_THEME")]
     /// The syntax-highlighting theme to use.
     ///
@@ -935,6 +1257,27 @@ pub struct Opt {
     /// syntax highlighting.
     pub syntax_theme: Option<String>,
 
+    #[arg(long = "syntax-theme-colors")]
+    /// Derive diff colors from the syntax theme.
+    ///
+    /// The backgrounds of removed and added lines, the line numbers, and the file and hunk header
+    /// decorations take their colors from the syntax theme's colors for deleted and inserted text
+    /// (the diff.deleted and diff.inserted, or markup.deleted and markup.inserted, scopes), shaded
+    /// towards its background color, and from its gutter and accent colors. Style options that
+    /// you set explicitly take precedence.
+    pub syntax_theme_colors: bool,
+
+    #[arg(long = "syntaxes-dir", value_name = "PATH")]
+    /// Directory of additional syntax definitions and syntax themes.
+    ///
+    /// The .sublime-syntax and .tmTheme files in this directory and its subdirectories are used in
+    /// addition to the syntaxes and themes of bat, taking precedence over them. A theme is named
+    /// after its file name without the extension. Delta caches them in its cache directory, and
+    /// rebuilds the cache when the files change. Use --list-languages to see which syntaxes come
+    /// from this directory. Defaults to ~/.config/delta/syntaxes (or delta/syntaxes in
+    /// $XDG_CONFIG_HOME).
+    pub syntaxes_dir: Option<String>,
+
     #[arg(long = "tabs", default_value = "4", value_name = "N")]
     /// The number of spaces to replace tab characters with.
     ///
@@ -944,6 +1287,26 @@ pub struct Opt {
     /// incorrect.
     pub tab_width: usize,
 
+    #[arg(long = "toc")]
+    /// Write a table of contents before the files of each commit.
+    ///
+    /// The table of contents lists the files changed by the commit (or by the whole diff, if not
+    /// viewing commits), with their numbers of added and removed lines, and links to the files if
+    /// --hyperlinks is set. It is written if more than one file was changed. The output for the
+    /// files is held back until the end of the commit; see --toc-max-buffer-size.
+    pub toc: bool,
+
+    #[arg(
+        long = "toc-max-buffer-size",
+        default_value = "1000000",
+        value_name = "N"
+    )]
+    /// Maximum number of bytes of output held back for a table of contents.
+    ///
+    /// If the output for the files of a commit is larger, it is written without a table of
+    /// contents, as it becomes available.
+    pub toc_max_buffer_size: usize,
+
     #[arg(
         long = "true-color",
         default_value = "auto",
@@ -958,6 +1321,37 @@ pub struct Opt {
     /// need to do anything.
     pub true_color: String,
 
+    #[arg(
+        long = "unicode-warning-style",
+        default_value = "bold yellow reverse",
+        value_name = "STYLE"
+    )]
+    /// Style string for the warnings of --unicode-warnings.
+    pub unicode_warning_style: String,
+
+    #[arg(
+        long = "unicode-warnings",
+        default_value = "off",
+        value_name = "off|lines|files"
+    )]
+    /// Whether to warn about Unicode characters which can disguise code in added lines.
+    ///
+    /// Bidirectional control characters (e.g. U+202E) and zero-width characters are replaced by
+    /// their code point, e.g. <U+202E>, and identifiers mixing letters of confusable scripts, such
+    /// as Latin and Cyrillic, are followed by the names of the scripts, e.g. <Latin+Cyrillic>.
+    /// These are painted in --unicode-warning-style. With "files", the file header also reports the
+    /// number of such lines; the output for a file is then held back until the file has been read.
+    /// No warnings are added to --output-format=json.
+    pub unicode_warnings: String,
+
+    #[arg(
+        long = "whitespace-cr-symbol",
+        default_value = "␍",
+        value_name = "STRING"
+    )]
+    /// Carriage return symbol for --show-whitespace.
+    pub whitespace_cr_symbol: String,
+
     #[arg(
         long = "whitespace-error-style",
         default_value = "auto auto",
@@ -968,6 +1362,45 @@ pub struct Opt {
     /// Defaults to color.diff.whitespace if that is set in git config, or else 'magenta reverse'.
     pub whitespace_error_style: String,
 
+    #[arg(
+        long = "whitespace-invisible-symbol",
+        default_value = "¤",
+        value_name = "STRING"
+    )]
+    /// Symbol for zero-width and other invisible characters, for --show-whitespace.
+    pub whitespace_invisible_symbol: String,
+
+    #[arg(
+        long = "whitespace-nbsp-symbol",
+        default_value = "⍽",
+        value_name = "STRING"
+    )]
+    /// Non-breaking space symbol for --show-whitespace.
+    pub whitespace_nbsp_symbol: String,
+
+    #[arg(
+        long = "whitespace-space-symbol",
+        default_value = "·",
+        value_name = "STRING"
+    )]
+    /// Space symbol for --show-whitespace.
+    pub whitespace_space_symbol: String,
+
+    #[arg(long = "whitespace-style", default_value = "dim", value_name = "STYLE")]
+    /// Style string for the symbols of --show-whitespace.
+    ///
+    /// Colors which are not set are taken from the surrounding text, so that e.g. the background
+    /// color of added lines is kept.
+    pub whitespace_style: String,
+
+    #[arg(
+        long = "whitespace-tab-symbol",
+        default_value = "→",
+        value_name = "STRING"
+    )]
+    /// Tab symbol for --show-whitespace.
+    pub whitespace_tab_symbol: String,
+
     #[arg(short = 'w', long = "width", value_name = "N")]
     /// The width of underline/overline decorations.
     ///
@@ -1063,6 +1496,7 @@ pub struct Opt {
 #[derive(Default, Clone, Debug)]
 pub struct ComputedValues {
     pub available_terminal_width: usize,
+    pub color_depth: ColorDepth,
     pub stdout_is_term: bool,
     pub background_color_extends_to_terminal_width: bool,
     pub decorations_width: Width,
@@ -1071,6 +1505,8 @@ pub struct ComputedValues {
     pub paging_mode: PagingMode,
     pub syntax_set: SyntaxSet,
     pub syntax_theme: Option<SyntaxTheme>,
+    pub syntax_theme_colors: Option<SyntaxThemeColors>,
+    pub syntax_sources: HashMap<String, PathBuf>,
     pub true_color: bool,
 }
 
@@ -1166,6 +1602,7 @@ impl Opt {
 // pseudo-flag commands such as --list-languages
 lazy_static! {
     static ref IGNORED_OPTION_NAMES: HashSet<&'static str> = vec![
+        "check-contrast",
         "list-languages",
         "list-syntax-themes",
         "show-config",
@@ -105,39 +105,207 @@ fn ansi_16_color_number_to_name(n: u8) -> Option<&'static str> {
     None
 }
 
-pub fn get_minus_background_color_default(is_light_mode: bool, is_true_color: bool) -> Color {
-    match (is_light_mode, is_true_color) {
-        (true, true) => LIGHT_THEME_MINUS_COLOR,
-        (true, false) => LIGHT_THEME_MINUS_COLOR_256,
-        (false, true) => DARK_THEME_MINUS_COLOR,
-        (false, false) => DARK_THEME_MINUS_COLOR_256,
+/// The number of colors that the terminal can display.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    #[default]
+    Ansi256,
+    TrueColor,
+}
+
+/// Return the color of the palette of `color_depth` which is perceptually closest to `color`.
+/// With 16 colors, the bright colors 8-15 are returned as the corresponding base colors, since a
+/// terminal with only 16 colors may not support the 256-color codes used for them by ansi_term.
+pub fn quantize_color(color: Color, color_depth: ColorDepth) -> Color {
+    quantize_foreground_color(color, color_depth).0
+}
+
+/// As `quantize_color`, but also return whether the color is one of the bright colors 8-15, which
+/// are written as their base colors in bold: a 16-color terminal shows bold text in bright colors.
+pub fn quantize_foreground_color(color: Color, color_depth: ColorDepth) -> (Color, bool) {
+    match (color_depth, color) {
+        (ColorDepth::TrueColor, _) => (color, false),
+        (ColorDepth::Ansi256, Color::RGB(r, g, b)) => (
+            Color::Fixed(ansi_colours::ansi256_from_rgb((r, g, b))),
+            false,
+        ),
+        (ColorDepth::Ansi256, _) => (color, false),
+        (ColorDepth::Ansi16, Color::Fixed(n)) if n < 16 => ansi_16_color(n),
+        (ColorDepth::Ansi16, Color::RGB(..) | Color::Fixed(_)) => {
+            let lab = rgb_to_lab(color_to_rgb(color));
+            let distance = |n: &u8| {
+                let other = rgb_to_lab(XTERM_16_COLORS[*n as usize]);
+                (0..3).map(|i| (lab[i] - other[i]).powi(2)).sum::<f64>()
+            };
+            // The distances are finite, so that they can be compared.
+            let n = (0..16)
+                .min_by(|m, n| distance(m).partial_cmp(&distance(n)).unwrap())
+                .unwrap();
+            ansi_16_color(n)
+        }
+        (ColorDepth::Ansi16, _) => (color, false),
     }
 }
 
-pub fn get_minus_emph_background_color_default(is_light_mode: bool, is_true_color: bool) -> Color {
-    match (is_light_mode, is_true_color) {
-        (true, true) => LIGHT_THEME_MINUS_EMPH_COLOR,
-        (true, false) => LIGHT_THEME_MINUS_EMPH_COLOR_256,
-        (false, true) => DARK_THEME_MINUS_EMPH_COLOR,
-        (false, false) => DARK_THEME_MINUS_EMPH_COLOR_256,
+/// Quantize the foreground and background colors of `style`, see `quantize_foreground_color`.
+pub fn quantize_ansi_term_style(
+    style: ansi_term::Style,
+    color_depth: ColorDepth,
+) -> ansi_term::Style {
+    let foreground = style
+        .foreground
+        .map(|color| quantize_foreground_color(color, color_depth));
+    ansi_term::Style {
+        foreground: foreground.map(|(color, _)| color),
+        background: style
+            .background
+            .map(|color| quantize_color(color, color_depth)),
+        is_bold: style.is_bold || matches!(foreground, Some((_, true))),
+        ..style
     }
 }
 
-pub fn get_plus_background_color_default(is_light_mode: bool, is_true_color: bool) -> Color {
-    match (is_light_mode, is_true_color) {
-        (true, true) => LIGHT_THEME_PLUS_COLOR,
-        (true, false) => LIGHT_THEME_PLUS_COLOR_256,
-        (false, true) => DARK_THEME_PLUS_COLOR,
-        (false, false) => DARK_THEME_PLUS_COLOR_256,
+/// Return the base ANSI color of the color with number `n` < 16, which uses codes 30-37 and
+/// 40-47 as in utils::bat::terminal::to_ansi_color, and whether the color is bright.
+fn ansi_16_color(n: u8) -> (Color, bool) {
+    let color = match n % 8 {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Purple,
+        6 => Color::Cyan,
+        _ => Color::White,
+    };
+    (color, n >= 8)
+}
+
+/// Return the RGB values of `color`, taking the ANSI colors from the xterm palette.
+pub fn color_to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Black => XTERM_16_COLORS[0],
+        Color::Red => XTERM_16_COLORS[1],
+        Color::Green => XTERM_16_COLORS[2],
+        Color::Yellow => XTERM_16_COLORS[3],
+        Color::Blue => XTERM_16_COLORS[4],
+        Color::Purple => XTERM_16_COLORS[5],
+        Color::Cyan => XTERM_16_COLORS[6],
+        Color::White => XTERM_16_COLORS[7],
+        Color::Fixed(n @ 0..=15) => XTERM_16_COLORS[n as usize],
+        Color::Fixed(n @ 16..=231) => {
+            let level = |i: u8| if i == 0 { 0 } else { 55 + 40 * i };
+            let i = n - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        Color::Fixed(n) => {
+            let gray = 8 + 10 * (n - 232);
+            (gray, gray, gray)
+        }
+        Color::RGB(r, g, b) => (r, g, b),
     }
 }
 
-pub fn get_plus_emph_background_color_default(is_light_mode: bool, is_true_color: bool) -> Color {
-    match (is_light_mode, is_true_color) {
-        (true, true) => LIGHT_THEME_PLUS_EMPH_COLOR,
-        (true, false) => LIGHT_THEME_PLUS_EMPH_COLOR_256,
-        (false, true) => DARK_THEME_PLUS_EMPH_COLOR,
-        (false, false) => DARK_THEME_PLUS_EMPH_COLOR_256,
+// The xterm palette.
+const XTERM_16_COLORS: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+fn linearize(c: u8) -> f64 {
+    let c = c as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The WCAG contrast ratio of two sRGB colors, from 1 (no contrast) to 21 (black and white).
+pub fn contrast_ratio(a: (u8, u8, u8), b: (u8, u8, u8)) -> f64 {
+    let luminance =
+        |(r, g, b)| 0.2126 * linearize(r) + 0.7152 * linearize(g) + 0.0722 * linearize(b);
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Convert an sRGB color to CIELAB, in which Euclidean distances approximate perceived
+/// differences.
+pub fn rgb_to_lab((r, g, b): (u8, u8, u8)) -> [f64; 3] {
+    let (r, g, b) = (linearize(r), linearize(g), linearize(b));
+    // Relative to the D65 white point.
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub fn get_minus_background_color_default(is_light_mode: bool, color_depth: ColorDepth) -> Color {
+    match (is_light_mode, color_depth) {
+        (_, ColorDepth::Ansi16) => MINUS_COLOR_16,
+        (true, ColorDepth::TrueColor) => LIGHT_THEME_MINUS_COLOR,
+        (true, ColorDepth::Ansi256) => LIGHT_THEME_MINUS_COLOR_256,
+        (false, ColorDepth::TrueColor) => DARK_THEME_MINUS_COLOR,
+        (false, ColorDepth::Ansi256) => DARK_THEME_MINUS_COLOR_256,
+    }
+}
+
+pub fn get_minus_emph_background_color_default(
+    is_light_mode: bool,
+    color_depth: ColorDepth,
+) -> Color {
+    match (is_light_mode, color_depth) {
+        (_, ColorDepth::Ansi16) => MINUS_EMPH_COLOR_16,
+        (true, ColorDepth::TrueColor) => LIGHT_THEME_MINUS_EMPH_COLOR,
+        (true, ColorDepth::Ansi256) => LIGHT_THEME_MINUS_EMPH_COLOR_256,
+        (false, ColorDepth::TrueColor) => DARK_THEME_MINUS_EMPH_COLOR,
+        (false, ColorDepth::Ansi256) => DARK_THEME_MINUS_EMPH_COLOR_256,
+    }
+}
+
+pub fn get_plus_background_color_default(is_light_mode: bool, color_depth: ColorDepth) -> Color {
+    match (is_light_mode, color_depth) {
+        (_, ColorDepth::Ansi16) => PLUS_COLOR_16,
+        (true, ColorDepth::TrueColor) => LIGHT_THEME_PLUS_COLOR,
+        (true, ColorDepth::Ansi256) => LIGHT_THEME_PLUS_COLOR_256,
+        (false, ColorDepth::TrueColor) => DARK_THEME_PLUS_COLOR,
+        (false, ColorDepth::Ansi256) => DARK_THEME_PLUS_COLOR_256,
+    }
+}
+
+pub fn get_plus_emph_background_color_default(
+    is_light_mode: bool,
+    color_depth: ColorDepth,
+) -> Color {
+    match (is_light_mode, color_depth) {
+        (_, ColorDepth::Ansi16) => PLUS_EMPH_COLOR_16,
+        (true, ColorDepth::TrueColor) => LIGHT_THEME_PLUS_EMPH_COLOR,
+        (true, ColorDepth::Ansi256) => LIGHT_THEME_PLUS_EMPH_COLOR_256,
+        (false, ColorDepth::TrueColor) => DARK_THEME_PLUS_EMPH_COLOR,
+        (false, ColorDepth::Ansi256) => DARK_THEME_PLUS_EMPH_COLOR_256,
     }
 }
 
@@ -173,8 +341,98 @@ const DARK_THEME_PLUS_EMPH_COLOR: Color = Color::RGB(0x00, 0x60, 0x00);
 
 const DARK_THEME_PLUS_EMPH_COLOR_256: Color = Color::Fixed(28);
 
+const MINUS_COLOR_16: Color = Color::Red;
+
+const MINUS_EMPH_COLOR_16: Color = Color::Fixed(9);
+
+const PLUS_COLOR_16: Color = Color::Green;
+
+const PLUS_EMPH_COLOR_16: Color = Color::Fixed(10);
+
 // blame
 
 pub const LIGHT_THEME_BLAME_PALETTE: &[&str] = &["#FFFFFF", "#DDDDDD", "#BBBBBB"];
 
 pub const DARK_THEME_BLAME_PALETTE: &[&str] = &["#000000", "#222222", "#444444"];
+
+#[cfg(test)]
+mod tests {
+    use ansi_term::Color;
+
+    use super::{
+        contrast_ratio, quantize_ansi_term_style, quantize_color, quantize_foreground_color,
+        ColorDepth,
+    };
+
+    #[test]
+    fn test_quantize_color() {
+        let quantize = |color, color_depth| quantize_color(color, color_depth);
+        let orange = Color::RGB(0xff, 0x87, 0x00);
+        assert_eq!(quantize(orange, ColorDepth::TrueColor), orange);
+        assert_eq!(quantize(orange, ColorDepth::Ansi256), Color::Fixed(208));
+        assert_eq!(
+            quantize(Color::RGB(0xd0, 0xc0, 0x20), ColorDepth::Ansi16),
+            Color::Yellow
+        );
+        assert_eq!(
+            quantize(Color::RGB(0x10, 0x10, 0x10), ColorDepth::Ansi16),
+            Color::Black
+        );
+        assert_eq!(
+            quantize(Color::RGB(0xff, 0x20, 0x20), ColorDepth::Ansi16),
+            Color::Red
+        );
+        assert_eq!(quantize(Color::Fixed(22), ColorDepth::Ansi16), Color::Green);
+        assert_eq!(
+            quantize(Color::Fixed(22), ColorDepth::Ansi256),
+            Color::Fixed(22)
+        );
+        assert_eq!(quantize(Color::Fixed(12), ColorDepth::Ansi16), Color::Blue);
+        assert_eq!(quantize(Color::Blue, ColorDepth::Ansi16), Color::Blue);
+        assert_eq!(
+            quantize_foreground_color(Color::RGB(0xff, 0x20, 0x20), ColorDepth::Ansi16),
+            (Color::Red, true)
+        );
+        assert_eq!(
+            quantize_foreground_color(Color::Fixed(12), ColorDepth::Ansi256),
+            (Color::Fixed(12), false)
+        );
+    }
+
+    #[test]
+    fn test_quantized_bright_colors_use_16_color_codes() {
+        let style = ansi_term::Style {
+            foreground: Some(Color::Fixed(9)),
+            background: Some(Color::Fixed(12)),
+            ..ansi_term::Style::new()
+        };
+        assert_eq!(
+            quantize_ansi_term_style(style, ColorDepth::Ansi16)
+                .paint("x")
+                .to_string(),
+            "\x1b[1;44;31mx\x1b[0m"
+        );
+        let style = ansi_term::Style {
+            foreground: Some(Color::RGB(0x10, 0x80, 0x10)),
+            background: Some(Color::RGB(0x80, 0x80, 0x80)),
+            ..ansi_term::Style::new()
+        };
+        assert_eq!(
+            quantize_ansi_term_style(style, ColorDepth::Ansi16)
+                .paint("x")
+                .to_string(),
+            "\x1b[40;32mx\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        let (black, white) = ((0, 0, 0), (0xff, 0xff, 0xff));
+        assert_eq!(contrast_ratio(black, white), 21.0);
+        assert_eq!(contrast_ratio(white, black), 21.0);
+        assert_eq!(contrast_ratio(white, white), 1.0);
+        // #767676 is the lightest gray passing WCAG AA on white.
+        assert!(contrast_ratio((0x76, 0x76, 0x76), white) > 4.5);
+        assert!(contrast_ratio((0x77, 0x77, 0x77), white) < 4.5);
+    }
+}
@@ -2,14 +2,16 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use clap::parser::ValueSource;
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 use regex::Regex;
 use syntect::highlighting::Style as SyntectStyle;
 use syntect::highlighting::Theme as SyntaxTheme;
 use syntect::parsing::SyntaxSet;
+use unicode_width::UnicodeWidthStr;
 
 use crate::ansi;
 use crate::cli;
-use crate::color;
+use crate::color::{self, ColorDepth};
 use crate::delta::State;
 use crate::fatal;
 use crate::features::navigate;
@@ -19,14 +21,17 @@ use crate::handlers;
 use crate::handlers::blame::parse_blame_line_numbers;
 use crate::handlers::blame::BlameLineNumbers;
 use crate::minusplus::MinusPlus;
+use crate::output_format::OutputFormat;
 use crate::paint::BgFillMethod;
 use crate::parse_styles;
 use crate::style;
 use crate::style::Style;
+use crate::suspicious_unicode::UnicodeWarnings;
 use crate::tests::TESTING;
 use crate::utils;
 use crate::utils::bat::output::PagingMode;
 use crate::utils::regex_replacement::RegexReplacement;
+use crate::whitespace::ShowWhitespaceConfig;
 use crate::wrapping::WrapConfig;
 
 pub const INLINE_SYMBOL_WIDTH_1: usize = 1;
@@ -42,6 +47,9 @@ pub struct Config {
     pub blame_separator_style: Option<Style>,
     pub blame_timestamp_format: String,
     pub blame_timestamp_output_format: Option<String>,
+    pub collapse_files: Option<GlobSet>,
+    pub collapse_gitattributes: bool,
+    pub color_depth: ColorDepth,
     pub color_only: bool,
     pub commit_regex: Regex,
     pub commit_style: Style,
@@ -54,13 +62,17 @@ pub struct Config {
     pub error_exit_code: i32,
     pub file_added_label: String,
     pub file_copied_label: String,
+    pub file_header_format: String,
     pub file_modified_label: String,
     pub file_removed_label: String,
     pub file_renamed_label: String,
     pub file_regex_replacement: Option<RegexReplacement>,
+    pub fold_unchanged_context: usize,
+    pub fold_unchanged_label: String,
+    pub fold_unchanged_lines: usize,
+    pub fold_unchanged_style: Style,
     pub right_arrow: String,
     pub file_style: Style,
-    pub git_config: Option<GitConfig>,
     pub git_minus_style: Style,
     pub git_plus_style: Style,
     pub grep_context_line_style: Style,
@@ -70,7 +82,13 @@ pub struct Config {
     pub grep_match_word_style: Style,
     pub grep_separator_symbol: String,
     pub handle_merge_conflicts: bool,
+    pub highlight_full_files: bool,
+    pub highlight_full_files_max_size: usize,
+    pub hunk_header_breadcrumb_separator: String,
+    pub hunk_header_breadcrumb_style: Style,
+    pub hunk_header_breadcrumbs: bool,
     pub hunk_header_file_style: Style,
+    pub hunk_header_format: String,
     pub hunk_header_line_number_style: Style,
     pub hunk_header_style_include_file_path: bool,
     pub hunk_header_style_include_line_number: bool,
@@ -81,16 +99,21 @@ pub struct Config {
     pub hyperlinks: bool,
     pub inline_hint_style: Style,
     pub inspect_raw_lines: cli::InspectRawLines,
+    pub is_light_mode: bool,
     pub keep_plus_minus_markers: bool,
     pub line_buffer_size: usize,
     pub line_fill_method: BgFillMethod,
     pub line_numbers_format: LeftRight<String>,
     pub line_numbers_style_leftright: LeftRight<Style>,
     pub line_numbers_style_minusplus: MinusPlus<Style>,
+    // The line number formats of lines painted in unified layout in side-by-side mode.
+    pub line_numbers_unified_format: LeftRight<String>,
     pub line_numbers_zero_style: Style,
     pub line_numbers: bool,
+    pub languages_map: Vec<(GlobMatcher, String)>,
     pub styles_map: Option<HashMap<style::AnsiTermStyleEqualityKey, Style>>,
     pub max_line_distance_for_naively_paired_lines: f64,
+    pub markdown_collapse_min_lines: usize,
     pub max_line_distance: f64,
     pub max_line_length: usize,
     pub merge_conflict_begin_symbol: String,
@@ -102,12 +125,15 @@ pub struct Config {
     pub minus_file: Option<PathBuf>,
     pub minus_non_emph_style: Style,
     pub minus_style: Style,
+    pub monochrome: bool,
     pub navigate_regex: Option<String>,
     pub navigate: bool,
     pub null_style: Style,
     pub null_syntect_style: SyntectStyle,
+    pub output_format: OutputFormat,
     pub pager: Option<String>,
     pub paging_mode: PagingMode,
+    pub paint_threads: usize,
     pub plus_emph_style: Style,
     pub plus_empty_line_marker_style: Style,
     pub plus_file: Option<PathBuf>,
@@ -115,16 +141,27 @@ pub struct Config {
     pub plus_style: Style,
     pub relative_paths: bool,
     pub show_themes: bool,
+    pub show_whitespace: Option<ShowWhitespaceConfig>,
     pub side_by_side_data: side_by_side::SideBySideData,
     pub side_by_side: bool,
+    pub side_by_side_mode: side_by_side::SideBySideMode,
+    pub side_by_side_ratio: side_by_side::SideBySideRatio,
+    pub side_by_side_separator: String,
+    pub summary: bool,
+    pub summary_style: Style,
     pub syntax_dummy_theme: SyntaxTheme,
     pub syntax_set: SyntaxSet,
     pub syntax_theme: Option<SyntaxTheme>,
     pub tab_width: usize,
+    pub toc: bool,
+    pub toc_max_buffer_size: usize,
     pub tokenization_regex: Regex,
     pub true_color: bool,
     pub truncation_symbol: String,
+    pub unicode_warning_style: Style,
+    pub unicode_warnings: UnicodeWarnings,
     pub whitespace_error_style: Style,
+    pub whitespace_style: Style,
     pub wrap_config: WrapConfig,
     pub zero_style: Style,
 }
@@ -142,18 +179,23 @@ impl Config {
             _ => delta_unreachable("Unreachable code reached in get_style."),
         }
     }
-
-    pub fn git_config(&self) -> Option<&GitConfig> {
-        self.git_config.as_ref()
-    }
 }
 
 impl From<cli::Opt> for Config {
     fn from(opt: cli::Opt) -> Self {
+        Self::from_opt(opt).0
+    }
+}
+
+impl Config {
+    /// Make the config from `opt`, and return it with the git config that `opt` was read from,
+    /// which is not part of it, see `GitConfig`.
+    pub fn from_opt(opt: cli::Opt) -> (Self, Option<GitConfig>) {
         let mut styles = parse_styles::parse_styles(&opt);
         let styles_map = parse_styles::parse_styles_map(&opt);
 
         let wrap_config = WrapConfig::from_opt(&opt, styles["inline-hint-style"]);
+        let show_whitespace = ShowWhitespaceConfig::from_opt(&opt);
 
         let max_line_distance_for_naively_paired_lines = opt
             .env
@@ -197,16 +239,64 @@ impl From<cli::Opt> for Config {
             _ => fatal("Invalid option for line-fill-method: Expected \"ansi\" or \"spaces\"."),
         };
 
+        let output_format = match opt.output_format.as_ref() {
+            "ansi" => OutputFormat::Ansi,
+            "html" => OutputFormat::Html,
+            "json" => OutputFormat::Json,
+            "markdown" => OutputFormat::Markdown,
+            "svg" => OutputFormat::Svg,
+            _ => fatal(
+                "Invalid option for output-format: \
+                 Expected \"ansi\", \"html\", \"json\", \"markdown\" or \"svg\".",
+            ),
+        };
+        let side_by_side_ratio = parse_side_by_side_ratio(&opt.side_by_side_ratio);
         let side_by_side_data = side_by_side::SideBySideData::new_sbs(
             &opt.computed.decorations_width,
             &opt.computed.available_terminal_width,
+            match side_by_side_ratio {
+                side_by_side::SideBySideRatio::Fixed(percent) => percent,
+                side_by_side::SideBySideRatio::Auto => 50,
+            },
+            opt.side_by_side_separator.width(),
         );
         let side_by_side_data = ansifill::UseFullPanelWidth::sbs_odd_fix(
             &opt.computed.decorations_width,
             &line_fill_method,
+            opt.side_by_side_separator.width(),
             side_by_side_data,
         );
 
+        let side_by_side_mode = match opt.side_by_side_mode.as_ref() {
+            "always" => side_by_side::SideBySideMode::Always,
+            "auto" => side_by_side::SideBySideMode::Auto,
+            _ => fatal("Invalid option for side-by-side-mode: Expected \"always\" or \"auto\"."),
+        };
+        let line_numbers_format =
+            LeftRight::new(opt.line_numbers_left_format, opt.line_numbers_right_format);
+        // Emphasis markers would not fit into the panels.
+        let side_by_side = opt.side_by_side
+            && !opt.monochrome
+            && !handlers::hunk::is_word_diff()
+            && (side_by_side_mode == side_by_side::SideBySideMode::Always
+                || side_by_side::panels_fit_min_code_width(
+                    &side_by_side_data,
+                    if opt.line_numbers {
+                        Some(&line_numbers_format)
+                    } else {
+                        None
+                    },
+                    opt.keep_plus_minus_markers,
+                    opt.side_by_side_min_code_width,
+                ));
+        let line_numbers_unified_format =
+            side_by_side::unified_line_numbers_format(&line_numbers_format);
+        let line_numbers_format = if opt.side_by_side && !side_by_side {
+            line_numbers_unified_format.clone()
+        } else {
+            line_numbers_format
+        };
+
         let navigate_regex = if (opt.navigate || opt.show_themes)
             && (opt.navigate_regex.is_none() || opt.navigate_regex == Some("".to_string()))
         {
@@ -217,6 +307,11 @@ impl From<cli::Opt> for Config {
                 &file_removed_label,
                 &file_renamed_label,
                 &hunk_label,
+                if opt.fold_unchanged_lines > 0 {
+                    &opt.fold_unchanged_label
+                } else {
+                    ""
+                },
             ))
         } else {
             opt.navigate_regex
@@ -234,11 +329,13 @@ impl From<cli::Opt> for Config {
             cwd_relative_to_repo_root.as_deref(),
         );
 
-        Self {
+        let config = Self {
             available_terminal_width: opt.computed.available_terminal_width,
+            // Filling the background would only leave trailing spaces.
             background_color_extends_to_terminal_width: opt
                 .computed
-                .background_color_extends_to_terminal_width,
+                .background_color_extends_to_terminal_width
+                && !opt.monochrome,
             blame_format: opt.blame_format,
             blame_code_style: styles.remove("blame-code-style"),
             blame_palette,
@@ -247,6 +344,9 @@ impl From<cli::Opt> for Config {
             blame_timestamp_format: opt.blame_timestamp_format,
             blame_timestamp_output_format: opt.blame_timestamp_output_format,
             commit_style: styles["commit-style"],
+            collapse_files: make_collapse_files_glob_set(&opt.collapse_files),
+            collapse_gitattributes: opt.collapse_gitattributes,
+            color_depth: opt.computed.color_depth,
             color_only: opt.color_only,
             commit_regex,
             cwd_of_delta_process,
@@ -258,6 +358,7 @@ impl From<cli::Opt> for Config {
             error_exit_code: 2, // Use 2 for error because diff uses 0 and 1 for non-error.
             file_added_label,
             file_copied_label,
+            file_header_format: opt.file_header_format,
             file_modified_label,
             file_removed_label,
             file_renamed_label,
@@ -265,10 +366,13 @@ impl From<cli::Opt> for Config {
                 .file_regex_replacement
                 .as_deref()
                 .and_then(RegexReplacement::from_sed_command),
+            fold_unchanged_context: opt.fold_unchanged_context,
+            fold_unchanged_label: opt.fold_unchanged_label,
+            fold_unchanged_lines: opt.fold_unchanged_lines,
+            fold_unchanged_style: styles["fold-unchanged-style"],
             right_arrow,
             hunk_label,
             file_style: styles["file-style"],
-            git_config: opt.git_config,
             grep_context_line_style: styles["grep-context-line-style"],
             grep_file_style: styles["grep-file-style"],
             grep_line_number_style: styles["grep-line-number-style"],
@@ -276,7 +380,13 @@ impl From<cli::Opt> for Config {
             grep_match_word_style: styles["grep-match-word-style"],
             grep_separator_symbol: opt.grep_separator_symbol,
             handle_merge_conflicts: !opt.raw,
+            highlight_full_files: opt.highlight_full_files,
+            highlight_full_files_max_size: opt.highlight_full_files_max_size,
+            hunk_header_breadcrumb_separator: opt.hunk_header_breadcrumb_separator,
+            hunk_header_breadcrumb_style: styles["hunk-header-breadcrumb-style"],
+            hunk_header_breadcrumbs: opt.hunk_header_breadcrumbs,
             hunk_header_file_style: styles["hunk-header-file-style"],
+            hunk_header_format: opt.hunk_header_format,
             hunk_header_line_number_style: styles["hunk-header-line-number-style"],
             hunk_header_style: styles["hunk-header-style"],
             hunk_header_style_include_file_path: opt
@@ -291,9 +401,12 @@ impl From<cli::Opt> for Config {
             hyperlinks_commit_link_format: opt.hyperlinks_commit_link_format,
             hyperlinks_file_link_format: opt.hyperlinks_file_link_format,
             inspect_raw_lines: opt.computed.inspect_raw_lines,
+            is_light_mode: opt.computed.is_light_mode,
             inline_hint_style: styles["inline-hint-style"],
             keep_plus_minus_markers: opt.keep_plus_minus_markers,
-            line_fill_method: if !opt.computed.stdout_is_term && !TESTING {
+            line_fill_method: if (!opt.computed.stdout_is_term && !TESTING)
+                || output_format != OutputFormat::Ansi
+            {
                 // Don't write ANSI sequences (which rely on the width of the
                 // current terminal) into a file. Also see UseFullPanelWidth.
                 // But when testing always use given value.
@@ -302,10 +415,7 @@ impl From<cli::Opt> for Config {
                 line_fill_method
             },
             line_numbers: opt.line_numbers && !handlers::hunk::is_word_diff(),
-            line_numbers_format: LeftRight::new(
-                opt.line_numbers_left_format,
-                opt.line_numbers_right_format,
-            ),
+            line_numbers_format,
             line_numbers_style_leftright: LeftRight::new(
                 styles["line-numbers-left-style"],
                 styles["line-numbers-right-style"],
@@ -314,8 +424,10 @@ impl From<cli::Opt> for Config {
                 styles["line-numbers-minus-style"],
                 styles["line-numbers-plus-style"],
             ),
+            line_numbers_unified_format,
             line_numbers_zero_style: styles["line-numbers-zero-style"],
             line_buffer_size: opt.line_buffer_size,
+            markdown_collapse_min_lines: opt.markdown_collapse_min_lines,
             max_line_distance: opt.max_line_distance,
             max_line_distance_for_naively_paired_lines,
             max_line_length: if opt.side_by_side {
@@ -336,12 +448,15 @@ impl From<cli::Opt> for Config {
             minus_file: opt.minus_file,
             minus_non_emph_style: styles["minus-non-emph-style"],
             minus_style: styles["minus-style"],
+            monochrome: opt.monochrome,
             navigate: opt.navigate,
             navigate_regex,
             null_style: Style::new(),
             null_syntect_style: SyntectStyle::default(),
+            output_format,
             pager: opt.pager,
             paging_mode: opt.computed.paging_mode,
+            paint_threads: opt.paint_threads,
             plus_emph_style: styles["plus-emph-style"],
             plus_empty_line_marker_style: styles["plus-empty-line-marker-style"],
             plus_file: opt.plus_file,
@@ -351,20 +466,37 @@ impl From<cli::Opt> for Config {
             git_plus_style: styles["git-plus-style"],
             relative_paths: opt.relative_paths,
             show_themes: opt.show_themes,
-            side_by_side: opt.side_by_side && !handlers::hunk::is_word_diff(),
+            show_whitespace,
+            side_by_side,
             side_by_side_data,
+            side_by_side_mode,
+            side_by_side_ratio,
+            side_by_side_separator: opt.side_by_side_separator,
+            languages_map: make_languages_map(opt.map_languages.as_deref()),
             styles_map,
+            summary: opt.summary,
+            summary_style: styles["summary-style"],
             syntax_dummy_theme: SyntaxTheme::default(),
             syntax_set: opt.computed.syntax_set,
-            syntax_theme: opt.computed.syntax_theme,
+            syntax_theme: if opt.monochrome {
+                None
+            } else {
+                opt.computed.syntax_theme
+            },
             tab_width: opt.tab_width,
+            toc: opt.toc,
+            toc_max_buffer_size: opt.toc_max_buffer_size,
             tokenization_regex,
             true_color: opt.computed.true_color,
             truncation_symbol: format!("{}→{}", ansi::ANSI_SGR_REVERSE, ansi::ANSI_SGR_RESET),
             wrap_config,
+            unicode_warning_style: styles["unicode-warning-style"],
+            unicode_warnings: UnicodeWarnings::from_str(&opt.unicode_warnings),
             whitespace_error_style: styles["whitespace-error-style"],
+            whitespace_style: styles["whitespace-style"],
             zero_style: styles["zero-style"],
-        }
+        };
+        (config, opt.git_config)
     }
 }
 
@@ -385,6 +517,69 @@ fn make_blame_palette(blame_palette: Option<String>, is_light_mode: bool) -> Vec
     }
 }
 
+fn make_collapse_files_glob_set(globs: &str) -> Option<GlobSet> {
+    if globs.trim().is_empty() {
+        return None;
+    }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs.split_whitespace() {
+        builder.add(make_path_glob(glob, "collapse-files"));
+    }
+    Some(
+        builder
+            .build()
+            .unwrap_or_else(|err| fatal(format!("Invalid collapse-files: {err}"))),
+    )
+}
+
+/// Parse --map-languages, e.g. "Jenkinsfile => Groovy, *.inc => PHP".
+fn make_languages_map(map_languages: Option<&str>) -> Vec<(GlobMatcher, String)> {
+    map_languages
+        .unwrap_or_default()
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once("=>") {
+            Some((glob, language)) if !language.trim().is_empty() => (
+                make_path_glob(glob.trim(), "map-languages").compile_matcher(),
+                language.trim().to_string(),
+            ),
+            _ => fatal(format!(
+                "Invalid value for map-languages: {pair}. Expected \"GLOB => LANGUAGE\"."
+            )),
+        })
+        .collect()
+}
+
+fn make_path_glob(glob: &str, option_name: &str) -> Glob {
+    // Like in .gitattributes, a pattern without a slash matches in any directory.
+    let pattern = if glob.contains('/') {
+        glob.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{glob}")
+    };
+    Glob::new(&pattern).unwrap_or_else(|err| fatal(format!("Invalid glob in {option_name}: {err}")))
+}
+
+fn parse_side_by_side_ratio(ratio: &str) -> side_by_side::SideBySideRatio {
+    if ratio == "auto" {
+        return side_by_side::SideBySideRatio::Auto;
+    }
+    match ratio.strip_suffix('%').unwrap_or(ratio).parse::<usize>() {
+        Ok(percent)
+            if (side_by_side::MIN_LEFT_PERCENT..=side_by_side::MAX_LEFT_PERCENT)
+                .contains(&percent) =>
+        {
+            side_by_side::SideBySideRatio::Fixed(percent)
+        }
+        _ => fatal(format!(
+            "Invalid value for side-by-side-ratio: {ratio}. \
+             Expected \"auto\" or a percentage between {}% and {}%.",
+            side_by_side::MIN_LEFT_PERCENT,
+            side_by_side::MAX_LEFT_PERCENT,
+        )),
+    }
+}
+
 /// Did the user supply `option` on the command line?
 pub fn user_supplied_option(option: &str, arg_matches: &clap::ArgMatches) -> bool {
     arg_matches.value_source(option) == Some(ValueSource::CommandLine)
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::BufRead;
+use std::io::ErrorKind;
 use std::io::Write;
 
 use bytelines::ByteLines;
@@ -9,9 +10,17 @@ use crate::ansi;
 use crate::config::delta_unreachable;
 use crate::config::Config;
 use crate::features;
+use crate::git_config::GitConfig;
 use crate::handlers::hunk_header::ParsedHunkHeader;
 use crate::handlers::{self, merge_conflict};
+use crate::output_format::html::HtmlWriter;
+use crate::output_format::json::JsonWriter;
+use crate::output_format::markdown::MarkdownWriter;
+use crate::output_format::monochrome::MonochromeWriter;
+use crate::output_format::svg::SvgWriter;
+use crate::output_format::{OutputFormat, StructuredOutput};
 use crate::paint::Painter;
+use crate::pipeline::{self, Pipeline};
 use crate::style::DecorationStyle;
 use crate::utils;
 
@@ -101,6 +110,7 @@ pub struct StateMachine<'a> {
     pub mode_info: String,
     pub painter: Painter<'a>,
     pub config: &'a Config,
+    pub git_config: Option<&'a GitConfig>,
 
     // When a file is modified, we use lines starting with '---' or '+++' to obtain the file name.
     // When a file is renamed without changes, we use lines starting with 'rename' to obtain the
@@ -110,17 +120,96 @@ pub struct StateMachine<'a> {
     pub current_file_pair: Option<(String, String)>,
     pub handled_diff_header_header_line_file_pair: Option<(String, String)>,
     pub blame_key_colors: HashMap<String, String>,
+    pub deferred_file_header: Option<handlers::diff_header::DeferredFileHeader>,
+    // Unchanged lines at the start of a hunk, held back with their raw lines until the hunk
+    // header is emitted, so that --hunk-header-breadcrumbs can take them into account.
+    pub hunk_header_context: Vec<(String, String)>,
+    // The 1-based index of the current hunk among the hunks of the current file.
+    pub hunk_index: usize,
+    pub file_metadata: handlers::diff_header::FileMetadata,
+    // The blob ids and the repository path of the current file, which is loaded for
+    // --highlight-full-files at its first hunk.
+    pub full_file_to_load: Option<((String, String), String)>,
+    pub summary: Option<handlers::summary::Summary>,
+    pub toc: Option<handlers::toc::TableOfContents>,
 }
 
-pub fn delta<I>(lines: ByteLines<I>, writer: &mut dyn Write, config: &Config) -> std::io::Result<()>
+pub fn delta<I>(
+    lines: ByteLines<I>,
+    writer: &mut dyn Write,
+    config: &Config,
+    git_config: Option<&GitConfig>,
+) -> std::io::Result<()>
 where
     I: BufRead,
 {
-    StateMachine::new(writer, config).consume(lines)
+    match config.output_format {
+        OutputFormat::Ansi if config.monochrome => {
+            let mut writer = MonochromeWriter::new(writer);
+            paint(lines, &mut writer, config, git_config)?;
+            writer.finish()
+        }
+        OutputFormat::Ansi => paint(lines, writer, config, git_config),
+        OutputFormat::Html => {
+            let mut writer = HtmlWriter::new(writer, config)?;
+            paint(lines, &mut writer, config, git_config)?;
+            writer.finish()
+        }
+        OutputFormat::Svg => {
+            let mut writer = SvgWriter::new(writer, config);
+            paint(lines, &mut writer, config, git_config)?;
+            writer.finish()
+        }
+        OutputFormat::Json | OutputFormat::Markdown => {
+            // Painted output is discarded; the structured output is written to `writer`.
+            let structured_output: Box<dyn StructuredOutput> = match config.output_format {
+                OutputFormat::Json => Box::new(JsonWriter::new(writer)),
+                _ => Box::new(MarkdownWriter::new(writer, config)),
+            };
+            let mut sink = std::io::sink();
+            let mut state_machine = StateMachine::new(&mut sink, config, git_config);
+            state_machine.painter.structured_output = Some(structured_output);
+            state_machine.consume(lines)?;
+            match &mut state_machine.painter.structured_output {
+                Some(structured_output) => structured_output.finish(),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// Paint `lines` to `writer`, with the lines of hunks painted on worker threads, unless
+/// --paint-threads is 1.
+fn paint<I>(
+    lines: ByteLines<I>,
+    writer: &mut dyn Write,
+    config: &Config,
+    git_config: Option<&GitConfig>,
+) -> std::io::Result<()>
+where
+    I: BufRead,
+{
+    let n_threads = pipeline::n_paint_threads(config.paint_threads);
+    if n_threads < 2 {
+        return StateMachine::new(writer, config, git_config).consume(lines);
+    }
+    std::thread::scope(move |scope| {
+        // The workers stop when the state machine, and with it the pipeline, is dropped.
+        let mut state_machine = StateMachine::new(writer, config, git_config);
+        state_machine
+            .painter
+            .writer
+            .set_pipeline(Pipeline::start(scope, n_threads, config));
+        state_machine.consume(lines)
+    })
 }
 
 impl<'a> StateMachine<'a> {
-    pub fn new(writer: &'a mut dyn Write, config: &'a Config) -> Self {
+    pub fn new(
+        writer: &'a mut dyn Write,
+        config: &'a Config,
+        git_config: Option<&'a GitConfig>,
+    ) -> Self {
         Self {
             line: "".to_string(),
             raw_line: "".to_string(),
@@ -136,7 +225,19 @@ impl<'a> StateMachine<'a> {
             handled_diff_header_header_line_file_pair: None,
             painter: Painter::new(writer, config),
             config,
+            git_config,
             blame_key_colors: HashMap::new(),
+            deferred_file_header: None,
+            hunk_header_context: Vec::new(),
+            hunk_index: 0,
+            file_metadata: handlers::diff_header::FileMetadata::default(),
+            full_file_to_load: None,
+            summary: if config.summary {
+                Some(handlers::summary::Summary::default())
+            } else {
+                None
+            },
+            toc: None,
         }
     }
 
@@ -144,7 +245,20 @@ impl<'a> StateMachine<'a> {
     where
         I: BufRead,
     {
-        while let Some(Ok(raw_line_bytes)) = lines.next() {
+        loop {
+            let raw_line_bytes = match lines.next() {
+                Some(Ok(raw_line_bytes)) => raw_line_bytes,
+                // The input is slow to come (see utils::input): show what can be shown so far.
+                Some(Err(error)) if error.kind() == ErrorKind::WouldBlock => {
+                    self.painter.flush_painted_lines(false)?;
+                    continue;
+                }
+                Some(Err(error)) if error.kind() == ErrorKind::TimedOut => {
+                    self.painter.flush_painted_lines(true)?;
+                    continue;
+                }
+                _ => break,
+            };
             self.ingest_line(raw_line_bytes);
 
             if self.source == Source::Unknown {
@@ -173,12 +287,20 @@ impl<'a> StateMachine<'a> {
                 || self.handle_grep_line()?
                 || self.should_skip_line()
                 || self.emit_line_unchanged()?;
+            self.check_toc_buffer_size()?;
         }
 
         self.handle_pending_line_with_diff_name()?;
+        if !self.hunk_header_context.is_empty() {
+            self.emit_pending_hunk_header_line()?;
+        }
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
+        self.handle_deferred_file_header_end()?;
+        self.handle_toc_end()?;
         self.painter.emit()?;
-        Ok(())
+        self.write_summary()?;
+        self.painter.writer.write_pending()
     }
 
     fn ingest_line(&mut self, raw_line_bytes: &[u8]) {
@@ -201,7 +323,12 @@ impl<'a> StateMachine<'a> {
         // When a file has \r\n line endings, git sometimes adds ANSI escape sequences between the
         // \r and \n, in which case byte_lines does not remove the \r. Remove it now.
         // TODO: Limit the number of characters we examine when looking for the \r?
-        if let Some(cr_index) = self.raw_line.rfind('\r') {
+        // With --show-whitespace the \r is kept, so that \r\n line endings are shown.
+        if let Some(cr_index) = self
+            .raw_line
+            .rfind('\r')
+            .filter(|_| self.config.show_whitespace.is_none())
+        {
             if ansi::measure_text_width(&self.raw_line[cr_index + 1..]) == 0 {
                 self.raw_line = format!(
                     "{}{}",
@@ -236,11 +363,18 @@ impl<'a> StateMachine<'a> {
 
     /// Emit unchanged any line that delta does not handle.
     pub fn emit_line_unchanged(&mut self) -> std::io::Result<bool> {
+        if let (State::CommitMeta, false, Some(structured_output)) = (
+            &self.state,
+            self.test_commit_meta_header_line(),
+            &mut self.painter.structured_output,
+        ) {
+            structured_output.write_commit_meta_line(&self.line);
+        }
         self.painter.emit()?;
         writeln!(
             self.painter.writer,
             "{}",
-            format_raw_line(&self.raw_line, self.config)
+            format_raw_line(&self.raw_line, self.config, self.git_config)
         )?;
         let handled_line = true;
         Ok(handled_line)
@@ -257,9 +391,15 @@ impl<'a> StateMachine<'a> {
 
 /// If output is going to a tty, emit hyperlinks if requested.
 // Although raw output should basically be emitted unaltered, we do this.
-pub fn format_raw_line<'a>(line: &'a str, config: &Config) -> Cow<'a, str> {
+pub fn format_raw_line<'a>(
+    line: &'a str,
+    config: &Config,
+    git_config: Option<&GitConfig>,
+) -> Cow<'a, str> {
     if config.hyperlinks && atty::is(atty::Stream::Stdout) {
-        features::hyperlinks::format_commit_line_with_osc8_commit_hyperlink(line, config)
+        features::hyperlinks::format_commit_line_with_osc8_commit_hyperlink(
+            line, config, git_config,
+        )
     } else {
         Cow::from(line)
     }
@@ -11,6 +11,8 @@ const DELTA_EXPERIMENTAL_MAX_LINE_DISTANCE_FOR_NAIVELY_PAIRED_LINES: &str =
 const DELTA_PAGER: &str = "DELTA_PAGER";
 const BAT_PAGER: &str = "BAT_PAGER";
 const PAGER: &str = "PAGER";
+const NO_COLOR: &str = "NO_COLOR";
+const TERM: &str = "TERM";
 
 #[derive(Default, Clone)]
 pub struct DeltaEnv {
@@ -22,7 +24,9 @@ pub struct DeltaEnv {
     pub git_config_parameters: Option<String>,
     pub git_prefix: Option<String>,
     pub navigate: Option<String>,
+    pub no_color: Option<String>,
     pub pagers: (Option<String>, Option<String>, Option<String>),
+    pub term: Option<String>,
 }
 
 impl DeltaEnv {
@@ -36,6 +40,8 @@ impl DeltaEnv {
         let git_config_parameters = env::var(GIT_CONFIG_PARAMETERS).ok();
         let git_prefix = env::var(GIT_PREFIX).ok();
         let navigate = env::var(DELTA_NAVIGATE).ok();
+        let no_color = env::var(NO_COLOR).ok();
+        let term = env::var(TERM).ok();
 
         let current_dir = env::current_dir().ok();
         let pagers = (
@@ -53,7 +59,9 @@ impl DeltaEnv {
             git_config_parameters,
             git_prefix,
             navigate,
+            no_color,
             pagers,
+            term,
         }
     }
 }
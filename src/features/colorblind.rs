@@ -0,0 +1,136 @@
+use crate::cli;
+use crate::features::OptionValueFunction;
+
+// Orange for removed lines and blue for added lines, after the Okabe-Ito palette: unlike red and
+// green, these remain distinguishable with deuteranopia and protanopia.
+const DARK_MINUS_COLOR: &str = "#3f2800";
+const DARK_MINUS_EMPH_COLOR: &str = "#6b4300";
+const DARK_PLUS_COLOR: &str = "#002b4d";
+const DARK_PLUS_EMPH_COLOR: &str = "#004a80";
+const DARK_MINUS_LINE_NUMBER_COLOR: &str = "#e69f00";
+const DARK_PLUS_LINE_NUMBER_COLOR: &str = "#56b4e9";
+
+const LIGHT_MINUS_COLOR: &str = "#ffe9cc";
+const LIGHT_MINUS_EMPH_COLOR: &str = "#ffcc80";
+const LIGHT_PLUS_COLOR: &str = "#ddeeff";
+const LIGHT_PLUS_EMPH_COLOR: &str = "#b0d8ff";
+const LIGHT_MINUS_LINE_NUMBER_COLOR: &str = "#a65c00";
+const LIGHT_PLUS_LINE_NUMBER_COLOR: &str = "#0064a0";
+
+/// Blue and orange instead of green and red, for color-blind users.
+pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
+    builtin_feature!([
+        (
+            "colorblind",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "minus-style",
+            String,
+            None,
+            opt => format!("normal {}", color(opt, DARK_MINUS_COLOR, LIGHT_MINUS_COLOR))
+        ),
+        (
+            "minus-emph-style",
+            String,
+            None,
+            opt => format!(
+                "normal {}",
+                color(opt, DARK_MINUS_EMPH_COLOR, LIGHT_MINUS_EMPH_COLOR)
+            )
+        ),
+        (
+            "minus-empty-line-marker-style",
+            String,
+            None,
+            opt => format!("normal {}", color(opt, DARK_MINUS_COLOR, LIGHT_MINUS_COLOR))
+        ),
+        (
+            "plus-style",
+            String,
+            None,
+            opt => format!("syntax {}", color(opt, DARK_PLUS_COLOR, LIGHT_PLUS_COLOR))
+        ),
+        (
+            "plus-emph-style",
+            String,
+            None,
+            opt => format!(
+                "syntax {}",
+                color(opt, DARK_PLUS_EMPH_COLOR, LIGHT_PLUS_EMPH_COLOR)
+            )
+        ),
+        (
+            "plus-empty-line-marker-style",
+            String,
+            None,
+            opt => format!("normal {}", color(opt, DARK_PLUS_COLOR, LIGHT_PLUS_COLOR))
+        ),
+        (
+            "line-numbers-minus-style",
+            String,
+            None,
+            opt => color(opt, DARK_MINUS_LINE_NUMBER_COLOR, LIGHT_MINUS_LINE_NUMBER_COLOR)
+        ),
+        (
+            "line-numbers-plus-style",
+            String,
+            None,
+            opt => color(opt, DARK_PLUS_LINE_NUMBER_COLOR, LIGHT_PLUS_LINE_NUMBER_COLOR)
+        )
+    ])
+}
+
+fn color(opt: &cli::Opt, dark: &str, light: &str) -> String {
+    if opt.computed.is_light_mode {
+        light.to_string()
+    } else {
+        dark.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::minusplus::*;
+    use crate::tests::integration_test_utils;
+
+    #[test]
+    fn test_colorblind() {
+        use super::*;
+        let color = |s: &str| crate::color::parse_color(s, true, None);
+        for (mode, minus, plus, minus_line_number) in [
+            (
+                "--dark",
+                DARK_MINUS_COLOR,
+                DARK_PLUS_COLOR,
+                DARK_MINUS_LINE_NUMBER_COLOR,
+            ),
+            (
+                "--light",
+                LIGHT_MINUS_COLOR,
+                LIGHT_PLUS_COLOR,
+                LIGHT_MINUS_LINE_NUMBER_COLOR,
+            ),
+        ] {
+            let config = integration_test_utils::make_config_from_args(&[
+                mode,
+                "--colorblind",
+                "--line-numbers",
+                "--true-color",
+                "always",
+            ]);
+            assert_eq!(config.minus_style.ansi_term_style.background, color(minus));
+            assert_eq!(config.plus_style.ansi_term_style.background, color(plus));
+            assert!(config.plus_style.is_syntax_highlighted);
+            // The palette replaces the red and green line numbers of line-numbers.
+            assert_eq!(
+                config.line_numbers_style_minusplus[Minus]
+                    .ansi_term_style
+                    .foreground,
+                color(minus_line_number)
+            );
+        }
+    }
+}
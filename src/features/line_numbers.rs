@@ -1,4 +1,5 @@
 use std::cmp::max;
+use std::path::{Path, PathBuf};
 
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -12,7 +13,6 @@ use crate::features::OptionValueFunction;
 use crate::format::{self, Align, Placeholder};
 use crate::minusplus::*;
 use crate::style::Style;
-use crate::utils;
 
 pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
     builtin_feature!([
@@ -112,16 +112,22 @@ pub fn format_and_paint_line_numbers<'a>(
 ) -> Vec<ansi_term::ANSIGenericString<'a, str>> {
     let mut formatted_numbers = Vec::new();
 
-    let (emit_left, emit_right) = match (config.side_by_side, side_by_side_panel) {
-        (false, _) => (true, true),
-        (true, Some(Left)) => (true, false),
-        (true, Some(Right)) => (false, true),
-        (true, None) => unreachable!(),
+    // Without a panel the line is painted in unified layout, which can happen in side-by-side
+    // mode too, see `SideBySideMode::Auto`.
+    let (emit_left, emit_right) = match side_by_side_panel {
+        None => (true, true),
+        Some(Left) => (true, false),
+        Some(Right) => (false, true),
+    };
+    let format_data = match (side_by_side_panel, &line_numbers_data.unified_format_data) {
+        (None, Some(unified_format_data)) => unified_format_data,
+        _ => &line_numbers_data.format_data,
     };
 
     if emit_left {
         formatted_numbers.extend(format_and_paint_line_number_field(
             line_numbers_data,
+            &format_data[Minus],
             Minus,
             &styles,
             &line_numbers,
@@ -132,6 +138,7 @@ pub fn format_and_paint_line_numbers<'a>(
     if emit_right {
         formatted_numbers.extend(format_and_paint_line_number_field(
             line_numbers_data,
+            &format_data[Plus],
             Plus,
             &styles,
             &line_numbers,
@@ -142,15 +149,20 @@ pub fn format_and_paint_line_numbers<'a>(
 }
 
 lazy_static! {
-    static ref LINE_NUMBERS_PLACEHOLDER_REGEX: Regex = format::make_placeholder_regex(&["nm", "np"]);
+    static ref LINE_NUMBERS_PLACEHOLDER_REGEX: Regex =
+        format::make_placeholder_regex(&["nm", "np"]);
 }
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct LineNumbersData<'a> {
     pub format_data: MinusPlus<format::FormatStringData<'a>>,
+    // The formats of lines painted in unified layout in side-by-side mode, if they differ.
+    pub unified_format_data: Option<MinusPlus<format::FormatStringData<'a>>>,
     pub line_number: MinusPlus<usize>,
     pub hunk_max_line_number_width: usize,
     pub plus_file: String,
+    // The absolute path of the plus file, to hyperlink line numbers to.
+    pub plus_file_absolute_path: Option<PathBuf>,
 }
 
 pub type SideBySideLineWidth = MinusPlus<usize>;
@@ -180,8 +192,27 @@ impl<'a> LineNumbersData<'a> {
         }
     }
 
+    /// Use `format` for the lines painted in unified layout in side-by-side mode, see
+    /// `SideBySideMode::Auto`.
+    pub fn with_unified_format_strings(mut self, format: &'a MinusPlus<String>) -> Self {
+        self.unified_format_data = Some(MinusPlus::new(
+            format::parse_line_number_format(&format[Left], &LINE_NUMBERS_PLACEHOLDER_REGEX, false),
+            format::parse_line_number_format(
+                &format[Right],
+                &LINE_NUMBERS_PLACEHOLDER_REGEX,
+                false,
+            ),
+        ));
+        self
+    }
+
     /// Initialize line number data for a hunk.
-    pub fn initialize_hunk(&mut self, line_numbers: &[(usize, usize)], plus_file: String) {
+    pub fn initialize_hunk(
+        &mut self,
+        line_numbers: &[(usize, usize)],
+        plus_file: String,
+        plus_file_absolute_path: Option<PathBuf>,
+    ) {
         // Typically, line_numbers has length 2: an entry for the minus file, and one for the plus
         // file. In the case of merge commits, it may be longer.
         self.line_number =
@@ -190,6 +221,13 @@ impl<'a> LineNumbersData<'a> {
         self.hunk_max_line_number_width =
             1 + (hunk_max_line_number as f64).log10().floor() as usize;
         self.plus_file = plus_file;
+        self.plus_file_absolute_path = plus_file_absolute_path;
+    }
+
+    /// Advance the line numbers past `n` unchanged lines which are not painted.
+    pub fn skip_zero_lines(&mut self, n: usize) {
+        self.line_number[Minus] += n;
+        self.line_number[Plus] += n;
     }
 
     pub fn empty_for_sbs(use_full_width: ansifill::UseFullPanelWidth) -> LineNumbersData<'a> {
@@ -240,6 +278,7 @@ impl<'a> LineNumbersData<'a> {
 #[allow(clippy::too_many_arguments)]
 fn format_and_paint_line_number_field<'a>(
     line_numbers_data: &'a LineNumbersData,
+    format_data: &'a format::FormatStringData<'a>,
     side: MinusPlusIndex,
     styles: &MinusPlus<Style>,
     line_numbers: &MinusPlus<Option<usize>>,
@@ -247,8 +286,10 @@ fn format_and_paint_line_number_field<'a>(
 ) -> Vec<ansi_term::ANSIGenericString<'a, str>> {
     let min_field_width = line_numbers_data.hunk_max_line_number_width;
 
-    let format_data = &line_numbers_data.format_data[side];
-    let plus_file = &line_numbers_data.plus_file;
+    let plus_file = (
+        line_numbers_data.plus_file.as_str(),
+        line_numbers_data.plus_file_absolute_path.as_deref(),
+    );
     let style = &config.line_numbers_style_leftright[side];
 
     let mut ansi_strings = Vec::new();
@@ -299,13 +340,13 @@ fn format_line_number(
     alignment: Align,
     width: usize,
     precision: Option<usize>,
-    plus_file: Option<&str>,
+    plus_file: Option<(&str, Option<&Path>)>,
     config: &config::Config,
 ) -> String {
     let pad = |n| format::pad(n, width, alignment, precision);
     match (line_number, config.hyperlinks, plus_file) {
         (None, _, _) => " ".repeat(width),
-        (Some(n), true, Some(file)) => match utils::path::absolute_path(file, config) {
+        (Some(n), true, Some((file, absolute_path))) => match absolute_path {
             Some(absolute_path) => {
                 hyperlinks::format_osc8_file_hyperlink(absolute_path, line_number, &pad(n), config)
                     .to_string()
@@ -590,31 +631,31 @@ pub mod tests {
         let w = ansifill::UseFullPanelWidth(false);
         let format = MinusPlus::new("".into(), "".into());
         let mut data = LineNumbersData::from_format_strings(&format, w.clone());
-        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into());
+        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into(), None);
         assert_eq!(data.formatted_width(), MinusPlus::new(0, 0));
 
         let format = MinusPlus::new("│".into(), "│+│".into());
         let mut data = LineNumbersData::from_format_strings(&format, w.clone());
 
-        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into());
+        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into(), None);
         assert_eq!(data.formatted_width(), MinusPlus::new(1, 3));
 
         let format = MinusPlus::new("│{nm:^3}│".into(), "│{np:^3}│".into());
         let mut data = LineNumbersData::from_format_strings(&format, w.clone());
 
-        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into());
+        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into(), None);
         assert_eq!(data.formatted_width(), MinusPlus::new(8, 8));
 
         let format = MinusPlus::new("│{nm:^3}│ │{np:<12}│ │{nm}│".into(), "".into());
         let mut data = LineNumbersData::from_format_strings(&format, w.clone());
 
-        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into());
+        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into(), None);
         assert_eq!(data.formatted_width(), MinusPlus::new(32, 0));
 
         let format = MinusPlus::new("│{np:^3}│ │{nm:<12}│ │{np}│".into(), "".into());
         let mut data = LineNumbersData::from_format_strings(&format, w);
 
-        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into());
+        data.initialize_hunk(&[(10, 11), (10000, 100001)], "a".into(), None);
         assert_eq!(data.formatted_width(), MinusPlus::new(32, 0));
     }
 
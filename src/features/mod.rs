@@ -26,6 +26,10 @@ type OptionValueFunction = Box<dyn Fn(&cli::Opt, &Option<GitConfig>) -> Provenan
 // for the option.
 pub fn make_builtin_features() -> HashMap<String, BuiltinFeature> {
     vec![
+        (
+            "colorblind".to_string(),
+            colorblind::make_feature().into_iter().collect(),
+        ),
         (
             "color-only".to_string(),
             color_only::make_feature().into_iter().collect(),
@@ -46,6 +50,10 @@ pub fn make_builtin_features() -> HashMap<String, BuiltinFeature> {
             "line-numbers".to_string(),
             line_numbers::make_feature().into_iter().collect(),
         ),
+        (
+            "monochrome".to_string(),
+            monochrome::make_feature().into_iter().collect(),
+        ),
         (
             "navigate".to_string(),
             navigate::make_feature().into_iter().collect(),
@@ -55,6 +63,10 @@ pub fn make_builtin_features() -> HashMap<String, BuiltinFeature> {
             "side-by-side".to_string(),
             side_by_side::make_feature().into_iter().collect(),
         ),
+        (
+            "syntax-theme-colors".to_string(),
+            syntax_theme_colors::make_feature().into_iter().collect(),
+        ),
     ]
     .into_iter()
     .collect()
@@ -80,13 +92,16 @@ macro_rules! builtin_feature {
 }
 
 pub mod color_only;
+pub mod colorblind;
 pub mod diff_highlight;
 pub mod diff_so_fancy;
 pub mod hyperlinks;
 pub mod line_numbers;
+pub mod monochrome;
 pub mod navigate;
 pub mod raw;
 pub mod side_by_side;
+pub mod syntax_theme_colors;
 
 #[cfg(test)]
 pub mod tests {
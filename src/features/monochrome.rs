@@ -0,0 +1,178 @@
+use crate::features::OptionValueFunction;
+
+/// ASCII replacements for the symbols used by default, and the +/- markers which show the kind
+/// of each line without colors.
+pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
+    builtin_feature!([
+        (
+            "monochrome",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "blame-separator-format",
+            String,
+            None,
+            _opt => "|{n:^4}|"
+        ),
+        (
+            "fold-unchanged-label",
+            String,
+            None,
+            _opt => "..."
+        ),
+        (
+            "hunk-header-breadcrumb-separator",
+            String,
+            None,
+            _opt => " > "
+        ),
+        (
+            "keep-plus-minus-markers",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "line-numbers-left-format",
+            String,
+            None,
+            _opt => "{nm:^4}:"
+        ),
+        (
+            "line-numbers-right-format",
+            String,
+            None,
+            _opt => "{np:^4}|"
+        ),
+        (
+            "merge-conflict-begin-symbol",
+            String,
+            None,
+            _opt => "v"
+        ),
+        (
+            "merge-conflict-end-symbol",
+            String,
+            None,
+            _opt => "^"
+        ),
+        (
+            "right-arrow",
+            String,
+            None,
+            _opt => "->  "
+        ),
+        (
+            "whitespace-cr-symbol",
+            String,
+            None,
+            _opt => "<"
+        ),
+        (
+            "whitespace-invisible-symbol",
+            String,
+            None,
+            _opt => "?"
+        ),
+        (
+            "whitespace-nbsp-symbol",
+            String,
+            None,
+            _opt => "_"
+        ),
+        (
+            "whitespace-space-symbol",
+            String,
+            None,
+            _opt => "."
+        ),
+        (
+            "whitespace-tab-symbol",
+            String,
+            None,
+            _opt => ">"
+        ),
+        (
+            "wrap-left-symbol",
+            String,
+            None,
+            _opt => "\\"
+        ),
+        (
+            "wrap-right-prefix-symbol",
+            String,
+            None,
+            _opt => "."
+        ),
+        (
+            "wrap-right-symbol",
+            String,
+            None,
+            _opt => "\\"
+        )
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli;
+    use crate::env::DeltaEnv;
+    use crate::tests::integration_test_utils::DeltaTest;
+
+    #[test]
+    fn test_monochrome() {
+        let output = DeltaTest::with_args(&["--monochrome"])
+            .with_input(DIFF)
+            .output;
+        assert!(output.is_ascii());
+        assert!(!output.contains('\x1b'));
+        assert!(output.contains("\na.py\n-----"));
+        assert!(output.contains("\n-x = [-1-]\n"));
+        assert!(output.contains("\n-z = 3\n"));
+        assert!(output.contains("\n+x = {+2+}\n"));
+        assert!(output.contains("\n y = 1\n"));
+        assert!(output.contains("renamed: b.py ->   c.py\n"));
+    }
+
+    #[test]
+    fn test_no_color_env_var_activates_monochrome() {
+        let env = DeltaEnv {
+            no_color: Some("1".to_string()),
+            ..DeltaEnv::default()
+        };
+        let opt = cli::Opt::from_iter_and_git_config(env, vec!["delta".to_string()], None);
+        assert!(opt.monochrome);
+        let opt = cli::Opt::from_iter_and_git_config(
+            DeltaEnv {
+                no_color: Some("".to_string()),
+                ..DeltaEnv::default()
+            },
+            vec!["delta".to_string()],
+            None,
+        );
+        assert!(!opt.monochrome);
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,3 +1,2 @@ def f():
+-x = 1
+-z = 3
++x = 2
+ y = 1
+diff --git a/b.py b/c.py
+similarity index 90%
+rename from b.py
+rename to c.py
+--- a/b.py
++++ b/c.py
+@@ -1 +1 @@
+-b = 1
++c = 1
+";
+}
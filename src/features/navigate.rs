@@ -37,6 +37,7 @@ pub fn make_navigate_regex(
     file_removed_label: &str,
     file_renamed_label: &str,
     hunk_label: &str,
+    fold_unchanged_label: &str,
 ) -> String {
     if show_themes {
         "^Theme:".to_string()
@@ -49,12 +50,13 @@ pub fn make_navigate_regex(
             }
         };
         format!(
-            "^(commit{}{}{}{}{})",
+            "^(commit{}{}{}{}{}{})",
             optional_regexp(file_added_label),
             optional_regexp(file_removed_label),
             optional_regexp(file_renamed_label),
             optional_regexp(file_modified_label),
             optional_regexp(hunk_label),
+            optional_regexp(fold_unchanged_label),
         )
     }
 }
@@ -12,6 +12,7 @@ use crate::features::{line_numbers, OptionValueFunction};
 use crate::minusplus::*;
 use crate::paint::{BgFillMethod, BgShouldFill, LineSections, Painter};
 use crate::style::Style;
+use crate::whitespace;
 use crate::wrapping::{wrap_minusplus_block, wrap_zero_block};
 
 pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
@@ -23,11 +24,29 @@ pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
             _opt => true
         ),
         ("features", bool, None, _opt => "line-numbers"),
-        ("line-numbers-left-format", String, None, _opt => "│{nm:^4}│".to_string()),
-        ("line-numbers-right-format", String, None, _opt => "│{np:^4}│".to_string())
+        ("line-numbers-left-format", String, None, _opt => LINE_NUMBERS_FORMAT[0].to_string()),
+        ("line-numbers-right-format", String, None, _opt => LINE_NUMBERS_FORMAT[1].to_string())
     ])
 }
 
+// The line number formats of the side-by-side feature, which show the borders of the panels, and
+// the default formats of unified layout, used in their place when lines are painted in unified
+// layout (see `SideBySideMode::Auto`).
+const LINE_NUMBERS_FORMAT: [&str; 2] = ["│{nm:^4}│", "│{np:^4}│"];
+const UNIFIED_LINE_NUMBERS_FORMAT: [&str; 2] = ["{nm:^4}⋮", "{np:^4}│"];
+
+/// The line number formats to use in place of `format` when lines are painted in unified layout.
+pub fn unified_line_numbers_format(format: &LeftRight<String>) -> LeftRight<String> {
+    let unified_format = |side: PanelSide, i: usize| {
+        if format[side] == LINE_NUMBERS_FORMAT[i] {
+            UNIFIED_LINE_NUMBERS_FORMAT[i].to_string()
+        } else {
+            format[side].clone()
+        }
+    };
+    LeftRight::new(unified_format(Left, 0), unified_format(Right, 1))
+}
+
 // Aliases for Minus/Plus because Left/Right and PanelSide makes
 // more sense in a side-by-side context.
 pub use crate::minusplus::MinusPlusIndex as PanelSide;
@@ -45,19 +64,88 @@ pub type LeftRight<T> = MinusPlus<T>;
 
 pub type SideBySideData = LeftRight<Panel>;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SideBySideMode {
+    /// Always use side-by-side layout when it is enabled.
+    Always,
+    /// Use side-by-side layout only when the panels are wide enough, see
+    /// [`panels_fit_min_code_width`] and [`has_very_long_lines`].
+    Auto,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SideBySideRatio {
+    /// The left panel takes up this percentage of the width.
+    Fixed(usize),
+    /// The split is derived from the widths of the minus and plus lines, see
+    /// [`adaptive_left_percent`].
+    Auto,
+}
+
+/// Bounds of the left panel percentage, both for fixed and adaptive ratios.
+pub const MIN_LEFT_PERCENT: usize = 10;
+pub const MAX_LEFT_PERCENT: usize = 90;
+/// Bounds of the left panel percentage chosen by [`adaptive_left_percent`].
+const MIN_ADAPTIVE_LEFT_PERCENT: usize = 20;
+const MAX_ADAPTIVE_LEFT_PERCENT: usize = 80;
+
 impl SideBySideData {
-    /// Create a [`LeftRight<Panel>`](LeftRight<Panel>) named [`SideBySideData`].
-    pub fn new_sbs(decorations_width: &cli::Width, available_terminal_width: &usize) -> Self {
-        let panel_width = match decorations_width {
-            cli::Width::Fixed(w) => w / 2,
-            _ => available_terminal_width / 2,
-        };
-        SideBySideData::new(Panel { width: panel_width }, Panel { width: panel_width })
+    /// Create a [`LeftRight<Panel>`](LeftRight<Panel>) named [`SideBySideData`]. The left
+    /// panel gets `left_percent` of the width remaining after the separator.
+    pub fn new_sbs(
+        decorations_width: &cli::Width,
+        available_terminal_width: &usize,
+        left_percent: usize,
+        separator_width: usize,
+    ) -> Self {
+        let width = match decorations_width {
+            cli::Width::Fixed(w) => *w,
+            _ => *available_terminal_width,
+        }
+        .saturating_sub(separator_width);
+        // Like for an even split the panels sum up to an even number, see `UseFullPanelWidth`.
+        let left_width = width * left_percent / 100;
+        let right_width = (width / 2 * 2).saturating_sub(left_width);
+        SideBySideData::new(Panel { width: left_width }, Panel { width: right_width })
+    }
+
+    /// Create the panels for the given percentage, taking the configured separator and the
+    /// odd width fix into account.
+    pub fn for_left_percent(left_percent: usize, config: &Config) -> Self {
+        ansifill::UseFullPanelWidth::sbs_odd_fix(
+            &config.decorations_width,
+            &config.line_fill_method,
+            config.side_by_side_separator.width(),
+            Self::new_sbs(
+                &config.decorations_width,
+                &config.available_terminal_width,
+                left_percent,
+                config.side_by_side_separator.width(),
+            ),
+        )
+    }
+}
+
+/// Return the percentage of the width the left panel should get so that the widest minus
+/// and plus lines have space in proportion to their width.
+pub fn adaptive_left_percent(lines: &LeftRight<&[(String, State)]>, config: &Config) -> usize {
+    let max_width = |side: PanelSide| {
+        lines[side]
+            .iter()
+            .map(|(line, _)| whitespace::display_width(line.trim_end(), config))
+            .max()
+            .unwrap_or(0)
+    };
+    let (left, right) = (max_width(Left), max_width(Right));
+    if left + right == 0 {
+        return 50;
     }
+    (100 * left / (left + right)).clamp(MIN_ADAPTIVE_LEFT_PERCENT, MAX_ADAPTIVE_LEFT_PERCENT)
 }
 
 pub fn available_line_width(
     config: &Config,
+    panels: &SideBySideData,
     data: &line_numbers::LineNumbersData,
 ) -> line_numbers::SideBySideLineWidth {
     let line_numbers_width = data.formatted_width();
@@ -65,7 +153,7 @@ pub fn available_line_width(
     // The width can be reduced by the line numbers and/or
     // a possibly added/restored 1-wide "+/-/ " prefix.
     let line_width = |side: PanelSide| {
-        config.side_by_side_data[side]
+        panels[side]
             .width
             .saturating_sub(line_numbers_width[side])
             .saturating_sub(config.keep_plus_minus_markers as usize)
@@ -74,28 +162,75 @@ pub fn available_line_width(
     LeftRight::new(line_width(Left), line_width(Right))
 }
 
-pub fn line_is_too_long(line: &str, line_width: usize) -> bool {
+/// Return whether each panel leaves at least `min_code_width` columns for code once the
+/// line numbers (if enabled) and the +/- marker (if kept) have been subtracted.
+pub fn panels_fit_min_code_width(
+    side_by_side_data: &SideBySideData,
+    line_numbers_format: Option<&LeftRight<String>>,
+    keep_plus_minus_markers: bool,
+    min_code_width: usize,
+) -> bool {
+    let line_numbers_width = match line_numbers_format {
+        Some(format) => {
+            LineNumbersData::from_format_strings(format, ansifill::UseFullPanelWidth(false))
+                .formatted_width()
+        }
+        None => LeftRight::default(),
+    };
+    [Left, Right].iter().all(|&side| {
+        side_by_side_data[side]
+            .width
+            .saturating_sub(line_numbers_width[side])
+            .saturating_sub(keep_plus_minus_markers as usize)
+            >= min_code_width
+    })
+}
+
+/// Return whether any of the input lines would still be truncated in its panel after
+/// wrapping to at most `wrap-max-lines` lines. Always false if wrapping is unlimited.
+pub fn has_very_long_lines(
+    lines: &LeftRight<&[(String, State)]>,
+    panels: &SideBySideData,
+    line_numbers_data: &LineNumbersData,
+    config: &Config,
+) -> bool {
+    let max_lines = config.wrap_config.max_lines;
+    if max_lines == 0 {
+        return false;
+    }
+    // Every wrapped line but the last one ends in a 1-wide wrap symbol.
+    let line_width = available_line_width(config, panels, line_numbers_data);
+    let capacity = |side: PanelSide| (max_lines * line_width[side]).saturating_sub(max_lines - 1);
+    has_long_lines(
+        lines,
+        &LeftRight::new(capacity(Left), capacity(Right)),
+        config,
+    )
+    .0
+}
+
+pub fn line_is_too_long(line: &str, line_width: usize, config: &Config) -> bool {
     debug_assert!(line.ends_with('\n'));
 
     // graphemes will take care of newlines
-    line.width() > line_width
+    whitespace::display_width(line, config) > line_width
 }
 
 /// Return whether any of the input lines is too long, and a data
 /// structure indicating which of the input lines are too long. This avoids
 /// recalculating the length later.
 pub fn has_long_lines(
-    lines: &LeftRight<&Vec<(String, State)>>,
+    lines: &LeftRight<&[(String, State)]>,
     line_width: &line_numbers::SideBySideLineWidth,
+    config: &Config,
 ) -> (bool, LeftRight<Vec<bool>>) {
     let mut wrap_any = LeftRight::default();
     let mut wrapping_lines = LeftRight::default();
 
     let mut check_if_too_long = |side| {
-        let lines_side: &[(String, State)] = lines[side];
-        wrapping_lines[side] = lines_side
+        wrapping_lines[side] = lines[side]
             .iter()
-            .map(|(line, _)| line_is_too_long(line, line_width[side]))
+            .map(|(line, _)| line_is_too_long(line, line_width[side], config))
             .inspect(|b| wrap_any[side] |= b)
             .collect();
     };
@@ -115,6 +250,7 @@ pub fn paint_minus_and_plus_lines_side_by_side(
     line_alignment: Vec<(Option<usize>, Option<usize>)>,
     line_numbers_data: &mut Option<LineNumbersData>,
     output_buffer: &mut String,
+    panels: &SideBySideData,
     config: &config::Config,
 ) {
     let line_states = LeftRight::new(
@@ -144,9 +280,13 @@ pub fn paint_minus_and_plus_lines_side_by_side(
         if config.wrap_config.max_lines == 1 {
             (false, LeftRight::default(), LeftRight::default())
         } else {
-            let line_width = available_line_width(config, line_numbers_data);
+            let line_width = available_line_width(config, panels, line_numbers_data);
 
-            let (should_wrap, long_lines) = has_long_lines(&lines, &line_width);
+            let (should_wrap, long_lines) = has_long_lines(
+                &LeftRight::new(&lines[Left][..], &lines[Right][..]),
+                &line_width,
+                config,
+            );
 
             (should_wrap, line_width, long_lines)
         }
@@ -184,8 +324,10 @@ pub fn paint_minus_and_plus_lines_side_by_side(
             left_state,
             &mut Some(line_numbers_data),
             bg_should_fill[Left],
+            panels[Left].width,
             config,
         ));
+        output_buffer.push_str(&config.side_by_side_separator);
 
         let right_state = match plus_line_index {
             Some(i) => &line_states[Right][i],
@@ -199,6 +341,7 @@ pub fn paint_minus_and_plus_lines_side_by_side(
             right_state,
             &mut Some(line_numbers_data),
             bg_should_fill[Right],
+            panels[Right].width,
             config,
         ));
         output_buffer.push('\n');
@@ -227,6 +370,7 @@ pub fn paint_zero_lines_side_by_side<'a>(
     syntax_style_sections: Vec<LineSections<'a, SyntectStyle>>,
     diff_style_sections: Vec<LineSections<'a, Style>>,
     output_buffer: &mut String,
+    panels: &SideBySideData,
     config: &Config,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
     painted_prefix: Option<ansi_term::ANSIString>,
@@ -236,6 +380,7 @@ pub fn paint_zero_lines_side_by_side<'a>(
 
     let (states, syntax_style_sections, diff_style_sections) = wrap_zero_block(
         config,
+        panels,
         line,
         states,
         syntax_style_sections,
@@ -267,10 +412,14 @@ pub fn paint_zero_lines_side_by_side<'a>(
                 None,
                 &state,
                 *panel_side,
+                panels[*panel_side].width,
                 background_color_extends_to_terminal_width,
                 config,
             );
             output_buffer.push_str(&panel_line);
+            if *panel_side == Left {
+                output_buffer.push_str(&config.side_by_side_separator);
+            }
         }
         output_buffer.push('\n');
     }
@@ -285,6 +434,7 @@ fn paint_left_panel_minus_line<'a>(
     state: &'a State,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
     background_color_extends_to_terminal_width: BgShouldFill,
+    panel_width: usize,
     config: &Config,
 ) -> String {
     let (mut panel_line, panel_line_is_empty) = paint_minus_or_plus_panel_line(
@@ -304,6 +454,7 @@ fn paint_left_panel_minus_line<'a>(
         Some(lines_have_homolog),
         state,
         Left,
+        panel_width,
         background_color_extends_to_terminal_width,
         config,
     );
@@ -320,6 +471,7 @@ fn paint_right_panel_plus_line<'a>(
     state: &'a State,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
     background_color_extends_to_terminal_width: BgShouldFill,
+    panel_width: usize,
     config: &Config,
 ) -> String {
     let (mut panel_line, panel_line_is_empty) = paint_minus_or_plus_panel_line(
@@ -340,6 +492,7 @@ fn paint_right_panel_plus_line<'a>(
         Some(lines_have_homolog),
         state,
         Right,
+        panel_width,
         background_color_extends_to_terminal_width,
         config,
     );
@@ -476,6 +629,7 @@ fn pad_panel_line_to_width(
     lines_have_homolog: Option<&[bool]>,
     state: &State,
     panel_side: PanelSide,
+    panel_width: usize,
     background_color_extends_to_terminal_width: BgShouldFill,
     config: &Config,
 ) {
@@ -500,7 +654,6 @@ fn pad_panel_line_to_width(
     };
 
     let text_width = ansi::measure_text_width(panel_line);
-    let panel_width = config.side_by_side_data[panel_side].width;
 
     if text_width > panel_width {
         *panel_line =
@@ -534,6 +687,8 @@ fn pad_panel_line_to_width(
 }
 
 pub mod ansifill {
+    use unicode_width::UnicodeWidthStr;
+
     use super::SideBySideData;
     use crate::config::Config;
     use crate::paint::BgFillMethod;
@@ -560,15 +715,20 @@ pub mod ansifill {
         pub fn new(config: &Config) -> Self {
             Self(
                 config.side_by_side
-                    && Self::is_odd_with_ansi(&config.decorations_width, &config.line_fill_method),
+                    && Self::is_odd_with_ansi(
+                        &config.decorations_width,
+                        &config.line_fill_method,
+                        config.side_by_side_separator.width(),
+                    ),
             )
         }
         pub fn sbs_odd_fix(
             width: &crate::cli::Width,
             method: &BgFillMethod,
+            separator_width: usize,
             sbs_data: SideBySideData,
         ) -> SideBySideData {
-            if Self::is_odd_with_ansi(width, method) {
+            if Self::is_odd_with_ansi(width, method, separator_width) {
                 Self::adapt_sbs_data(sbs_data)
             } else {
                 sbs_data
@@ -577,9 +737,16 @@ pub mod ansifill {
         pub fn pad_width(&self) -> bool {
             self.0
         }
-        fn is_odd_with_ansi(width: &crate::cli::Width, method: &BgFillMethod) -> bool {
+        // The separator between the panels takes up columns as well, so what matters is
+        // whether the width without it is odd.
+        fn is_odd_with_ansi(
+            width: &crate::cli::Width,
+            method: &BgFillMethod,
+            separator_width: usize,
+        ) -> bool {
             method == &BgFillMethod::TryAnsiSequence
-                && matches!(&width, crate::cli::Width::Fixed(width) if width % 2 == 1)
+                && matches!(&width, crate::cli::Width::Fixed(width)
+                            if width.saturating_sub(separator_width) % 2 == 1)
         }
         fn adapt_sbs_data(mut sbs_data: SideBySideData) -> SideBySideData {
             sbs_data[super::Right].width += 1;
@@ -590,6 +757,7 @@ pub mod ansifill {
 
 #[cfg(test)]
 pub mod tests {
+    use super::{Left, Right};
     use crate::ansi::strip_ansi_codes;
     use crate::features::line_numbers::tests::*;
     use crate::tests::integration_test_utils::{make_config_from_args, run_delta, DeltaTest};
@@ -767,4 +935,171 @@ pub mod tests {
             │    │三 │    │"#,
         );
     }
+
+    #[test]
+    fn test_side_by_side_mode_auto_depends_on_width() {
+        let args = ["--side-by-side", "--side-by-side-mode=auto", "--width"];
+        let config = make_config_from_args(&[&args[..], &["100"]].concat());
+        assert!(!config.side_by_side);
+        assert_eq!(config.line_numbers_format[Left], "{nm:^4}⋮");
+        let config = make_config_from_args(&[&args[..], &["160"]].concat());
+        assert!(config.side_by_side);
+        let config = make_config_from_args(
+            &[&args[..], &["100", "--side-by-side-min-code-width", "40"]].concat(),
+        );
+        assert!(config.side_by_side);
+        let config = make_config_from_args(&["--side-by-side", "--width", "100"]);
+        assert!(config.side_by_side);
+    }
+
+    #[test]
+    fn test_side_by_side_mode_auto_falls_back_on_long_lines() {
+        DeltaTest::with_args(&[
+            "--side-by-side",
+            "--side-by-side-mode=auto",
+            "--side-by-side-min-code-width=4",
+            "--wrap-max-lines=0",
+            "--width",
+            "24",
+            "--line-fill-method=spaces",
+        ])
+        .with_input(TWO_PLUS_LINES_DIFF)
+        .expect_after_header(
+            r#"
+            #indent_mark
+                ⋮  1 │a = 1
+                ⋮  2 │b = 234567"#,
+        );
+    }
+
+    #[test]
+    fn test_side_by_side_mode_auto_chooses_the_layout_of_each_hunk() {
+        DeltaTest::with_args(&[
+            "--side-by-side",
+            "--side-by-side-mode=auto",
+            "--side-by-side-min-code-width=4",
+            "--wrap-max-lines=0",
+            "--width",
+            "24",
+            "--line-fill-method=spaces",
+            "--hunk-header-style=omit",
+        ])
+        .with_input(
+            "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+ a = 1
+-b = 2
++b = 234567
+@@ -10,2 +10,2 @@
+ c = 1
+-d = 2
++d = 3
+",
+        )
+        .expect_after_skip(
+            4,
+            r#"
+            #indent_mark
+              1 ⋮  1 │a = 1
+              2 ⋮    │b = 2
+                ⋮  2 │b = 234567
+            
+            │ 10 │c = 1 │ 10 │c = 1
+            │ 11 │d = 2 │ 11 │d = 3 "#,
+        );
+    }
+
+    #[test]
+    fn test_side_by_side_ratio_and_separator() {
+        let config = make_config_from_args(&["--side-by-side", "--width", "40"]);
+        assert_eq!(config.side_by_side_data[Left].width, 20);
+        assert_eq!(config.side_by_side_data[Right].width, 20);
+        let config = make_config_from_args(&[
+            "--side-by-side",
+            "--width",
+            "40",
+            "--side-by-side-ratio=30%",
+            "--side-by-side-separator= ",
+        ]);
+        assert_eq!(config.side_by_side_data[Left].width, 11);
+        // 38 plus one column to fill the odd width, see `UseFullPanelWidth`.
+        assert_eq!(config.side_by_side_data[Right].width, 28);
+
+        DeltaTest::with_args(&[
+            "--side-by-side",
+            "--width",
+            "40",
+            "--side-by-side-ratio=40%",
+            "--side-by-side-separator=|",
+            "--line-fill-method=spaces",
+        ])
+        .with_input(ONE_MINUS_ONE_PLUS_LINE_DIFF)
+        .expect_after_header(
+            r#"
+            │  1 │a = 1    |│  1 │a = 1
+            │  2 │b = 2    |│  2 │bb = 2           "#,
+        );
+    }
+
+    #[test]
+    fn test_side_by_side_ratio_auto() {
+        DeltaTest::with_args(&[
+            "--side-by-side",
+            "--width",
+            "40",
+            "--side-by-side-ratio=auto",
+            "--line-fill-method=spaces",
+        ])
+        .with_input(TWO_PLUS_LINES_DIFF)
+        .expect_after_header(
+            r#"
+            │    │  │  1 │a = 1                     
+            │    │  │  2 │b = 234567                "#,
+        );
+    }
+
+    #[test]
+    fn test_side_by_side_ratio_auto_is_the_same_for_the_whole_hunk() {
+        // The split is chosen from the first block, which has a long added line, and kept for the
+        // second one, which has a long removed line.
+        DeltaTest::with_args(&[
+            "--side-by-side",
+            "--width",
+            "40",
+            "--side-by-side-ratio=auto",
+            "--line-fill-method=spaces",
+        ])
+        .with_input(
+            "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,5 +1,5 @@
+-a = 1
++a = 1234567890
+ b = 2
+ c = 3
+-d = 1234
++d = 1
+",
+        )
+        .expect_after_header(
+            r#"
+            │  1 │a =↵│    │
+            │    │ 1  │    │
+            │    │    │  1 │a = 1234567890          
+            │  2 │b =↵│  2 │b =↵
+            │    │ 2  │    │ 2
+            │  3 │c =↵│  3 │c =↵
+            │    │ 3  │    │ 3
+            │  4 │d =↵│  4 │d = 1                   
+            │    │ 12↵│    │
+            │    │34  │    │"#,
+        );
+    }
 }
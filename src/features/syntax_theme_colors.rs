@@ -0,0 +1,198 @@
+use crate::cli;
+use crate::features::OptionValueFunction;
+use crate::options::theme::SyntaxThemeColors;
+
+/// Styles using the colors derived from the syntax theme, or delta's default styles if syntax
+/// highlighting is disabled.
+pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
+    builtin_feature!([
+        (
+            "syntax-theme-colors",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "minus-style",
+            String,
+            None,
+            opt => derived_style(opt, "normal auto", |c| format!("normal {}", c.minus_background))
+        ),
+        (
+            "minus-emph-style",
+            String,
+            None,
+            opt => derived_style(opt, "normal auto", |c| {
+                format!("normal {}", c.minus_emph_background)
+            })
+        ),
+        (
+            "minus-empty-line-marker-style",
+            String,
+            None,
+            opt => derived_style(opt, "normal auto", |c| format!("normal {}", c.minus_background))
+        ),
+        (
+            "plus-style",
+            String,
+            None,
+            opt => derived_style(opt, "syntax auto", |c| format!("syntax {}", c.plus_background))
+        ),
+        (
+            "plus-emph-style",
+            String,
+            None,
+            opt => derived_style(opt, "syntax auto", |c| {
+                format!("syntax {}", c.plus_emph_background)
+            })
+        ),
+        (
+            "plus-empty-line-marker-style",
+            String,
+            None,
+            opt => derived_style(opt, "normal auto", |c| format!("normal {}", c.plus_background))
+        ),
+        (
+            "line-numbers-minus-style",
+            String,
+            None,
+            opt => derived_style(opt, "auto", |c| c.minus_foreground.clone())
+        ),
+        (
+            "line-numbers-zero-style",
+            String,
+            None,
+            opt => derived_style(opt, "auto", |c| c.gutter_foreground.clone())
+        ),
+        (
+            "line-numbers-plus-style",
+            String,
+            None,
+            opt => derived_style(opt, "auto", |c| c.plus_foreground.clone())
+        ),
+        (
+            "line-numbers-left-style",
+            String,
+            None,
+            opt => derived_style(opt, "auto", |c| c.gutter_foreground.clone())
+        ),
+        (
+            "line-numbers-right-style",
+            String,
+            None,
+            opt => derived_style(opt, "auto", |c| c.gutter_foreground.clone())
+        ),
+        (
+            "file-style",
+            String,
+            None,
+            opt => derived_style(opt, "blue", |c| c.accent.clone())
+        ),
+        (
+            "file-decoration-style",
+            String,
+            None,
+            opt => derived_style(opt, "blue ul", |c| format!("{} ul", c.accent))
+        ),
+        (
+            "hunk-header-decoration-style",
+            String,
+            None,
+            opt => derived_style(opt, "blue box", |c| format!("{} box", c.accent))
+        ),
+        (
+            "hunk-header-file-style",
+            String,
+            None,
+            opt => derived_style(opt, "blue", |c| c.accent.clone())
+        ),
+        (
+            "hunk-header-line-number-style",
+            String,
+            None,
+            opt => derived_style(opt, "blue", |c| c.accent.clone())
+        )
+    ])
+}
+
+fn derived_style(
+    opt: &cli::Opt,
+    default: &str,
+    style: impl Fn(&SyntaxThemeColors) -> String,
+) -> String {
+    match &opt.computed.syntax_theme_colors {
+        Some(colors) => style(colors),
+        None => default.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::theme::derive_syntax_theme_colors;
+    use crate::tests::integration_test_utils;
+
+    #[test]
+    fn test_syntax_theme_colors() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--syntax-theme-colors",
+            "--syntax-theme",
+            "GitHub",
+            "--true-color",
+            "always",
+            "--line-numbers",
+            "--plus-emph-style",
+            "bold",
+        ]);
+        let colors = derive_syntax_theme_colors(config.syntax_theme.as_ref().unwrap());
+        let color = |s: &str| crate::color::parse_color(s, true, None);
+        assert_eq!(
+            config.minus_style.ansi_term_style.background,
+            color(&colors.minus_background)
+        );
+        assert_eq!(
+            config.minus_emph_style.ansi_term_style.background,
+            color(&colors.minus_emph_background)
+        );
+        assert_eq!(
+            config.plus_style.ansi_term_style.background,
+            color(&colors.plus_background)
+        );
+        assert_eq!(
+            config.line_numbers_style_minusplus[crate::minusplus::Minus]
+                .ansi_term_style
+                .foreground,
+            color(&colors.minus_foreground)
+        );
+        // Styles set explicitly are not replaced.
+        assert_eq!(config.plus_emph_style.ansi_term_style.background, None);
+        assert!(config.plus_emph_style.ansi_term_style.is_bold);
+    }
+
+    #[test]
+    fn test_syntax_theme_colors_are_shades_of_the_background() {
+        let config = integration_test_utils::make_config_from_args(&["--syntax-theme", "GitHub"]);
+        let colors = derive_syntax_theme_colors(config.syntax_theme.as_ref().unwrap());
+        let rgb = |s: &str| {
+            let n = u32::from_str_radix(&s[1..], 16).unwrap();
+            (n >> 16, (n >> 8) & 0xff, n & 0xff)
+        };
+        // The GitHub theme has a white background: the backgrounds of removed and added lines
+        // are light tints of red and green, and emphasis is darker.
+        let (r, g, b) = rgb(&colors.minus_background);
+        assert!(r > 0xe0 && r > g && r > b);
+        let (r, g, b) = rgb(&colors.plus_background);
+        assert!(g > 0xc0 && g > r && g > b);
+        let lightness = |s: &str| {
+            let (r, g, b) = rgb(s);
+            r + g + b
+        };
+        assert!(lightness(&colors.minus_emph_background) < lightness(&colors.minus_background));
+        assert!(lightness(&colors.plus_emph_background) < lightness(&colors.plus_background));
+
+        let config =
+            integration_test_utils::make_config_from_args(&["--syntax-theme", "Monokai Extended"]);
+        let colors = derive_syntax_theme_colors(config.syntax_theme.as_ref().unwrap());
+        assert!(lightness(&colors.minus_emph_background) > lightness(&colors.minus_background));
+        assert!(lightness(&colors.minus_background) < 0x180);
+    }
+}
@@ -6,7 +6,7 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::features::side_by_side::ansifill::ODD_PAD_CHAR;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Placeholder<'a> {
     NumberMinus,
     NumberPlus,
@@ -0,0 +1,366 @@
+// Syntax highlighting of hunk lines looked up in the highlighting of the full files (see
+// --highlight-full-files).
+//
+// Highlighting each hunk from its first line goes wrong when the hunk starts inside a multi-line
+// string or comment. Instead, both versions of the file are loaded from the git repository at the
+// first hunk of the file and highlighted once from the start when it is painted, and the lines of
+// each hunk are found by their line numbers.
+// Whenever the lines of the diff do not match the loaded files, delta falls back to highlighting
+// the hunk by itself.
+
+use std::sync::{Arc, OnceLock};
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::Style as SyntectStyle;
+use syntect::parsing::SyntaxReference;
+
+use crate::config::Config;
+use crate::delta::State;
+use crate::git_config::GitConfig;
+use crate::minusplus::*;
+use crate::paint::{self, LineSections, Painter};
+
+// The number of lines at the start and at the end of a file which are searched for a modeline.
+const MODELINE_SEARCH_LINES: usize = 5;
+
+#[derive(Clone)]
+pub struct FullFileHighlighting {
+    // None if the files are no longer used. They are shared with the copies used to paint hunks in
+    // the background.
+    files: Option<Arc<Files>>,
+    // The (1-based) numbers of the next minus and plus lines of the current hunk.
+    next_line_numbers: MinusPlus<usize>,
+}
+
+struct Files {
+    contents: MinusPlus<Option<String>>,
+    syntax_name: String,
+    // The lines of the minus and plus files, prepared as the lines of the diff are, with the
+    // lengths and styles of their syntax sections. None if the file could not be loaded. They are
+    // highlighted when a hunk is first painted, which may be in the background (see `pipeline`).
+    highlighted_lines: OnceLock<MinusPlus<Option<Vec<HighlightedLine>>>>,
+}
+
+struct HighlightedLine {
+    line: String,
+    sections: Vec<(SyntectStyle, usize)>,
+}
+
+/// Load the files with the ids `blob_ids`, to be highlighted when they are first needed, if the
+/// diff is from a git repository and the files are not too large. The plus file is read from the working tree at `plus_path` if
+/// it is not in the repository. If the syntax is unknown from the file name, then it is detected
+/// from the content of the files first.
+pub fn load(
+    blob_ids: &(String, String),
+    plus_path: &str,
+    git_config: Option<&GitConfig>,
+    painter: &mut Painter,
+) -> Option<FullFileHighlighting> {
+    let config = painter.config;
+    let git_config = git_config?;
+    let load_file = |id: &str, worktree_path: Option<&str>| {
+        if id.bytes().all(|byte| byte == b'0') {
+            // The file does not exist on this side of the diff.
+            return None;
+        }
+        git_config
+            .get_blob_content(id, worktree_path, config.highlight_full_files_max_size)
+            .and_then(|content| String::from_utf8(content).ok())
+    };
+    let contents = MinusPlus::new(
+        load_file(&blob_ids.0, None),
+        load_file(&blob_ids.1, Some(plus_path)),
+    );
+    if contents[Minus].is_none() && contents[Plus].is_none() {
+        return None;
+    }
+    if let Some(content) = contents[Plus].as_ref().or(contents[Minus].as_ref()) {
+        let lines: Vec<&str> = content.lines().collect();
+        if let Some(first_line) = lines.first() {
+            painter.detect_syntax(first_line, true);
+        }
+        let n_head = lines.len().min(MODELINE_SEARCH_LINES);
+        let n_tail = (lines.len() - n_head).min(MODELINE_SEARCH_LINES);
+        for line in lines[..n_head].iter().chain(&lines[lines.len() - n_tail..]) {
+            painter.detect_syntax(line, false);
+        }
+    }
+    Some(FullFileHighlighting::new(contents, painter.syntax))
+}
+
+impl FullFileHighlighting {
+    fn new(contents: MinusPlus<Option<String>>, syntax: &SyntaxReference) -> Self {
+        Self {
+            files: Some(Arc::new(Files {
+                contents,
+                syntax_name: syntax.name.clone(),
+                highlighted_lines: OnceLock::new(),
+            })),
+            next_line_numbers: MinusPlus::new(1, 1),
+        }
+    }
+
+    /// Start a hunk, given the line numbers and lengths of its hunk header. Only hunks of
+    /// unified diffs are supported: after any other hunk header, the files are no longer used.
+    pub fn start_hunk(&mut self, line_numbers_and_hunk_lengths: &[(usize, usize)]) {
+        match line_numbers_and_hunk_lengths {
+            [(minus_line_number, _), (plus_line_number, _)] => {
+                self.next_line_numbers = MinusPlus::new(*minus_line_number, *plus_line_number);
+            }
+            _ => self.files = None,
+        }
+    }
+
+    /// Return the syntax style sections of `lines`, the next lines of the current hunk, or None if
+    /// they do not match the files, in which case the lines must be highlighted by themselves.
+    pub fn syntax_style_sections<'a>(
+        &mut self,
+        lines: &'a [(String, State)],
+        config: &Config,
+    ) -> Option<Vec<LineSections<'a, SyntectStyle>>> {
+        let mut line_sections = Vec::with_capacity(lines.len());
+        let mut lines_match = true;
+        for (line, state) in lines {
+            let highlighted_line = match state {
+                State::HunkMinus(_, _) => {
+                    let line_number = self.advance(Minus);
+                    self.line(Minus, line_number, config)
+                }
+                State::HunkPlus(_, _) => {
+                    let line_number = self.advance(Plus);
+                    self.line(Plus, line_number, config)
+                }
+                State::HunkZero(_, _) => {
+                    let line_numbers = (self.advance(Minus), self.advance(Plus));
+                    self.line(Plus, line_numbers.1, config)
+                        .or_else(|| self.line(Minus, line_numbers.0, config))
+                }
+                _ => None,
+            };
+            if !Painter::should_compute_syntax_highlighting(state, config) {
+                line_sections.push(vec![(config.null_syntect_style, line.as_str())]);
+                continue;
+            }
+            match highlighted_line {
+                Some(highlighted_line) if highlighted_line.line == *line => {
+                    let mut start = 0;
+                    line_sections.push(
+                        highlighted_line
+                            .sections
+                            .iter()
+                            .map(|(style, len)| {
+                                start += len;
+                                (*style, &line[start - len..start])
+                            })
+                            .collect(),
+                    );
+                }
+                // Keep advancing through the lines, so that the following lines can be found.
+                _ => lines_match = false,
+            }
+        }
+        lines_match.then_some(line_sections)
+    }
+
+    /// Return the number of the next line of `side`, and advance to the following line.
+    fn advance(&mut self, side: MinusPlusIndex) -> usize {
+        self.next_line_numbers[side] += 1;
+        self.next_line_numbers[side] - 1
+    }
+
+    fn line(
+        &self,
+        side: MinusPlusIndex,
+        line_number: usize,
+        config: &Config,
+    ) -> Option<&HighlightedLine> {
+        self.files.as_ref()?.highlighted_lines(config)[side]
+            .as_ref()?
+            .get(line_number.checked_sub(1)?)
+    }
+}
+
+impl Files {
+    fn highlighted_lines(&self, config: &Config) -> &MinusPlus<Option<Vec<HighlightedLine>>> {
+        self.highlighted_lines.get_or_init(|| {
+            match config.syntax_set.find_syntax_by_name(&self.syntax_name) {
+                Some(syntax) => MinusPlus::new(
+                    highlight(self.contents[Minus].as_ref(), syntax, config),
+                    highlight(self.contents[Plus].as_ref(), syntax, config),
+                ),
+                None => MinusPlus::new(None, None),
+            }
+        })
+    }
+}
+
+fn highlight(
+    content: Option<&String>,
+    syntax: &SyntaxReference,
+    config: &Config,
+) -> Option<Vec<HighlightedLine>> {
+    let theme = config.syntax_theme.as_ref()?;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut lines = Vec::new();
+    for line in split_lines(content?, config) {
+        let line = paint::prepare(line, 0, config);
+        let sections = highlighter
+            .highlight_line(&line, &config.syntax_set)
+            .ok()?
+            .into_iter()
+            .map(|(style, section)| (style, section.len()))
+            .collect();
+        lines.push(HighlightedLine { line, sections });
+    }
+    Some(lines)
+}
+
+/// The lines of `content`, split as delta splits the lines of its input.
+fn split_lines<'c>(content: &'c str, config: &Config) -> impl Iterator<Item = &'c str> {
+    let keep_carriage_returns = config.show_whitespace.is_some();
+    content
+        .strip_suffix('\n')
+        .unwrap_or(content)
+        .split('\n')
+        .map(move |line| {
+            if keep_carriage_returns {
+                line
+            } else {
+                line.strip_suffix('\r').unwrap_or(line)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delta::DiffType;
+    use crate::tests::integration_test_utils;
+
+    const FILE: &str = r#"def f():
+    """
+    A docstring
+    spanning lines.
+    """
+    return 1
+"#;
+
+    fn make_full_file_highlighting(config: &Config) -> FullFileHighlighting {
+        let syntax = config.syntax_set.find_syntax_by_extension("py").unwrap();
+        FullFileHighlighting::new(
+            MinusPlus::new(Some(FILE.to_string()), Some(FILE.replace("1", "2"))),
+            syntax,
+        )
+    }
+
+    fn hunk_lines(lines: &[(&str, State)], config: &Config) -> Vec<(String, State)> {
+        lines
+            .iter()
+            .map(|(line, state)| (paint::prepare(line, 0, config), state.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_hunk_starting_inside_a_multi_line_string() {
+        let config = integration_test_utils::make_config_from_args(&["--syntax-theme", "GitHub"]);
+        let mut full_file_highlighting = make_full_file_highlighting(&config);
+        let files = full_file_highlighting.files.clone().unwrap();
+        // The files are highlighted when they are first needed.
+        assert!(files.highlighted_lines.get().is_none());
+        let zero = State::HunkZero(DiffType::Unified, None);
+        let minus = State::HunkMinus(DiffType::Unified, None);
+        let plus = State::HunkPlus(DiffType::Unified, None);
+        full_file_highlighting.start_hunk(&[(4, 3), (4, 3)]);
+        let lines = hunk_lines(
+            &[("    spanning lines.", zero.clone()), ("    \"\"\"", zero)],
+            &config,
+        );
+        let sections = full_file_highlighting
+            .syntax_style_sections(&lines, &config)
+            .unwrap();
+        assert!(files.highlighted_lines.get().is_some());
+        // The line is highlighted as part of the string, as it is in the full file.
+        let string_style = sections[0][0].0;
+        assert_eq!(sections[0].len(), 1);
+        assert_eq!(sections[0][0].1, "    spanning lines.\n");
+
+        let lines = hunk_lines(&[("    return 1", minus)], &config);
+        let minus_sections = full_file_highlighting
+            .syntax_style_sections(&lines, &config)
+            .unwrap();
+        let lines = hunk_lines(&[("    return 2", plus)], &config);
+        let plus_sections = full_file_highlighting
+            .syntax_style_sections(&lines, &config)
+            .unwrap();
+        assert_ne!(minus_sections[0][0].0, string_style);
+        assert_eq!(
+            plus_sections[0].iter().map(|(_, s)| *s).collect::<String>(),
+            "    return 2\n"
+        );
+    }
+
+    #[test]
+    fn test_lines_not_matching_the_files() {
+        let config = integration_test_utils::make_config_from_args(&["--syntax-theme", "GitHub"]);
+        let mut full_file_highlighting = make_full_file_highlighting(&config);
+        let plus = State::HunkPlus(DiffType::Unified, None);
+        full_file_highlighting.start_hunk(&[(6, 1), (6, 2)]);
+        let lines = hunk_lines(&[("    return 3", plus.clone())], &config);
+        assert!(full_file_highlighting
+            .syntax_style_sections(&lines, &config)
+            .is_none());
+        // Beyond the end of the file.
+        let lines = hunk_lines(&[("", plus)], &config);
+        assert!(full_file_highlighting
+            .syntax_style_sections(&lines, &config)
+            .is_none());
+
+        // Only hunks of unified diffs are supported.
+        full_file_highlighting.start_hunk(&[(1, 1), (1, 1), (1, 1)]);
+        full_file_highlighting.start_hunk(&[(1, 1), (1, 1)]);
+        let lines = hunk_lines(
+            &[("def f():", State::HunkZero(DiffType::Unified, None))],
+            &config,
+        );
+        assert!(full_file_highlighting
+            .syntax_style_sections(&lines, &config)
+            .is_none());
+    }
+
+    #[test]
+    fn test_hunk_not_matching_the_files_is_highlighted_by_itself() {
+        let config = integration_test_utils::make_config_from_args(&["--syntax-theme", "GitHub"]);
+        let syntax = config.syntax_set.find_syntax_by_extension("py").unwrap();
+        let plus = State::HunkPlus(DiffType::Unified, None);
+        let paint = |full_file_highlighting: Option<FullFileHighlighting>| {
+            let mut paint_state = paint::PaintState {
+                highlighter: None,
+                line_numbers_data: None,
+                full_file_highlighting,
+            };
+            paint_state.set_highlighter(syntax, &config);
+            let mut output = String::new();
+            paint::PaintOp::MinusAndPlusLines {
+                lines: MinusPlus::new(
+                    Vec::new(),
+                    hunk_lines(&[("    return 3", plus.clone())], &config),
+                ),
+                unicode_warnings: vec![Vec::new()],
+                side_by_side_data: None,
+            }
+            .paint(&mut paint_state, &mut output, &config);
+            output
+        };
+        let mut full_file_highlighting = make_full_file_highlighting(&config);
+        full_file_highlighting.start_hunk(&[(6, 1), (6, 1)]);
+        let output = paint(Some(full_file_highlighting));
+        assert_eq!(output, paint(None));
+        // "return" is highlighted as a keyword.
+        let return_style = crate::ansi::parse_style_sections(&output)
+            .into_iter()
+            .find(|(_, text)| text.contains("return"))
+            .unwrap()
+            .0;
+        assert!(return_style.foreground.is_some());
+    }
+}
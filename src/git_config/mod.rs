@@ -10,6 +10,9 @@ use std::str::FromStr;
 
 use lazy_static::lazy_static;
 
+// The libgit2 objects must stay on the thread which created them, so the git config is not part of
+// `Config`, which is shared with the threads painting hunks in the background (see
+// --paint-threads): it is used by the `StateMachine`.
 pub struct GitConfig {
     config: git2::Config,
     config_from_env_var: HashMap<String, String>,
@@ -117,6 +120,67 @@ impl GitConfig {
             .and_then(|url| GitRemoteRepo::from_str(url).ok())
     }
 
+    /// Return whether the gitattribute `name` is set for `path`, which is relative to the
+    /// repository root. None if the attribute is unspecified or there is no repository.
+    pub fn get_attribute(&self, path: &str, name: &str) -> Option<bool> {
+        let value = self
+            .repo
+            .as_ref()?
+            .get_attr_bytes(Path::new(path), name, git2::AttrCheckFlags::default())
+            .ok()?;
+        match git2::AttrValue::from_bytes(value) {
+            git2::AttrValue::True => Some(true),
+            git2::AttrValue::False => Some(false),
+            git2::AttrValue::String(s) => Some(s != "false"),
+            git2::AttrValue::Bytes(_) => Some(true),
+            git2::AttrValue::Unspecified => None,
+        }
+    }
+
+    /// Return the value of the gitattribute `name` for `path`, if it is set to a value.
+    pub fn get_attribute_value(&self, path: &str, name: &str) -> Option<String> {
+        let value = self
+            .repo
+            .as_ref()?
+            .get_attr_bytes(Path::new(path), name, git2::AttrCheckFlags::default())
+            .ok()?;
+        match git2::AttrValue::from_bytes(value) {
+            git2::AttrValue::String(s) => Some(s.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Return the content of the blob with the (possibly abbreviated) id `id`, unless it is larger
+    /// than `max_size` bytes. If the blob is not in the object database, then fall back to the
+    /// file at `worktree_path`, relative to the repository root, if its content has that id: git
+    /// diff does not store the blobs of files in the working tree.
+    pub fn get_blob_content(
+        &self,
+        id: &str,
+        worktree_path: Option<&str>,
+        max_size: usize,
+    ) -> Option<Vec<u8>> {
+        let repo = self.repo.as_ref()?;
+        let odb = repo.odb().ok()?;
+        if let Ok(oid) = git2::Oid::from_str(id).and_then(|oid| odb.exists_prefix(oid, id.len())) {
+            let (size, _) = odb.read_header(oid).ok()?;
+            if size > max_size {
+                return None;
+            }
+            return repo.find_blob(oid).ok().map(|blob| blob.content().to_vec());
+        }
+        let path = repo.workdir()?.join(worktree_path?);
+        if std::fs::metadata(&path).ok()?.len() > max_size as u64 {
+            return None;
+        }
+        let content = std::fs::read(&path).ok()?;
+        git2::Oid::hash_object(git2::ObjectType::Blob, &content)
+            .ok()?
+            .to_string()
+            .starts_with(id)
+            .then_some(content)
+    }
+
     pub fn for_each<F>(&self, regex: &str, mut f: F)
     where
         F: FnMut(&str, Option<&str>),
@@ -11,6 +11,7 @@ use crate::delta::{self, State, StateMachine};
 use crate::fatal;
 use crate::format::{self, FormatStringSimple, Placeholder};
 use crate::format::{make_placeholder_regex, parse_line_number_format};
+use crate::git_config::GitConfig;
 use crate::paint::{self, BgShouldFill, StyleSectionSpecifier};
 use crate::style::Style;
 use crate::utils;
@@ -47,7 +48,7 @@ impl<'a> StateMachine<'a> {
                     false,
                 );
                 let mut formatted_blame_metadata =
-                    format_blame_metadata(&format_data, &blame, self.config);
+                    format_blame_metadata(&format_data, &blame, self.config, self.git_config);
                 let key = formatted_blame_metadata.clone();
                 let is_repeat = previous_key.as_deref() == Some(&key);
                 if is_repeat {
@@ -118,7 +119,8 @@ impl<'a> StateMachine<'a> {
                 // borrow checker won't permit that.
                 let style = Style::from_colors(
                     None,
-                    color::parse_color(&color, true, self.config.git_config()),
+                    color::parse_color(&color, true, self.git_config)
+                        .map(|color| color::quantize_color(color, self.config.color_depth)),
                 );
                 self.blame_key_colors.insert(key.to_owned(), color);
                 style
@@ -259,6 +261,7 @@ pub fn format_blame_metadata(
     format_data: &[format::FormatStringPlaceholderData],
     blame: &BlameLine,
     config: &config::Config,
+    git_config: Option<&GitConfig>,
 ) -> String {
     let mut s = String::new();
     let mut suffix = "";
@@ -276,7 +279,9 @@ pub fn format_blame_metadata(
                 }))
             }
             Some(Placeholder::Str("author")) => Some(Cow::from(blame.author)),
-            Some(Placeholder::Str("commit")) => Some(delta::format_raw_line(blame.commit, config)),
+            Some(Placeholder::Str("commit")) => {
+                Some(delta::format_raw_line(blame.commit, config, git_config))
+            }
             None => None,
             _ => unreachable!("Unexpected `git blame` input"),
         };
@@ -418,7 +423,7 @@ mod tests {
         let blame = make_blame_line_with_time("1996-12-19T16:39:57-08:00");
         let config = integration_test_utils::make_config_from_args(&[]);
         let regex = Regex::new(r"^\d+ years ago$").unwrap();
-        let result = format_blame_metadata(&[format_data], &blame, &config);
+        let result = format_blame_metadata(&[format_data], &blame, &config, None);
         assert!(regex.is_match(result.trim()));
     }
 
@@ -432,7 +437,7 @@ mod tests {
         let config = integration_test_utils::make_config_from_args(&[
             "--blame-timestamp-output-format=%Y-%m-%d %H:%M",
         ]);
-        let result = format_blame_metadata(&[format_data], &blame, &config);
+        let result = format_blame_metadata(&[format_data], &blame, &config, None);
         assert_eq!(result.trim(), "1996-12-19 16:39");
     }
 
@@ -445,7 +450,7 @@ mod tests {
             "--blame-palette",
             "1 2",
         ]);
-        let mut machine = StateMachine::new(&mut writer, &config);
+        let mut machine = StateMachine::new(&mut writer, &config, None);
 
         let blame_lines: HashMap<&str, &str> = vec![
             (
@@ -6,7 +6,7 @@ use crate::features;
 
 impl<'a> StateMachine<'a> {
     #[inline]
-    fn test_commit_meta_header_line(&self) -> bool {
+    pub fn test_commit_meta_header_line(&self) -> bool {
         self.config.commit_regex.is_match(&self.line)
     }
 
@@ -15,9 +15,21 @@ impl<'a> StateMachine<'a> {
             return Ok(false);
         }
         let mut handled_line = false;
+        if !self.hunk_header_context.is_empty() {
+            self.emit_pending_hunk_header_line()?;
+        }
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
         self.handle_pending_line_with_diff_name()?;
+        self.handle_deferred_file_header_end()?;
+        self.handle_toc_end()?;
         self.state = State::CommitMeta;
+        if let Some(summary) = &mut self.summary {
+            summary.count_commit();
+        }
+        if let Some(structured_output) = &mut self.painter.structured_output {
+            structured_output.write_commit(&self.line);
+        }
         if self.should_handle() {
             self.painter.emit()?;
             self._handle_commit_meta_header_line()?;
@@ -30,17 +42,21 @@ impl<'a> StateMachine<'a> {
         if self.config.commit_style.is_omitted {
             return Ok(());
         }
-        let (mut draw_fn, pad, decoration_ansi_term_style) =
-            draw::get_draw_function(self.config.commit_style.decoration_style);
+        let (mut draw_fn, pad, decoration_ansi_term_style) = draw::get_draw_function(
+            self.config.commit_style.decoration_style,
+            self.config.monochrome,
+        );
         let (formatted_line, formatted_raw_line) = if self.config.hyperlinks {
             (
                 features::hyperlinks::format_commit_line_with_osc8_commit_hyperlink(
                     &self.line,
                     self.config,
+                    self.git_config,
                 ),
                 features::hyperlinks::format_commit_line_with_osc8_commit_hyperlink(
                     &self.raw_line,
                     self.config,
+                    self.git_config,
                 ),
             )
         } else {
@@ -48,7 +64,7 @@ impl<'a> StateMachine<'a> {
         };
 
         draw_fn(
-            self.painter.writer,
+            &mut self.painter.writer,
             &format!("{}{}", formatted_line, if pad { " " } else { "" }),
             &format!("{}{}", formatted_raw_line, if pad { " " } else { "" }),
             "",
@@ -1,18 +1,28 @@
 use std::borrow::Cow;
+use std::io::Write;
 use std::path::Path;
 
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
 use unicode_segmentation::UnicodeSegmentation;
 
 use super::draw;
+use super::hunk_header::{hunk_header_format_needs_counts, DeferredHunkHeader};
+use super::summary;
 use crate::config::Config;
 use crate::delta::{DiffType, Source, State, StateMachine};
+use crate::format::{self, Placeholder};
+use crate::git_config::GitConfig;
 use crate::paint::Painter;
+use crate::suspicious_unicode::UnicodeWarnings;
 use crate::{features, utils};
 
 // https://git-scm.com/docs/git-config#Documentation/git-config.txt-diffmnemonicPrefix
 const DIFF_PREFIXES: [&str; 6] = ["a/", "b/", "c/", "i/", "o/", "w/"];
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FileEvent {
     Added,
     Change,
@@ -22,17 +32,75 @@ pub enum FileEvent {
     NoEvent,
 }
 
+/// A file header which is written at the end of the file, because it reports on the lines of
+/// the file. Unless the file is collapsed (see --collapse-files), the output for the lines of the
+/// file is held back until then.
+#[derive(Debug, Default)]
+pub struct DeferredFileHeader {
+    header: Option<String>,
+    mode_info: String,
+    pub collapsed: bool,
+    pub n_minus: usize,
+    pub n_plus: usize,
+    pub n_unicode_warnings: usize,
+    pub hunk_headers: Vec<DeferredHunkHeader>,
+}
+
+/// Information from the diff header lines and the lines of a file, for --file-header-format.
+#[derive(Debug, Default)]
+pub struct FileMetadata {
+    old_mode: String,
+    new_mode: String,
+    similarity: String,
+    pub is_symlink: bool,
+    pub old_symlink_target: Option<String>,
+    pub new_symlink_target: Option<String>,
+    // The ids of the minus and plus blobs, from the "index" line of a unified diff.
+    pub blob_ids: Option<(String, String)>,
+}
+
+impl FileMetadata {
+    fn record_mode(&mut self, mode: &str) {
+        // https://git-scm.com/docs/git-fast-import#_filemodify
+        if mode == "120000" {
+            self.is_symlink = true;
+        }
+    }
+}
+
+impl DeferredFileHeader {
+    /// Count a removed line of the file, and of its current hunk.
+    pub fn count_minus_line(&mut self) {
+        self.n_minus += 1;
+        if let Some(hunk_header) = self.hunk_headers.last_mut() {
+            hunk_header.n_minus += 1;
+        }
+    }
+
+    /// Count an added line of the file, and of its current hunk.
+    pub fn count_plus_line(&mut self) {
+        self.n_plus += 1;
+        if let Some(hunk_header) = self.hunk_headers.last_mut() {
+            hunk_header.n_plus += 1;
+        }
+    }
+}
+
 impl<'a> StateMachine<'a> {
     /// Check for the old mode|new mode lines and cache their info for later use.
     pub fn handle_diff_header_mode_line(&mut self) -> std::io::Result<bool> {
         let mut handled_line = false;
         if let Some(line_suf) = self.line.strip_prefix("old mode ") {
+            self.file_metadata.old_mode = line_suf.to_string();
+            self.file_metadata.record_mode(line_suf);
             self.state = State::DiffHeader(DiffType::Unified);
             if self.should_handle() && !self.config.color_only {
                 self.mode_info = line_suf.to_string();
                 handled_line = true;
             }
         } else if let Some(line_suf) = self.line.strip_prefix("new mode ") {
+            self.file_metadata.new_mode = line_suf.to_string();
+            self.file_metadata.record_mode(line_suf);
             self.state = State::DiffHeader(DiffType::Unified);
             if self.should_handle() && !self.config.color_only && !self.mode_info.is_empty() {
                 self.mode_info = match (self.mode_info.as_str(), line_suf) {
@@ -47,6 +115,22 @@ impl<'a> StateMachine<'a> {
                 };
                 handled_line = true;
             }
+        } else if matches!(self.state, State::DiffHeader(_)) {
+            // These lines are not handled here, only recorded for --file-header-format.
+            if let Some(similarity) = self.line.strip_prefix("similarity index ") {
+                self.file_metadata.similarity = similarity.to_string();
+            } else if let Some(index) = self.line.strip_prefix("index ") {
+                // E.g. "index 1111111..2222222 120000"
+                let mut fields = index.split(' ');
+                self.file_metadata.blob_ids = fields
+                    .next()
+                    .and_then(|ids| ids.split_once(".."))
+                    .filter(|(minus_id, _)| !minus_id.contains(','))
+                    .map(|(minus_id, plus_id)| (minus_id.to_string(), plus_id.to_string()));
+                if let Some(mode) = fields.next() {
+                    self.file_metadata.record_mode(mode);
+                }
+            }
         }
         Ok(handled_line)
     }
@@ -83,6 +167,9 @@ impl<'a> StateMachine<'a> {
         if !self.test_diff_header_minus_line() {
             return Ok(false);
         }
+        if !self.hunk_header_context.is_empty() {
+            self.emit_pending_hunk_header_line()?;
+        }
 
         let (path_or_mode, file_event) =
             parse_diff_header_line(&self.line, self.source == Source::GitDiff);
@@ -104,6 +191,12 @@ impl<'a> StateMachine<'a> {
         }
 
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
+        // In git output, files start at "diff --git" lines: the header of a renamed file may
+        // already have been deferred at its "rename to" line.
+        if self.source != Source::GitDiff {
+            self.handle_deferred_file_header_end()?;
+        }
         self.should_write_generic_diff_header_header_line()
     }
 
@@ -124,17 +217,63 @@ impl<'a> StateMachine<'a> {
         let (path_or_mode, file_event) =
             parse_diff_header_line(&self.line, self.source == Source::GitDiff);
 
+        if self.deferred_file_header.is_none() && !self.config.color_only {
+            let path = if path_or_mode == "/dev/null" {
+                &self.minus_file
+            } else {
+                &path_or_mode
+            };
+            let collapsed = should_collapse_file(path, self.config, self.git_config);
+            if collapsed
+                || self.config.unicode_warnings == UnicodeWarnings::Files
+                || hunk_header_format_needs_counts(self.config)
+                || file_header_format_needs_file_end(self.config)
+            {
+                self.deferred_file_header = Some(DeferredFileHeader {
+                    collapsed,
+                    ..DeferredFileHeader::default()
+                });
+            }
+        }
+
+        let language = get_language_for_path(
+            if path_or_mode == "/dev/null" {
+                &self.minus_file
+            } else {
+                &path_or_mode
+            },
+            self.config,
+            self.git_config,
+        );
+        // Git diff paths are relative to the repository root, as required to load the file.
+        let repo_path = path_or_mode.clone();
         self.plus_file = utils::path::relativize_path_maybe(&path_or_mode, self.config)
             .map(|p| p.to_string_lossy().into_owned())
             .unwrap_or(path_or_mode);
         self.plus_file_event = file_event;
-        self.painter
-            .set_syntax(get_file_extension_from_diff_header_line_file_path(
-                &self.plus_file,
-            ));
+        self.hunk_index = 0;
+        self.start_file_in_summary();
+        self.start_file_in_toc();
+        self.start_file_in_structured_output();
+        if !matches!(language, Some(language) if self.painter.set_syntax_for_language(&language)) {
+            self.painter
+                .set_syntax(get_file_extension_from_diff_header_line_file_path(
+                    &self.plus_file,
+                ));
+        }
+        self.painter.full_file_highlighting = None;
+        self.full_file_to_load = match &self.file_metadata.blob_ids {
+            Some(blob_ids)
+                if self.config.highlight_full_files && self.source == Source::GitDiff =>
+            {
+                Some((blob_ids.clone(), repo_path))
+            }
+            _ => None,
+        };
         self.current_file_pair = Some((self.minus_file.clone(), self.plus_file.clone()));
 
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
         if self.should_write_generic_diff_header_header_line()? {
             handled_line = true;
         } else if self.should_handle()
@@ -160,8 +299,8 @@ impl<'a> StateMachine<'a> {
             return Ok(false);
         }
         let mut handled_line = false;
-        let (_mode_info, file_event) =
-            parse_diff_header_line(&self.line, self.source == Source::GitDiff);
+        let (mode, file_event) = parse_diff_header_line(&self.line, self.source == Source::GitDiff);
+        self.file_metadata.record_mode(&mode);
         let name = get_repeated_file_path_from_diff_line(&self.diff_line).unwrap_or_default();
         match file_event {
             FileEvent::Removed => {
@@ -180,6 +319,11 @@ impl<'a> StateMachine<'a> {
             }
             _ => (),
         }
+        if matches!(file_event, FileEvent::Added | FileEvent::Removed) {
+            self.start_file_in_summary();
+            self.start_file_in_toc();
+            self.start_file_in_structured_output();
+        }
 
         if self.should_write_generic_diff_header_header_line()?
             || (self.should_handle()
@@ -200,6 +344,15 @@ impl<'a> StateMachine<'a> {
             &self.plus_file_event,
             self.config,
         );
+        if let Some(deferred_file_header) = &mut self.deferred_file_header {
+            deferred_file_header.header = Some(line);
+            deferred_file_header.mode_info = std::mem::take(&mut self.mode_info);
+            if !deferred_file_header.collapsed {
+                self.painter.writer.hold();
+            }
+            return Ok(());
+        }
+        let line = self.format_file_header(line, None);
         // FIXME: no support for 'raw'
         write_generic_diff_header_header_line(
             &line,
@@ -210,6 +363,117 @@ impl<'a> StateMachine<'a> {
         )
     }
 
+    /// Return the file header given by --file-header-format, in which `file` is the file path with
+    /// its label, and `counts` the numbers of added and removed lines if they are known. If the
+    /// format is not set, return `file`. The mode change is part of the format, so it is not shown
+    /// separately.
+    fn format_file_header(&mut self, file: String, counts: Option<(usize, usize)>) -> String {
+        if self.config.file_header_format.is_empty() || self.config.color_only {
+            return file;
+        }
+        self.mode_info.clear();
+        let metadata = &self.file_metadata;
+        let arrow = &self.config.right_arrow;
+        let old_and_new = |old: &str, new: &str| match (old.is_empty(), new.is_empty()) {
+            (false, false) => format!("{old} {arrow} {new}"),
+            _ => format!("{old}{new}"),
+        };
+        let format_data = format::parse_line_number_format(
+            &self.config.file_header_format,
+            &FILE_HEADER_PLACEHOLDER_REGEX,
+            false,
+        );
+        let mut s = String::new();
+        let mut suffix = "";
+        for placeholder in &format_data {
+            s.push_str(placeholder.prefix.as_str());
+            let alignment_spec = placeholder.alignment_spec.unwrap_or(format::Align::Left);
+            let width = placeholder.width.unwrap_or(0);
+            let field = match placeholder.placeholder {
+                Some(Placeholder::Str("file")) => Some(file.clone()),
+                // Unknown counts are left out.
+                Some(Placeholder::Str("added")) => Some(
+                    counts
+                        .map(|(n_plus, _)| n_plus.to_string())
+                        .unwrap_or_default(),
+                ),
+                Some(Placeholder::Str("removed")) => Some(
+                    counts
+                        .map(|(_, n_minus)| n_minus.to_string())
+                        .unwrap_or_default(),
+                ),
+                Some(Placeholder::Str("mode")) => {
+                    Some(old_and_new(&metadata.old_mode, &metadata.new_mode))
+                }
+                Some(Placeholder::Str("similarity")) => Some(metadata.similarity.clone()),
+                Some(Placeholder::Str("symlink")) => Some(old_and_new(
+                    metadata.old_symlink_target.as_deref().unwrap_or_default(),
+                    metadata.new_symlink_target.as_deref().unwrap_or_default(),
+                )),
+                None => None,
+                _ => unreachable!("Unexpected file-header-format placeholder"),
+            };
+            if let Some(field) = field {
+                s.push_str(&format::pad(
+                    field,
+                    width,
+                    alignment_spec,
+                    placeholder.precision,
+                ));
+            }
+            suffix = placeholder.suffix.as_str();
+        }
+        s.push_str(suffix);
+        s
+    }
+
+    /// Write a deferred file header, followed by the output held back since the file started.
+    pub fn handle_deferred_file_header_end(&mut self) -> std::io::Result<()> {
+        if let Some(DeferredFileHeader {
+            header: Some(line),
+            mode_info,
+            collapsed,
+            n_minus,
+            n_plus,
+            n_unicode_warnings,
+            hunk_headers,
+        }) = self.deferred_file_header.take()
+        {
+            self.painter.emit()?;
+            // The output of a collapsed file is not held back, see _handle_diff_header_header_line.
+            let held_output = if collapsed {
+                Vec::new()
+            } else {
+                self.painter.writer.release()
+            };
+            let held_output = self.insert_deferred_hunk_headers(held_output, hunk_headers)?;
+            self.mode_info = mode_info;
+            let mut line = self.format_file_header(line, Some((n_plus, n_minus)));
+            let mut mode_info = std::mem::take(&mut self.mode_info);
+            if collapsed {
+                line.push_str(&format!(
+                    "  {}",
+                    summary::format_line_counts(n_plus, n_minus, self.config)
+                ));
+            }
+            if n_unicode_warnings > 0 {
+                line.push_str(&format!(
+                    "  ⚠ suspicious Unicode in {n_unicode_warnings} line{}",
+                    if n_unicode_warnings == 1 { "" } else { "s" }
+                ));
+            }
+            write_generic_diff_header_header_line(
+                &line,
+                &line,
+                &mut self.painter,
+                &mut mode_info,
+                self.config,
+            )?;
+            self.painter.writer.write_all(&held_output)?;
+        }
+        Ok(())
+    }
+
     #[inline]
     fn test_pending_line_with_diff_name(&self) -> bool {
         matches!(self.state, State::DiffHeader(_)) || self.source == Source::DiffUnified
@@ -243,6 +507,7 @@ impl<'a> StateMachine<'a> {
             let label = format_label(&self.config.file_modified_label);
             let name = get_repeated_file_path_from_diff_line(&self.diff_line).unwrap_or_default();
             let line = format!("{}{}", label, format_file(&name));
+            let line = self.format_file_header(line, Some((0, 0)));
             write_generic_diff_header_header_line(
                 &line,
                 &line,
@@ -263,6 +528,74 @@ impl<'a> StateMachine<'a> {
     }
 }
 
+lazy_static! {
+    static ref FILE_HEADER_PLACEHOLDER_REGEX: Regex = format::make_placeholder_regex(&[
+        "file",
+        "added",
+        "removed",
+        "mode",
+        "similarity",
+        "symlink",
+    ]);
+}
+
+/// Whether --file-header-format refers to information which is known only at the end of the file.
+fn file_header_format_needs_file_end(config: &Config) -> bool {
+    FILE_HEADER_PLACEHOLDER_REGEX
+        .captures_iter(&config.file_header_format)
+        .any(|caps| matches!(&caps[1], "added" | "removed" | "symlink"))
+}
+
+/// Return whether only the header of the file at `path` should be shown, according to
+/// --collapse-files and --collapse-gitattributes.
+fn should_collapse_file(path: &str, config: &Config, git_config: Option<&GitConfig>) -> bool {
+    if path.is_empty() || path == "/dev/null" {
+        return false;
+    }
+    if let Some(collapse_files) = &config.collapse_files {
+        if collapse_files.is_match(path) {
+            return true;
+        }
+    }
+    match git_config {
+        Some(git_config) if config.collapse_gitattributes => {
+            let attribute = |name| git_config.get_attribute(path, name);
+            attribute("linguist-generated") == Some(true)
+                || attribute("linguist-vendored") == Some(true)
+                || attribute("delta-collapse") == Some(true)
+                || attribute("diff") == Some(false)
+        }
+        _ => false,
+    }
+}
+
+/// Return the language of the file at `path` according to its delta-language or
+/// linguist-language gitattribute, or to --map-languages, if any.
+fn get_language_for_path(
+    path: &str,
+    config: &Config,
+    git_config: Option<&GitConfig>,
+) -> Option<String> {
+    if path.is_empty() || path == "/dev/null" {
+        return None;
+    }
+    git_config
+        .and_then(|git_config| {
+            git_config
+                .get_attribute_value(path, "delta-language")
+                .or_else(|| git_config.get_attribute_value(path, "linguist-language"))
+        })
+        .or_else(|| {
+            // Later patterns take precedence, like in .gitattributes.
+            config
+                .languages_map
+                .iter()
+                .rev()
+                .find(|(glob, _)| glob.is_match(path))
+                .map(|(_, language)| language.clone())
+        })
+}
+
 /// Write `line` with DiffHeader styling.
 pub fn write_generic_diff_header_header_line(
     line: &str,
@@ -278,13 +611,13 @@ pub fn write_generic_diff_header_header_line(
         return Ok(());
     }
     let (mut draw_fn, pad, decoration_ansi_term_style) =
-        draw::get_draw_function(config.file_style.decoration_style);
+        draw::get_draw_function(config.file_style.decoration_style, config.monochrome);
     if !config.color_only {
         // Maintain 1-1 correspondence between input and output lines.
         writeln!(painter.writer)?;
     }
     draw_fn(
-        painter.writer,
+        &mut painter.writer,
         &format!("{}{}", line, if pad { " " } else { "" }),
         &format!("{}{}", raw_line, if pad { " " } else { "" }),
         mode_info,
@@ -475,6 +808,7 @@ pub fn get_file_change_description_from_file_paths(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tests::integration_test_utils::DeltaTest;
 
     #[test]
     fn test_get_file_extension_from_marker_line() {
@@ -646,4 +980,140 @@ mod tests {
             Some(".config/Code - Insiders/User/settings.json".to_string())
         );
     }
+
+    #[test]
+    fn test_collapse_files() {
+        let output = DeltaTest::with_args(&["--collapse-files", "*.lock"])
+            .with_input(TWO_FILES_DIFF)
+            .expect_after_skip(
+                1,
+                r#"#partial
+                Cargo.lock  +2/−1"#,
+            )
+            .expect_contains("x = 2")
+            .output;
+        assert!(!output.contains("version = 3"));
+        assert!(!output.contains("name = "));
+    }
+
+    #[test]
+    fn test_collapse_files_no_match() {
+        DeltaTest::with_args(&["--collapse-files", "*.json vendor/"])
+            .with_input(TWO_FILES_DIFF)
+            .expect_after_skip(
+                1,
+                r#"#partial
+                Cargo.lock"#,
+            )
+            .expect_contains("name = \"c\"");
+    }
+
+    #[test]
+    fn test_file_header_format() {
+        DeltaTest::with_args(&[
+            "--file-header-format",
+            "{file} +{added}/-{removed} {similarity}",
+        ])
+        .with_input(FILE_METADATA_DIFF)
+        .expect_contains("renamed: a.py ⟶   b.py +2/-1 90%")
+        .expect_contains("z = 4");
+    }
+
+    #[test]
+    fn test_file_header_format_mode_and_symlink() {
+        DeltaTest::with_args(&["--file-header-format", "{file}: {mode}{symlink}"])
+            .with_input(FILE_METADATA_DIFF)
+            .expect_contains("link: old/target ⟶   new/target")
+            .expect_contains("run.sh: 100644 ⟶   100755");
+    }
+
+    /// The raw output of the lines containing `print` in a diff of `file` with the given hunk.
+    fn highlighted_print_lines(args: &[&str], file: &str, hunk: &str) -> Vec<String> {
+        let input = format!(
+            "diff --git a/{file} b/{file}\nindex 1111111..2222222 100644\n--- a/{file}\n+++ b/{file}\n{hunk}"
+        );
+        DeltaTest::with_args(args)
+            .with_input(&input)
+            .raw_output
+            .lines()
+            .filter(|line| line.contains("print"))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_language_from_shebang() {
+        let hunk = "@@ -1,2 +1,2 @@\n #!/usr/bin/env python3\n-print('a')\n+print('b')\n";
+        let python = highlighted_print_lines(&[], "run.py", hunk);
+        assert_eq!(highlighted_print_lines(&[], "run", hunk), python);
+        assert_ne!(highlighted_print_lines(&[], "run.txt", hunk), python);
+        // A shebang is only recognized on the first line of the file.
+        let hunk = hunk.replace("-1,2 +1,2", "-5,2 +5,2");
+        assert_eq!(
+            highlighted_print_lines(&[], "run", &hunk),
+            highlighted_print_lines(&[], "run.txt", &hunk)
+        );
+    }
+
+    #[test]
+    fn test_language_from_modeline() {
+        let hunk = "@@ -8,2 +8,3 @@\n-print('a')\n+print('b')\n+# vim: set ft=python :\n";
+        let python = highlighted_print_lines(&[], "run.py", hunk);
+        assert_eq!(highlighted_print_lines(&[], "run", hunk), python);
+    }
+
+    #[test]
+    fn test_map_languages() {
+        let hunk = "@@ -8 +8 @@\n-print('a')\n+print('b')\n";
+        let python = highlighted_print_lines(&[], "lib/a.py", hunk);
+        let args = ["--map-languages", "*.inc => txt, lib/*.inc => Python"];
+        assert_eq!(highlighted_print_lines(&args, "lib/a.inc", hunk), python);
+        assert_ne!(highlighted_print_lines(&args, "a.inc", hunk), python);
+    }
+
+    const FILE_METADATA_DIFF: &str = "\
+diff --git a/link b/link
+index 1111111..2222222 120000
+--- a/link
++++ b/link
+@@ -1 +1 @@
+-old/target
+\\ No newline at end of file
++new/target
+\\ No newline at end of file
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/a.py b/b.py
+similarity index 90%
+rename from a.py
+rename to b.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/b.py
+@@ -1,2 +1,3 @@
+ x = 1
+-y = 2
++y = 3
++z = 4
+";
+
+    const TWO_FILES_DIFF: &str = "\
+diff --git a/Cargo.lock b/Cargo.lock
+index 1111111..2222222 100644
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -1,2 +1,3 @@
+ version = 3
+-name = \"a\"
++name = \"b\"
++name = \"c\"
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1 +1 @@
+-x = 1
++x = 2
+";
 }
@@ -11,7 +11,11 @@ impl<'a> StateMachine<'a> {
         if !self.test_diff_header_diff_line() {
             return Ok(false);
         }
+        if !self.hunk_header_context.is_empty() {
+            self.emit_pending_hunk_header_line()?;
+        }
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
         self.state =
             if self.line.starts_with("diff --cc ") || self.line.starts_with("diff --combined ") {
                 // We will determine the number of parents when we see the hunk header.
@@ -23,6 +27,9 @@ impl<'a> StateMachine<'a> {
                 State::DiffHeader(DiffType::Unified)
             };
         self.handle_pending_line_with_diff_name()?;
+        self.handle_deferred_file_header_end()?;
+        self.file_metadata = Default::default();
+        self.handle_toc_start();
         self.handled_diff_header_header_line_file_pair = None;
         self.diff_line = self.line.clone();
         if !self.should_skip_line() {
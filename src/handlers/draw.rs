@@ -25,30 +25,93 @@ pub type DrawFunction = dyn FnMut(
     ansi_term::Style,
 ) -> std::io::Result<()>;
 
+/// Return the function drawing the given decoration, whether the text should be padded, and the
+/// style of the decoration. With `ascii`, the decoration is drawn with ASCII characters instead
+/// of box-drawing characters.
 pub fn get_draw_function(
     decoration_style: DecorationStyle,
+    ascii: bool,
 ) -> (Box<DrawFunction>, bool, ansi_term::Style) {
-    match decoration_style {
-        DecorationStyle::Box(style) => (Box::new(write_boxed), true, style),
-        DecorationStyle::BoxWithUnderline(style) => {
-            (Box::new(write_boxed_with_underline), true, style)
-        }
+    let (draw_fn, pad, style): (InnerDrawFunction, bool, ansi_term::Style) = match decoration_style
+    {
+        DecorationStyle::Box(style) => (write_boxed, true, style),
+        DecorationStyle::BoxWithUnderline(style) => (write_boxed_with_underline, true, style),
         DecorationStyle::BoxWithOverline(style) => {
             // TODO: not implemented
-            (Box::new(write_boxed), true, style)
+            (write_boxed, true, style)
         }
         DecorationStyle::BoxWithUnderOverline(style) => {
             // TODO: not implemented
-            (Box::new(write_boxed), true, style)
+            (write_boxed, true, style)
         }
-        DecorationStyle::Underline(style) => (Box::new(write_underlined), false, style),
-        DecorationStyle::Overline(style) => (Box::new(write_overlined), false, style),
-        DecorationStyle::UnderOverline(style) => (Box::new(write_underoverlined), false, style),
-        DecorationStyle::NoDecoration => (
-            Box::new(write_no_decoration),
-            false,
-            ansi_term::Style::new(),
+        DecorationStyle::Underline(style) => (write_underlined, false, style),
+        DecorationStyle::Overline(style) => (write_overlined, false, style),
+        DecorationStyle::UnderOverline(style) => (write_underoverlined, false, style),
+        DecorationStyle::NoDecoration => (write_no_decoration, false, ansi_term::Style::new()),
+    };
+    (
+        Box::new(
+            move |writer, text, raw_text, addendum, line_width, text_style, style| {
+                draw_fn(
+                    writer,
+                    text,
+                    raw_text,
+                    addendum,
+                    line_width,
+                    text_style,
+                    Decoration { style, ascii },
+                )
+            },
         ),
+        pad,
+        style,
+    )
+}
+
+type InnerDrawFunction =
+    fn(&mut dyn Write, &str, &str, &str, &Width, Style, Decoration) -> std::io::Result<()>;
+
+#[derive(Clone, Copy)]
+struct Decoration {
+    style: ansi_term::Style,
+    ascii: bool,
+}
+
+struct BoxChars {
+    horizontal: &'static str,
+    vertical: &'static str,
+    down_left: &'static str,
+    up_left: &'static str,
+    up_horizontal: &'static str,
+}
+
+impl Decoration {
+    fn chars(&self) -> BoxChars {
+        if self.ascii {
+            BoxChars {
+                horizontal: if self.style.is_bold { "=" } else { "-" },
+                vertical: "|",
+                down_left: "+",
+                up_left: "+",
+                up_horizontal: "+",
+            }
+        } else if self.style.is_bold {
+            BoxChars {
+                horizontal: box_drawing::heavy::HORIZONTAL,
+                vertical: box_drawing::heavy::VERTICAL,
+                down_left: box_drawing::heavy::DOWN_LEFT,
+                up_left: box_drawing::heavy::UP_LEFT,
+                up_horizontal: box_drawing::heavy::UP_HORIZONTAL,
+            }
+        } else {
+            BoxChars {
+                horizontal: box_drawing::light::HORIZONTAL,
+                vertical: box_drawing::light::VERTICAL,
+                down_left: box_drawing::light::DOWN_LEFT,
+                up_left: box_drawing::light::UP_LEFT,
+                up_horizontal: box_drawing::light::UP_HORIZONTAL,
+            }
+        }
     }
 }
 
@@ -59,7 +122,7 @@ fn write_no_decoration(
     addendum: &str,
     _line_width: &Width, // ignored
     text_style: Style,
-    _decoration_style: ansi_term::Style,
+    _decoration: Decoration,
 ) -> std::io::Result<()> {
     if text_style.is_raw {
         writeln!(writer, "{raw_text}")?;
@@ -71,31 +134,24 @@ fn write_no_decoration(
 
 /// Write text to stream, surrounded by a box, leaving the cursor just
 /// beyond the bottom right corner.
-pub fn write_boxed(
+fn write_boxed(
     writer: &mut dyn Write,
     text: &str,
     raw_text: &str,
     addendum: &str,
     _line_width: &Width, // ignored
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let up_left = if decoration_style.is_bold {
-        box_drawing::heavy::UP_LEFT
-    } else {
-        box_drawing::light::UP_LEFT
-    };
     let box_width = ansi::measure_text_width(text);
     write_boxed_partial(
+        writer, text, raw_text, addendum, box_width, text_style, decoration,
+    )?;
+    writeln!(
         writer,
-        text,
-        raw_text,
-        addendum,
-        box_width,
-        text_style,
-        decoration_style,
+        "{}",
+        decoration.style.paint(decoration.chars().up_left)
     )?;
-    writeln!(writer, "{}", decoration_style.paint(up_left))?;
     Ok(())
 }
 
@@ -108,17 +164,11 @@ fn write_boxed_with_underline(
     addendum: &str,
     line_width: &Width,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
     let box_width = ansi::measure_text_width(text);
     write_boxed_with_horizontal_whisker(
-        writer,
-        text,
-        raw_text,
-        addendum,
-        box_width,
-        text_style,
-        decoration_style,
+        writer, text, raw_text, addendum, box_width, text_style, decoration,
     )?;
     let line_width = match *line_width {
         Width::Fixed(n) => n,
@@ -132,7 +182,7 @@ fn write_boxed_with_underline(
             0
         },
         text_style,
-        decoration_style,
+        decoration,
     )?;
     writeln!(writer)?;
     Ok(())
@@ -151,7 +201,7 @@ fn write_underlined(
     addendum: &str,
     line_width: &Width,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Under,
@@ -161,7 +211,7 @@ fn write_underlined(
         addendum,
         line_width,
         text_style,
-        decoration_style,
+        decoration,
     )
 }
 
@@ -172,7 +222,7 @@ fn write_overlined(
     addendum: &str,
     line_width: &Width,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Over,
@@ -182,7 +232,7 @@ fn write_overlined(
         addendum,
         line_width,
         text_style,
-        decoration_style,
+        decoration,
     )
 }
 
@@ -193,7 +243,7 @@ fn write_underoverlined(
     addendum: &str,
     line_width: &Width,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Underover,
@@ -203,7 +253,7 @@ fn write_underoverlined(
         addendum,
         line_width,
         text_style,
-        decoration_style,
+        decoration,
     )
 }
 
@@ -216,7 +266,7 @@ fn _write_under_or_over_lined(
     addendum: &str,
     line_width: &Width,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
     let text_width = ansi::measure_text_width(text);
     let line_width = match *line_width {
@@ -224,7 +274,7 @@ fn _write_under_or_over_lined(
         Width::Variable => text_width,
     };
     let write_line = |writer: &mut dyn Write| -> std::io::Result<()> {
-        write_horizontal_line(writer, line_width, text_style, decoration_style)?;
+        write_horizontal_line(writer, line_width, text_style, decoration)?;
         writeln!(writer)?;
         Ok(())
     };
@@ -248,17 +298,14 @@ fn write_horizontal_line(
     writer: &mut dyn Write,
     width: usize,
     _text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let horizontal = if decoration_style.is_bold {
-        box_drawing::heavy::HORIZONTAL
-    } else {
-        box_drawing::light::HORIZONTAL
-    };
     write!(
         writer,
         "{}",
-        decoration_style.paint(horizontal.repeat(width))
+        decoration
+            .style
+            .paint(decoration.chars().horizontal.repeat(width))
     )
 }
 
@@ -269,23 +316,16 @@ fn write_boxed_with_horizontal_whisker(
     addendum: &str,
     box_width: usize,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let up_horizontal = if decoration_style.is_bold {
-        box_drawing::heavy::UP_HORIZONTAL
-    } else {
-        box_drawing::light::UP_HORIZONTAL
-    };
     write_boxed_partial(
+        writer, text, raw_text, addendum, box_width, text_style, decoration,
+    )?;
+    write!(
         writer,
-        text,
-        raw_text,
-        addendum,
-        box_width,
-        text_style,
-        decoration_style,
+        "{}",
+        decoration.style.paint(decoration.chars().up_horizontal)
     )?;
-    write!(writer, "{}", decoration_style.paint(up_horizontal))?;
     Ok(())
 }
 
@@ -296,27 +336,15 @@ fn write_boxed_partial(
     addendum: &str,
     box_width: usize,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let (horizontal, down_left, vertical) = if decoration_style.is_bold {
-        (
-            box_drawing::heavy::HORIZONTAL,
-            box_drawing::heavy::DOWN_LEFT,
-            box_drawing::heavy::VERTICAL,
-        )
-    } else {
-        (
-            box_drawing::light::HORIZONTAL,
-            box_drawing::light::DOWN_LEFT,
-            box_drawing::light::VERTICAL,
-        )
-    };
-    let horizontal_edge = horizontal.repeat(box_width);
+    let chars = decoration.chars();
+    let horizontal_edge = chars.horizontal.repeat(box_width);
     writeln!(
         writer,
         "{}{}",
-        decoration_style.paint(&horizontal_edge),
-        decoration_style.paint(down_left),
+        decoration.style.paint(&horizontal_edge),
+        decoration.style.paint(chars.down_left),
     )?;
     if text_style.is_raw {
         write!(writer, "{raw_text}")?;
@@ -326,7 +354,7 @@ fn write_boxed_partial(
     write!(
         writer,
         "{}\n{}",
-        decoration_style.paint(vertical),
-        decoration_style.paint(&horizontal_edge),
+        decoration.style.paint(chars.vertical),
+        decoration.style.paint(&horizontal_edge),
     )
 }
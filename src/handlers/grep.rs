@@ -72,10 +72,14 @@ impl<'a> StateMachine<'a> {
                     // Emit context header line
                     (LineType::ContextHeader, true) => handlers::hunk_header::write_hunk_header(
                         &grep_line.code,
-                        &[(grep_line.line_number.unwrap_or(0), 0)],
+                        "",
+                        &handlers::hunk_header::paint_file_path_with_line_number(
+                            Some(grep_line.line_number.unwrap_or(0)),
+                            &grep_line.path,
+                            self.config,
+                        ),
                         &mut self.painter,
                         &self.line,
-                        &grep_line.path,
                         self.config,
                     )?,
                     _ => {
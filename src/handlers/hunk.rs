@@ -5,11 +5,17 @@ use lazy_static::lazy_static;
 use crate::cli;
 use crate::config::{delta_unreachable, Config};
 use crate::delta::{DiffType, InMergeConflict, MergeParents, State, StateMachine};
+use crate::handlers::hunk_header::ParsedHunkHeader;
 use crate::paint::{expand_tabs, prepare, prepare_raw_line};
 use crate::style;
+use crate::suspicious_unicode::{mark_suspicious_unicode, UnicodeWarnings};
 use crate::utils::process::{self, CallingProcess};
 use unicode_segmentation::UnicodeSegmentation;
 
+// The maximum number of context lines read before a hunk header is emitted, see
+// `should_defer_hunk_header_line`.
+const MAX_HUNK_HEADER_CONTEXT_LINES: usize = 20;
+
 // HACK: WordDiff should probably be a distinct top-level line state
 pub fn is_word_diff() -> bool {
     #[cfg(not(test))]
@@ -41,6 +47,113 @@ fn compute_is_word_diff() -> bool {
 }
 
 impl<'a> StateMachine<'a> {
+    /// Whether to delay emitting the hunk header until more lines of the hunk have been read, in
+    /// order to compute its breadcrumb.
+    fn should_defer_hunk_header_line(&self, parsed_hunk_header: &ParsedHunkHeader) -> bool {
+        self.config.hunk_header_breadcrumbs
+            && !self.config.color_only
+            && !is_word_diff()
+            && !parsed_hunk_header.has_code_fragment()
+            && self.hunk_header_context.len() < MAX_HUNK_HEADER_CONTEXT_LINES
+            && matches!(
+                new_line_state(&self.line, &self.raw_line, &self.state, self.config),
+                Some(State::HunkZero(_, _))
+            )
+    }
+
+    /// The leading context lines among the lines held back while the hunk header is deferred,
+    /// which may contain the definitions shown in the breadcrumb, and the line following them.
+    pub fn hunk_header_breadcrumb_context(&self) -> (&[(String, String)], &str) {
+        let n_context_lines = self
+            .hunk_header_context
+            .iter()
+            .take(MAX_HUNK_HEADER_CONTEXT_LINES)
+            .take_while(|(line, raw_line)| {
+                matches!(
+                    new_line_state(line, raw_line, &self.state, self.config),
+                    Some(State::HunkZero(_, _))
+                )
+            })
+            .count();
+        let next_line = match self.hunk_header_context.get(n_context_lines) {
+            Some((line, _)) => line,
+            None => &self.line,
+        };
+        (&self.hunk_header_context[..n_context_lines], next_line)
+    }
+
+    /// Remove the prefix of the current line and prepare it for painting. Structured output is
+    /// written from the text of the line as it is.
+    fn prepare_line(&self, n_parents: usize) -> String {
+        if self.painter.structured_output.is_some() {
+            format!("{}\n", self.line.get(n_parents..).unwrap_or(""))
+        } else {
+            prepare(&self.line, n_parents, self.config)
+        }
+    }
+
+    /// Count a removed line for the file header, the table of contents and the summary.
+    fn count_minus_line(&mut self) {
+        if let Some(deferred_file_header) = &mut self.deferred_file_header {
+            deferred_file_header.count_minus_line();
+        }
+        if let Some(toc) = &mut self.toc {
+            toc.count_minus_line();
+        }
+        if let Some(summary) = &mut self.summary {
+            summary.count_minus_line();
+        }
+    }
+
+    /// Count an added line for the file header, the table of contents and the summary.
+    fn count_plus_line(&mut self) {
+        if let Some(deferred_file_header) = &mut self.deferred_file_header {
+            deferred_file_header.count_plus_line();
+        }
+        if let Some(toc) = &mut self.toc {
+            toc.count_plus_line();
+        }
+        if let Some(summary) = &mut self.summary {
+            summary.count_plus_line();
+        }
+    }
+
+    /// Emit the hunk header line if it has not been emitted yet, followed by the context lines
+    /// which were held back while it was deferred.
+    pub fn emit_pending_hunk_header_line(&mut self) -> std::io::Result<()> {
+        let (diff_type, parsed_hunk_header, line, raw_line) = match &self.state {
+            State::HunkHeader(diff_type, parsed_hunk_header, line, raw_line) => (
+                diff_type.clone(),
+                parsed_hunk_header.clone(),
+                line.clone(),
+                raw_line.clone(),
+            ),
+            _ => return Ok(()),
+        };
+        self.emit_hunk_header_line(&parsed_hunk_header, &line, &raw_line)?;
+        if self.hunk_header_context.is_empty() {
+            return Ok(());
+        }
+        self.state = State::HunkZero(
+            match diff_type {
+                DiffType::Combined(MergeParents::Prefix(prefix), in_merge_conflict) => {
+                    DiffType::Combined(MergeParents::Number(prefix.len()), in_merge_conflict)
+                }
+                diff_type => diff_type,
+            },
+            None,
+        );
+        let (line, raw_line) = (self.line.clone(), self.raw_line.clone());
+        for (context_line, context_raw_line) in std::mem::take(&mut self.hunk_header_context) {
+            self.line = context_line;
+            self.raw_line = context_raw_line;
+            self.handle_hunk_line()?;
+        }
+        self.line = line;
+        self.raw_line = raw_line;
+        Ok(())
+    }
+
     #[inline]
     fn test_hunk_line(&self) -> bool {
         matches!(
@@ -67,6 +180,23 @@ impl<'a> StateMachine<'a> {
         if !self.test_hunk_line() {
             return Ok(false);
         }
+        if matches!(&self.deferred_file_header, Some(header) if header.collapsed) {
+            // The lines of a collapsed file are counted but not shown.
+            self.state = match new_line_state(&self.line, &self.raw_line, &self.state, self.config)
+            {
+                Some(state @ HunkMinus(_, _)) => {
+                    self.count_minus_line();
+                    state
+                }
+                Some(state @ HunkPlus(_, _)) => {
+                    self.count_plus_line();
+                    state
+                }
+                Some(state) => state,
+                None => HunkZero(Unified, None),
+            };
+            return Ok(true);
+        }
         // Don't let the line buffers become arbitrarily large -- if we
         // were to allow that, then for a large deleted/added file we
         // would process the entire file before painting anything.
@@ -75,8 +205,22 @@ impl<'a> StateMachine<'a> {
         {
             self.painter.paint_buffered_minus_and_plus_lines();
         }
-        if let State::HunkHeader(_, parsed_hunk_header, line, raw_line) = &self.state.clone() {
-            self.emit_hunk_header_line(parsed_hunk_header, line, raw_line)?;
+        // The first line of the hunk is the first line of the file if the hunk starts there.
+        let is_first_line = matches!(
+            &self.state,
+            State::HunkHeader(_, parsed_hunk_header, _, _)
+                if parsed_hunk_header.starts_at_first_line() && self.hunk_header_context.is_empty()
+        );
+        self.painter
+            .detect_syntax(self.line.get(1..).unwrap_or_default(), is_first_line);
+        if let State::HunkHeader(_, parsed_hunk_header, _, _) = &self.state {
+            if self.should_defer_hunk_header_line(parsed_hunk_header) {
+                // Leading context lines may contain the definitions shown in the breadcrumb.
+                self.hunk_header_context
+                    .push((self.line.clone(), self.raw_line.clone()));
+                return Ok(true);
+            }
+            self.emit_pending_hunk_header_line()?;
         }
         self.state = match new_line_state(&self.line, &self.raw_line, &self.state, self.config) {
             Some(HunkMinus(diff_type, raw_line)) => {
@@ -85,32 +229,85 @@ impl<'a> StateMachine<'a> {
                     // and flush the line buffers.
                     self.painter.paint_buffered_minus_and_plus_lines();
                 }
+                self.count_minus_line();
                 let n_parents = diff_type.n_parents();
-                let line = prepare(&self.line, n_parents, self.config);
+                if self.file_metadata.is_symlink && self.file_metadata.old_symlink_target.is_none()
+                {
+                    self.file_metadata.old_symlink_target =
+                        self.line.get(n_parents..).map(str::to_string);
+                }
+                let line = self.prepare_line(n_parents);
                 let state = HunkMinus(diff_type, raw_line);
                 self.painter.minus_lines.push((line, state.clone()));
                 state
             }
             Some(HunkPlus(diff_type, raw_line)) => {
                 let n_parents = diff_type.n_parents();
-                let line = prepare(&self.line, n_parents, self.config);
+                if self.file_metadata.is_symlink && self.file_metadata.new_symlink_target.is_none()
+                {
+                    self.file_metadata.new_symlink_target =
+                        self.line.get(n_parents..).map(str::to_string);
+                }
+                let marked_line = if self.config.unicode_warnings != UnicodeWarnings::Off
+                    && raw_line.is_none()
+                    && self.painter.structured_output.is_none()
+                {
+                    mark_suspicious_unicode(&self.line)
+                } else {
+                    None
+                };
+                self.count_plus_line();
+                if let (Some(_), Some(deferred_file_header)) =
+                    (&marked_line, &mut self.deferred_file_header)
+                {
+                    deferred_file_header.n_unicode_warnings += 1;
+                }
+                let (line, unicode_warnings) = match marked_line {
+                    Some((marked_line, ranges)) => {
+                        // The ranges are moved as the prefix is removed and tabs are expanded.
+                        let prepared_offset = |offset: usize| {
+                            prepare(&marked_line[..offset], n_parents, self.config).len() - 1
+                        };
+                        let ranges = ranges
+                            .into_iter()
+                            .map(|range| prepared_offset(range.start)..prepared_offset(range.end))
+                            .collect();
+                        (prepare(&marked_line, n_parents, self.config), ranges)
+                    }
+                    None => (self.prepare_line(n_parents), Vec::new()),
+                };
                 let state = HunkPlus(diff_type, raw_line);
                 self.painter.plus_lines.push((line, state.clone()));
+                self.painter
+                    .plus_line_unicode_warnings
+                    .push(unicode_warnings);
                 state
             }
             Some(HunkZero(diff_type, raw_line)) => {
                 // We are in a zero (unchanged) line, therefore we have just exited a subhunk (a
                 // sequence of consecutive minus (removed) and/or plus (added) lines). Process that
-                // subhunk and flush the line buffers.
-                self.painter.paint_buffered_minus_and_plus_lines();
+                // subhunk and flush the line buffers. If unchanged lines may be folded then they
+                // are buffered as well, and painted together with the next subhunk or at the end
+                // of the hunk.
+                let fold = self.config.fold_unchanged_lines > 0;
+                if !(fold
+                    && self.painter.minus_lines.is_empty()
+                    && self.painter.plus_lines.is_empty())
+                {
+                    self.painter.paint_buffered_minus_and_plus_lines();
+                }
                 let n_parents = if is_word_diff() {
                     0
                 } else {
                     diff_type.n_parents()
                 };
-                let line = prepare(&self.line, n_parents, self.config);
+                let line = self.prepare_line(n_parents);
                 let state = State::HunkZero(diff_type, raw_line);
-                self.painter.paint_zero_line(&line, state.clone());
+                if fold {
+                    self.painter.push_zero_line(line, state.clone());
+                } else {
+                    self.painter.paint_zero_line(line, state.clone());
+                }
                 state
             }
             _ => {
@@ -418,4 +615,126 @@ Date:   Sat Dec 11 17:08:56 2021 -0500
     [31m[-aaa-][m[32m{+bbb+}[m
 "#;
     }
+
+    mod fold_unchanged {
+        use super::*;
+
+        #[test]
+        fn test_fold_unchanged_lines() {
+            DeltaTest::with_args(&[
+                "--fold-unchanged-lines",
+                "3",
+                "--fold-unchanged-context",
+                "1",
+            ])
+            .with_input(DIFF_WITH_LONG_CONTEXT)
+            .expect_after_header(
+                r#"
+                c1
+                c2
+                c3
+                a = 1
+                a = 2
+                c5
+                ⋯ 3 unchanged lines ⋯
+                c9
+                b = 1
+                b = 2
+                c11
+                ⋯ 3 unchanged lines ⋯"#,
+            );
+        }
+
+        #[test]
+        fn test_fold_unchanged_lines_disabled() {
+            DeltaTest::with_args(&[])
+                .with_input(DIFF_WITH_LONG_CONTEXT)
+                .expect_after_header(
+                    r#"
+                c1
+                c2
+                c3
+                a = 1
+                a = 2
+                c5
+                c6
+                c7
+                c8
+                c9
+                b = 1
+                b = 2
+                c11
+                c12
+                c13
+                c14"#,
+                );
+        }
+
+        #[test]
+        fn test_fold_unchanged_lines_in_long_runs() {
+            // The runs are longer than the lines buffered before they are known to be folded.
+            let mut input = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,41 +1,41 @@
+-a = 1
++a = 2
+"
+            .to_string();
+            for i in 2..21 {
+                input.push_str(&format!(" c{}\n", i));
+            }
+            input.push_str("-b = 1\n+b = 2\n");
+            for i in 22..42 {
+                input.push_str(&format!(" c{}\n", i));
+            }
+            DeltaTest::with_args(&[
+                "--fold-unchanged-lines",
+                "3",
+                "--fold-unchanged-context",
+                "1",
+                "--line-numbers",
+            ])
+            .with_input(&input)
+            .expect_after_header(
+                r#"
+                #indent_mark
+                  1 ⋮    │a = 1
+                    ⋮  1 │a = 2
+                  2 ⋮  2 │c2
+                ⋯ 17 unchanged lines ⋯
+                 20 ⋮ 20 │c20
+                 21 ⋮    │b = 1
+                    ⋮ 21 │b = 2
+                 22 ⋮ 22 │c22
+                ⋯ 19 unchanged lines ⋯"#,
+            );
+        }
+
+        const DIFF_WITH_LONG_CONTEXT: &str = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,14 +1,14 @@
+ c1
+ c2
+ c3
+-a = 1
++a = 2
+ c5
+ c6
+ c7
+ c8
+ c9
+-b = 1
++b = 2
+ c11
+ c12
+ c13
+ c14
+";
+    }
 }
@@ -18,17 +18,24 @@
 // src/hunk_header.rs:119: fn write_to_output_buffer( │
 // ───────────────────────────────────────────────────┘
 // ```
+//
+// Alternatively, the file and line-number part is given by the hunk-header-format string.
 
 use std::fmt::Write as FmtWrite;
 
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
+use syntect::parsing::{ParseState, Scope, ScopeStack, ScopeStackOp, SyntaxReference};
 
 use super::draw;
 use crate::config::Config;
 use crate::delta::{self, DiffType, InMergeConflict, MergeParents, State, StateMachine};
-use crate::paint::{self, BgShouldFill, Painter, StyleSectionSpecifier};
-use crate::style::DecorationStyle;
+use crate::format::{self, Placeholder};
+use crate::full_file_highlighting;
+use crate::paint::{self, prepare, BgShouldFill, Painter, StyleSectionSpecifier};
+use crate::style::{DecorationStyle, Style};
+use crate::utils;
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub struct ParsedHunkHeader {
@@ -36,6 +43,43 @@ pub struct ParsedHunkHeader {
     line_numbers_and_hunk_lengths: Vec<(usize, usize)>,
 }
 
+impl ParsedHunkHeader {
+    pub fn has_code_fragment(&self) -> bool {
+        !self.code_fragment.trim().is_empty()
+    }
+
+    /// Whether the hunk starts at the first line of a version of the file.
+    pub fn starts_at_first_line(&self) -> bool {
+        self.line_numbers_and_hunk_lengths
+            .iter()
+            .any(|(line_number, _)| *line_number == 1)
+    }
+}
+
+/// A hunk header whose hunk-header-format refers to counts which are known only at the end of
+/// its file. It is inserted at byte `offset` of the output held back for the file.
+#[derive(Debug)]
+pub struct DeferredHunkHeader {
+    offset: usize,
+    code_fragment: String,
+    breadcrumb: String,
+    file_path: String,
+    line_number: usize,
+    hunk_index: usize,
+    pub n_minus: usize,
+    pub n_plus: usize,
+}
+
+/// The values of the hunk-header-format placeholders. Counts are None if not known yet.
+struct HunkHeaderFields<'a> {
+    hunk_index: usize,
+    hunk_count: Option<usize>,
+    added: Option<usize>,
+    removed: Option<usize>,
+    file_path: &'a str,
+    line_number: usize,
+}
+
 impl<'a> StateMachine<'a> {
     #[inline]
     fn test_hunk_header_line(&self) -> bool {
@@ -52,7 +96,13 @@ impl<'a> StateMachine<'a> {
             return Ok(false);
         }
         let mut handled_line = false;
+        if !self.hunk_header_context.is_empty() {
+            self.emit_pending_hunk_header_line()?;
+        }
         if let Some(parsed_hunk_header) = parse_hunk_header(&self.line) {
+            if let Some(summary) = &mut self.summary {
+                summary.count_hunk();
+            }
             let diff_type = match &self.state {
                 DiffHeader(Combined(MergeParents::Unknown, InMergeConflict::No)) => {
                     // https://git-scm.com/docs/git-diff#_combined_diff_format
@@ -84,6 +134,17 @@ impl<'a> StateMachine<'a> {
         raw_line: &str,
     ) -> std::io::Result<bool> {
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
+        self.hunk_index += 1;
+        self.painter.zero_lines_follow_change = false;
+        if let Some((blob_ids, repo_path)) = self.full_file_to_load.take() {
+            self.painter.full_file_highlighting = full_file_highlighting::load(
+                &blob_ids,
+                &repo_path,
+                self.git_config,
+                &mut self.painter,
+            );
+        }
         self.painter.set_highlighter();
         self.painter.emit()?;
 
@@ -92,12 +153,56 @@ impl<'a> StateMachine<'a> {
             line_numbers_and_hunk_lengths,
         } = parsed_hunk_header;
 
+        let breadcrumb = match &self.state {
+            State::HunkHeader(diff_type, _, _, _)
+                if self.config.hunk_header_breadcrumbs
+                    && !parsed_hunk_header.has_code_fragment() =>
+            {
+                let n_parents = diff_type.n_parents();
+                let (context_lines, next_line) = self.hunk_header_breadcrumb_context();
+                let context_lines: Vec<String> = context_lines
+                    .iter()
+                    .map(|(line, _)| prepare(line, n_parents, self.config))
+                    .collect();
+                compute_breadcrumb(
+                    &context_lines,
+                    &prepare(next_line, n_parents, self.config),
+                    self.painter.syntax,
+                    self.config,
+                )
+            }
+            _ => "".to_string(),
+        };
+
+        if let Some(structured_output) = &mut self.painter.structured_output {
+            structured_output.write_hunk(
+                self.hunk_index,
+                line_numbers_and_hunk_lengths,
+                code_fragment,
+            );
+        }
+
+        if let Some(full_file_highlighting) = &mut self.painter.full_file_highlighting {
+            full_file_highlighting.start_hunk(line_numbers_and_hunk_lengths);
+        }
+
         if self.config.line_numbers {
+            // The path is resolved here rather than when the lines are painted, which may be on
+            // another thread, since it depends on the calling process.
+            let plus_file_absolute_path = if self.config.hyperlinks {
+                utils::path::absolute_path(&self.plus_file, self.config)
+            } else {
+                None
+            };
             self.painter
                 .line_numbers_data
                 .as_mut()
                 .unwrap()
-                .initialize_hunk(line_numbers_and_hunk_lengths, self.plus_file.to_string());
+                .initialize_hunk(
+                    line_numbers_and_hunk_lengths,
+                    self.plus_file.to_string(),
+                    plus_file_absolute_path,
+                );
         }
 
         if self.config.hunk_header_style.is_raw {
@@ -111,22 +216,179 @@ impl<'a> StateMachine<'a> {
                 writeln!(self.painter.writer)?;
             }
 
+            let file_path = if self.plus_file == "/dev/null" {
+                &self.minus_file
+            } else {
+                &self.plus_file
+            };
+            let line_number =
+                line_numbers_and_hunk_lengths[line_numbers_and_hunk_lengths.len() - 1].0;
+            match (
+                &mut self.deferred_file_header,
+                self.painter.writer.held_len(),
+            ) {
+                (Some(deferred_file_header), Some(offset))
+                    if hunk_header_format_needs_counts(self.config) =>
+                {
+                    deferred_file_header.hunk_headers.push(DeferredHunkHeader {
+                        offset,
+                        code_fragment: code_fragment.clone(),
+                        breadcrumb,
+                        file_path: file_path.clone(),
+                        line_number,
+                        hunk_index: self.hunk_index,
+                        n_minus: 0,
+                        n_plus: 0,
+                    });
+                }
+                _ => {
+                    let file_with_line_number = format_file_with_line_number(
+                        &HunkHeaderFields {
+                            hunk_index: self.hunk_index,
+                            hunk_count: None,
+                            added: None,
+                            removed: None,
+                            file_path,
+                            line_number,
+                        },
+                        self.config,
+                    );
+                    write_hunk_header(
+                        code_fragment,
+                        &breadcrumb,
+                        &file_with_line_number,
+                        &mut self.painter,
+                        line,
+                        self.config,
+                    )?;
+                }
+            }
+        };
+        self.painter.start_hunk_painting();
+        self.painter.set_highlighter();
+        Ok(true)
+    }
+
+    /// Insert the deferred hunk headers of a file into `held_output`, the output held back for the
+    /// file, now that the counts they refer to are known.
+    pub fn insert_deferred_hunk_headers(
+        &mut self,
+        held_output: Vec<u8>,
+        hunk_headers: Vec<DeferredHunkHeader>,
+    ) -> std::io::Result<Vec<u8>> {
+        if hunk_headers.is_empty() {
+            return Ok(held_output);
+        }
+        let hunk_count = hunk_headers.len();
+        let mut output = Vec::with_capacity(held_output.len());
+        let mut start = 0;
+        for hunk_header in hunk_headers {
+            output.extend_from_slice(&held_output[start..hunk_header.offset]);
+            start = hunk_header.offset;
+            let file_with_line_number = format_file_with_line_number(
+                &HunkHeaderFields {
+                    hunk_index: hunk_header.hunk_index,
+                    hunk_count: Some(hunk_count),
+                    added: Some(hunk_header.n_plus),
+                    removed: Some(hunk_header.n_minus),
+                    file_path: &hunk_header.file_path,
+                    line_number: hunk_header.line_number,
+                },
+                self.config,
+            );
+            self.painter.set_highlighter();
+            self.painter.writer.hold();
             write_hunk_header(
-                code_fragment,
-                line_numbers_and_hunk_lengths,
+                &hunk_header.code_fragment,
+                &hunk_header.breadcrumb,
+                &file_with_line_number,
                 &mut self.painter,
-                line,
-                if self.plus_file == "/dev/null" {
-                    &self.minus_file
-                } else {
-                    &self.plus_file
-                },
+                "",
                 self.config,
             )?;
+            output.extend(self.painter.writer.release());
+        }
+        output.extend_from_slice(&held_output[start..]);
+        Ok(output)
+    }
+}
+
+lazy_static! {
+    static ref HUNK_HEADER_PLACEHOLDER_REGEX: Regex = format::make_placeholder_regex(&[
+        "hunk_index",
+        "hunk_count",
+        "added",
+        "removed",
+        "file_path",
+        "line_number",
+    ]);
+}
+
+/// Whether hunk-header-format refers to counts which are known only at the end of the file.
+pub fn hunk_header_format_needs_counts(config: &Config) -> bool {
+    !config.color_only
+        && HUNK_HEADER_PLACEHOLDER_REGEX
+            .captures_iter(&config.hunk_header_format)
+            .any(|caps| matches!(&caps[1], "hunk_count" | "added" | "removed"))
+}
+
+/// Return the file and line-number part of the hunk header, as given by hunk-header-format if it
+/// is set, or else by the hunk-header-style attributes.
+fn format_file_with_line_number(fields: &HunkHeaderFields, config: &Config) -> String {
+    if config.hunk_header_format.is_empty() || config.color_only {
+        return paint_file_path_with_line_number(
+            Some(fields.line_number),
+            fields.file_path,
+            config,
+        );
+    }
+    let format_data = format::parse_line_number_format(
+        &config.hunk_header_format,
+        &HUNK_HEADER_PLACEHOLDER_REGEX,
+        false,
+    );
+    let text_style = config.hunk_header_style.ansi_term_style;
+    let mut s = String::new();
+    let mut suffix = "";
+    for placeholder in &format_data {
+        s.push_str(&text_style.paint(placeholder.prefix.as_str()).to_string());
+        let alignment_spec = placeholder.alignment_spec.unwrap_or(format::Align::Left);
+        let width = placeholder.width.unwrap_or(0);
+        let number = |n: Option<usize>| match n {
+            Some(n) => format::pad(n, width, alignment_spec, None),
+            // Unknown counts are left out.
+            None => format::pad("", width, alignment_spec, None),
         };
-        self.painter.set_highlighter();
-        Ok(true)
+        let field = match placeholder.placeholder {
+            Some(Placeholder::Str("hunk_index")) => {
+                Some((number(Some(fields.hunk_index)), text_style))
+            }
+            Some(Placeholder::Str("hunk_count")) => Some((number(fields.hunk_count), text_style)),
+            Some(Placeholder::Str("added")) => Some((number(fields.added), text_style)),
+            Some(Placeholder::Str("removed")) => Some((number(fields.removed), text_style)),
+            Some(Placeholder::Str("file_path")) => Some((
+                format::pad(
+                    fields.file_path,
+                    width,
+                    alignment_spec,
+                    placeholder.precision,
+                ),
+                config.hunk_header_file_style.ansi_term_style,
+            )),
+            Some(Placeholder::Str("line_number")) => Some((
+                number(Some(fields.line_number)),
+                config.hunk_header_line_number_style.ansi_term_style,
+            )),
+            None => None,
+            _ => unreachable!("Unexpected hunk-header-format placeholder"),
+        };
+        if let Some((field, style)) = field {
+            s.push_str(&style.paint(field).to_string());
+        }
+        suffix = placeholder.suffix.as_str();
     }
+    s.push_str(&text_style.paint(suffix).to_string());
+    s
 }
 
 lazy_static! {
@@ -186,12 +448,12 @@ fn write_hunk_header_raw(
     config: &Config,
 ) -> std::io::Result<()> {
     let (mut draw_fn, pad, decoration_ansi_term_style) =
-        draw::get_draw_function(config.hunk_header_style.decoration_style);
+        draw::get_draw_function(config.hunk_header_style.decoration_style, config.monochrome);
     if config.hunk_header_style.decoration_style != DecorationStyle::NoDecoration {
         writeln!(painter.writer)?;
     }
     draw_fn(
-        painter.writer,
+        &mut painter.writer,
         &format!("{}{}", line, if pad { " " } else { "" }),
         &format!("{}{}", raw_line, if pad { " " } else { "" }),
         "",
@@ -204,30 +466,31 @@ fn write_hunk_header_raw(
 
 pub fn write_hunk_header(
     code_fragment: &str,
-    line_numbers_and_hunk_lengths: &[(usize, usize)],
+    breadcrumb: &str,
+    file_with_line_number: &str,
     painter: &mut Painter,
     line: &str,
-    plus_file: &str,
     config: &Config,
 ) -> std::io::Result<()> {
     let (mut draw_fn, _, decoration_ansi_term_style) =
-        draw::get_draw_function(config.hunk_header_style.decoration_style);
-    let line = if config.color_only {
-        line.to_string()
+        draw::get_draw_function(config.hunk_header_style.decoration_style, config.monochrome);
+    let (line, style) = if config.color_only {
+        (line.to_string(), config.hunk_header_style)
     } else if !code_fragment.is_empty() {
-        format!("{code_fragment} ")
+        (format!("{code_fragment} "), config.hunk_header_style)
+    } else if !breadcrumb.is_empty() {
+        (
+            format!(" {breadcrumb} "),
+            config.hunk_header_breadcrumb_style,
+        )
     } else {
-        "".to_string()
+        ("".to_string(), config.hunk_header_style)
     };
 
-    let plus_line_number = line_numbers_and_hunk_lengths[line_numbers_and_hunk_lengths.len() - 1].0;
-    let file_with_line_number =
-        paint_file_path_with_line_number(Some(plus_line_number), plus_file, config);
-
     if !line.is_empty() || !file_with_line_number.is_empty() {
-        write_to_output_buffer(&file_with_line_number, line, painter, config);
+        write_to_output_buffer(file_with_line_number, line, style, painter, config);
         draw_fn(
-            painter.writer,
+            &mut painter.writer,
             &painter.output_buffer,
             &painter.output_buffer,
             "",
@@ -241,7 +504,7 @@ pub fn write_hunk_header(
     Ok(())
 }
 
-fn paint_file_path_with_line_number(
+pub fn paint_file_path_with_line_number(
     line_number: Option<usize>,
     plus_file: &str,
     config: &Config,
@@ -276,6 +539,7 @@ fn paint_file_path_with_line_number(
 fn write_to_output_buffer(
     file_with_line_number: &str,
     line: String,
+    style: Style,
     painter: &mut Painter,
     config: &Config,
 ) {
@@ -288,17 +552,23 @@ fn write_to_output_buffer(
     }
     if !file_with_line_number.is_empty() {
         // The code fragment in "line" adds whitespace, but if only a line number is printed
-        // then the trailing space must be added.
+        // then the trailing space must be added. A hunk-header-format ends as it is written when
+        // nothing follows it.
+        let separator = if !line.is_empty() || config.hunk_header_format.is_empty() {
+            ":"
+        } else {
+            ""
+        };
         let space = if line.is_empty() { " " } else { "" };
         let _ = write!(
             &mut painter.output_buffer,
-            "{file_with_line_number}:{space}",
+            "{file_with_line_number}{separator}{space}",
         );
     }
     if !line.is_empty() {
         painter.syntax_highlight_and_paint_line(
             &line,
-            StyleSectionSpecifier::Style(config.hunk_header_style),
+            StyleSectionSpecifier::Style(style),
             delta::State::HunkHeader(
                 DiffType::Unified,
                 ParsedHunkHeader::default(),
@@ -311,11 +581,120 @@ fn write_to_output_buffer(
     }
 }
 
+lazy_static! {
+    static ref DEFINITION_NAME_SCOPES: Vec<Scope> = [
+        "entity.name.class",
+        "entity.name.enum",
+        "entity.name.function",
+        "entity.name.impl",
+        "entity.name.interface",
+        "entity.name.module",
+        "entity.name.namespace",
+        "entity.name.struct",
+        "entity.name.trait",
+        "entity.name.union",
+    ]
+    .iter()
+    .map(|s| Scope::new(s).unwrap())
+    .collect();
+    static ref DEFINITION_KEYWORD_SCOPES: Vec<Scope> = ["keyword.declaration", "storage.type"]
+        .iter()
+        .map(|s| Scope::new(s).unwrap())
+        .collect();
+}
+
+/// Return the definitions (of functions, classes, modules etc.) among `context_lines` which
+/// enclose `line` according to indentation, joined by the breadcrumb separator. For example
+/// "impl StateMachine › fn handle_hunk_line".
+fn compute_breadcrumb(
+    context_lines: &[String],
+    line: &str,
+    syntax: &SyntaxReference,
+    config: &Config,
+) -> String {
+    let mut parse_state = ParseState::new(syntax);
+    let mut scope_stack = ScopeStack::new();
+    let mut enclosing: Vec<(usize, String)> = Vec::new();
+    for context_line in context_lines {
+        let ops = parse_state
+            .parse_line(context_line, &config.syntax_set)
+            .unwrap_or_default();
+        let definition = find_definition(context_line, &ops, &mut scope_stack);
+        if let Some(indentation) = indentation(context_line) {
+            enclosing.retain(|(i, _)| *i < indentation);
+            if let Some(definition) = definition {
+                enclosing.push((indentation, definition));
+            }
+        }
+    }
+    if let Some(indentation) = indentation(line) {
+        enclosing.retain(|(i, _)| *i < indentation);
+    }
+    enclosing
+        .into_iter()
+        .map(|(_, definition)| definition)
+        .join(&config.hunk_header_breadcrumb_separator)
+}
+
+/// If `line` contains the name of a definition, return it preceded by its keyword, e.g. "fn
+/// main". `scope_stack` is updated by applying `ops`, the scope operations of the line.
+fn find_definition(
+    line: &str,
+    ops: &[(usize, ScopeStackOp)],
+    scope_stack: &mut ScopeStack,
+) -> Option<String> {
+    let has_scope = |scope_stack: &ScopeStack, scopes: &[Scope]| {
+        scope_stack
+            .as_slice()
+            .iter()
+            .any(|s| scopes.iter().any(|scope| scope.is_prefix_of(*s)))
+    };
+    let mut keyword = None;
+    let mut name = String::new();
+    let mut name_is_complete = false;
+    let mut start = 0;
+    for (end, op) in ops
+        .iter()
+        .map(|(i, op)| (*i, Some(op)))
+        .chain(std::iter::once((line.len(), None)))
+    {
+        let text = &line[start..end];
+        if !name_is_complete && !text.trim().is_empty() {
+            if has_scope(scope_stack, &DEFINITION_NAME_SCOPES) {
+                name.push_str(text.trim());
+            } else if !name.is_empty() {
+                name_is_complete = true;
+            } else if has_scope(scope_stack, &DEFINITION_KEYWORD_SCOPES) {
+                keyword = Some(text.trim());
+            }
+        }
+        if let Some(op) = op {
+            let _ = scope_stack.apply(op);
+        }
+        start = end;
+    }
+    match (keyword, name.is_empty()) {
+        (_, true) => None,
+        (Some(keyword), false) => Some(format!("{keyword} {name}")),
+        (None, false) => Some(name),
+    }
+}
+
+/// The number of leading whitespace characters of `line`, or None if it is blank.
+fn indentation(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(line.chars().count() - trimmed.chars().count())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
     use crate::ansi::strip_ansi_codes;
-    use crate::tests::integration_test_utils;
+    use crate::tests::integration_test_utils::{self, DeltaTest};
 
     #[test]
     fn test_parse_hunk_header() {
@@ -487,6 +866,104 @@ pub mod tests {
         assert!(output.contains("@@@2021-12-05"));
     }
 
+    #[test]
+    fn test_hunk_header_breadcrumbs() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--hunk-header-breadcrumbs",
+            "--hunk-header-style",
+            "line-number",
+        ]);
+        let output = strip_ansi_codes(&integration_test_utils::run_delta(
+            HUNK_WITHOUT_CODE_FRAGMENT,
+            &config,
+        ));
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines.contains(&"10: impl Foo › fn bar │"));
+        // The context lines read to compute the breadcrumb are emitted after the header.
+        let header_index = lines.iter().position(|l| l.starts_with("10:")).unwrap();
+        assert_eq!(lines[header_index + 2], "impl Foo {");
+        assert_eq!(lines[header_index + 6], "        x + 2");
+    }
+
+    #[test]
+    fn test_hunk_header_breadcrumbs_leave_scope() {
+        // The first changed line is not inside `fn bar`.
+        let input = HUNK_WITHOUT_CODE_FRAGMENT.replace(
+            "-        x + 1\n+        x + 2\n     }\n",
+            "     }\n+    fn baz() {}\n",
+        );
+        let config = integration_test_utils::make_config_from_args(&[
+            "--hunk-header-breadcrumbs",
+            "--hunk-header-style",
+            "line-number",
+        ]);
+        let output = strip_ansi_codes(&integration_test_utils::run_delta(&input, &config));
+        assert!(output.contains("10: impl Foo │"));
+    }
+
+    #[test]
+    fn test_hunk_header_format() {
+        DeltaTest::with_args(&[
+            "--hunk-header-format",
+            "{file_path} {hunk_index}/{hunk_count} +{added}/-{removed}",
+        ])
+        .with_input(TWO_HUNKS_DIFF)
+        .expect_contains("a.py 1/2 +1/-1: def f(): │")
+        .expect_contains("a.py 2/2 +2/-1: def g(): │");
+    }
+
+    #[test]
+    fn test_hunk_header_format_without_counts() {
+        DeltaTest::with_args(&["--hunk-header-format", "[{hunk_index:>2}] {line_number}"])
+            .with_input(TWO_HUNKS_DIFF)
+            .expect_contains("[ 1] 1: def f(): │")
+            .expect_contains("[ 2] 10: def g(): │");
+    }
+
+    #[test]
+    fn test_hunk_header_format_without_code_fragment() {
+        DeltaTest::with_args(&[
+            "--hunk-header-format",
+            "{file_path}:{line_number} ({hunk_index})",
+        ])
+        .with_input(HUNK_WITHOUT_CODE_FRAGMENT)
+        .expect_contains("src/lib.rs:10 (1) │");
+    }
+
+    const TWO_HUNKS_DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,3 +1,3 @@ def f():
+ x = 1
+-y = 2
++y = 3
+ z = 4
+@@ -10,3 +10,4 @@ def g():
+ a = 1
++b = 2
++c = 2
+-d = 4
+ e = 5
+";
+
+    const HUNK_WITHOUT_CODE_FRAGMENT: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,7 +10,7 @@
+ impl Foo {
+     fn bar(&self) -> u32 {
+         let x = 1;
+-        x + 1
++        x + 2
+     }
+ }
+ 
+";
+
     const GIT_LOG_OUTPUT_WITH_NOT_A_HUNK_HEADER: &str = "\
 @@@2021-12-05
 
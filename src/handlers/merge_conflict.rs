@@ -38,6 +38,10 @@ impl<'a> StateMachine<'a> {
         if self.config.color_only || !self.config.handle_merge_conflicts {
             return Ok(handled_line);
         }
+        if !self.hunk_header_context.is_empty() && self.line.starts_with("++<<<<<<<") {
+            // The lines held back before the hunk header precede the merge conflict.
+            self.emit_pending_hunk_header_line()?;
+        }
 
         match self.state.clone() {
             HunkHeader(Combined(merge_parents, InMergeConflict::No), _, _, _)
@@ -153,16 +157,15 @@ impl<'a> StateMachine<'a> {
                 self.config,
             )?;
             self.painter.emit()?;
-            paint::paint_minus_and_plus_lines(
-                MinusPlus::new(
-                    &self.painter.merge_conflict_lines[Ancestral],
-                    &self.painter.merge_conflict_lines[derived_commit_type],
-                ),
-                &mut self.painter.line_numbers_data,
-                &mut self.painter.highlighter,
-                &mut self.painter.output_buffer,
-                self.config,
+            let lines = MinusPlus::new(
+                self.painter.merge_conflict_lines[Ancestral].clone(),
+                self.painter.merge_conflict_lines[derived_commit_type].clone(),
             );
+            self.painter.paint(paint::PaintOp::MinusAndPlusLines {
+                lines,
+                unicode_warnings: Vec::new(),
+                side_by_side_data: self.painter.current_side_by_side_data(),
+            });
             self.painter.emit()?;
         }
         // write_merge_conflict_decoration("bold ol", &mut self.painter, self.config)?;
@@ -184,7 +187,7 @@ fn write_diff_header(
     config: &config::Config,
 ) -> std::io::Result<()> {
     let (mut draw_fn, pad, decoration_ansi_term_style) =
-        draw::get_draw_function(style.decoration_style);
+        draw::get_draw_function(style.decoration_style, config.monochrome);
     let derived_commit_name = &painter.merge_conflict_commit_names[derived_commit_type];
     let text = if let Some(_ancestral_commit) = &painter.merge_conflict_commit_names[Ancestral] {
         format!(
@@ -197,7 +200,7 @@ fn write_diff_header(
         derived_commit_name.as_deref().unwrap_or("?").to_string()
     };
     draw_fn(
-        painter.writer,
+        &mut painter.writer,
         &text,
         &text,
         "",
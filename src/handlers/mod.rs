@@ -14,6 +14,8 @@ pub mod hunk_header;
 pub mod merge_conflict;
 mod ripgrep_json;
 pub mod submodule;
+pub mod summary;
+pub mod toc;
 
 use crate::delta::{State, StateMachine};
 
@@ -34,7 +36,12 @@ impl<'a> StateMachine<'a> {
         // See https://github.com/dandavison/delta/issues/60#issuecomment-557485242 for a
         // proposal for more robust parsing logic.
 
+        if !self.hunk_header_context.is_empty() {
+            self.emit_pending_hunk_header_line()?;
+        }
         self.painter.paint_buffered_minus_and_plus_lines();
+        self.painter.finish_hunk_painting();
+        self.handle_deferred_file_header_end()?;
         self.state = to_state;
         if self.should_handle() {
             self.painter.emit()?;
@@ -19,6 +19,7 @@ impl<'a> StateMachine<'a> {
     #[inline]
     fn test_submodule_short_line(&self) -> bool {
         matches!(self.state, State::HunkHeader(_, _, _, _))
+            && self.hunk_header_context.is_empty()
             && self.line.starts_with("-Subproject commit ")
             || matches!(self.state, State::SubmoduleShort(_))
                 && self.line.starts_with("+Subproject commit ")
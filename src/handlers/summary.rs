@@ -0,0 +1,203 @@
+// The summary written at the end of the output by --summary.
+//
+// For example
+// ```
+// ───────────────────────────────────────────────┐
+// 3 commits, 4 files, 7 hunks, +120/−35 │
+// ───────────────────────────────────────────────┘
+// src/delta.rs     +80/−20
+// src/config.rs    +30/−10
+// ```
+
+use std::collections::HashMap;
+
+use super::draw;
+use crate::ansi::measure_text_width;
+use crate::config::Config;
+use crate::delta::StateMachine;
+
+// The number of files listed in the summary.
+const MAX_SUMMARY_FILES: usize = 5;
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    n_commits: usize,
+    n_hunks: usize,
+    // The files in order of appearance, with their numbers of added and removed lines.
+    files: Vec<(String, usize, usize)>,
+    file_indexes: HashMap<String, usize>,
+    current_file: Option<usize>,
+}
+
+impl Summary {
+    pub fn count_commit(&mut self) {
+        self.n_commits += 1;
+    }
+
+    /// Count lines from now on for the file at `path`. A file appearing in several commits is
+    /// counted once.
+    pub fn start_file(&mut self, path: &str) {
+        let files = &mut self.files;
+        let index = *self
+            .file_indexes
+            .entry(path.to_string())
+            .or_insert_with(|| {
+                files.push((path.to_string(), 0, 0));
+                files.len() - 1
+            });
+        self.current_file = Some(index);
+    }
+
+    pub fn count_hunk(&mut self) {
+        self.n_hunks += 1;
+    }
+
+    pub fn count_minus_line(&mut self) {
+        if let Some(index) = self.current_file {
+            self.files[index].2 += 1;
+        }
+    }
+
+    pub fn count_plus_line(&mut self) {
+        if let Some(index) = self.current_file {
+            self.files[index].1 += 1;
+        }
+    }
+}
+
+impl<'a> StateMachine<'a> {
+    /// Count lines from now on for the current file, in the summary if requested.
+    pub fn start_file_in_summary(&mut self) {
+        if let Some(summary) = &mut self.summary {
+            summary.start_file(if self.plus_file == "/dev/null" {
+                &self.minus_file
+            } else {
+                &self.plus_file
+            });
+        }
+    }
+
+    /// Write the summary, if requested and if any file was seen.
+    pub fn write_summary(&mut self) -> std::io::Result<()> {
+        let summary = match self.summary.take() {
+            Some(summary) if !summary.files.is_empty() => summary,
+            _ => return Ok(()),
+        };
+        let n_plus: usize = summary.files.iter().map(|(_, n_plus, _)| n_plus).sum();
+        let n_minus: usize = summary.files.iter().map(|(_, _, n_minus)| n_minus).sum();
+        let mut counts = Vec::new();
+        if summary.n_commits > 0 {
+            counts.push(pluralize(summary.n_commits, "commit"));
+        }
+        counts.push(pluralize(summary.files.len(), "file"));
+        counts.push(pluralize(summary.n_hunks, "hunk"));
+        let line = format!(
+            "{}, {}",
+            counts.join(", "),
+            format_line_counts(n_plus, n_minus, self.config)
+        );
+
+        let style = self.config.summary_style;
+        let (mut draw_fn, pad, decoration_ansi_term_style) =
+            draw::get_draw_function(style.decoration_style, self.config.monochrome);
+        writeln!(self.painter.writer)?;
+        draw_fn(
+            &mut self.painter.writer,
+            &format!("{}{}", line, if pad { " " } else { "" }),
+            &format!("{}{}", line, if pad { " " } else { "" }),
+            "",
+            &self.config.decorations_width,
+            style,
+            decoration_ansi_term_style,
+        )?;
+
+        let mut files = summary.files;
+        // The sort is stable, so that files with equal changes stay in order of appearance.
+        files.sort_by_key(|(_, n_plus, n_minus)| std::cmp::Reverse(n_plus + n_minus));
+        files.truncate(MAX_SUMMARY_FILES);
+        let width = files
+            .iter()
+            .map(|(path, _, _)| measure_text_width(path))
+            .max()
+            .unwrap_or(0);
+        for (path, n_plus, n_minus) in files {
+            let padding = " ".repeat(width - measure_text_width(&path));
+            writeln!(
+                self.painter.writer,
+                "{}",
+                style.paint(format!(
+                    "{path}{padding}  {}",
+                    format_line_counts(n_plus, n_minus, self.config)
+                ))
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn pluralize(n: usize, noun: &str) -> String {
+    format!("{n} {noun}{}", if n == 1 { "" } else { "s" })
+}
+
+/// Format the numbers of added and removed lines, e.g. "+3/−1".
+pub fn format_line_counts(n_plus: usize, n_minus: usize, config: &Config) -> String {
+    let minus_sign = if config.monochrome { "-" } else { "−" };
+    format!("+{n_plus}/{minus_sign}{n_minus}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::integration_test_utils::DeltaTest;
+
+    #[test]
+    fn test_summary() {
+        DeltaTest::with_args(&["--summary"])
+            .with_input(TWO_COMMITS_LOG)
+            .expect_contains("2 commits, 2 files, 3 hunks, +4/−1 │")
+            .expect_contains("a.py  +3/−1\nb.py  +1/−0");
+    }
+
+    #[test]
+    fn test_no_summary_by_default() {
+        let output = DeltaTest::with_args(&[]).with_input(TWO_COMMITS_LOG).output;
+        assert!(!output.contains("2 commits"));
+    }
+
+    const TWO_COMMITS_LOG: &str = "\
+commit 1111111111111111111111111111111111111111
+Author: A <a@example.com>
+Date:   Mon Jan 1 00:00:00 2024 +0000
+
+    First
+
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+-x = 1
++x = 2
+ y = 1
+commit 2222222222222222222222222222222222222222
+Author: A <a@example.com>
+Date:   Mon Jan 1 00:00:00 2024 +0000
+
+    Second
+
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,3 @@
+ x = 2
++z = 3
++w = 4
+diff --git a/b.py b/b.py
+new file mode 100644
+index 0000000..2222222
+--- /dev/null
++++ b/b.py
+@@ -0,0 +1 @@
++b = 1
+";
+}
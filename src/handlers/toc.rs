@@ -0,0 +1,170 @@
+// The table of contents written by --toc before the files of each commit.
+//
+// Since delta streams its output, the output for the files of a commit is held back until the
+// commit ends, and then written after the table of contents. If the held back output becomes
+// larger than --toc-max-buffer-size, it is written without a table of contents.
+
+use std::io::Write;
+
+use super::summary;
+use crate::ansi::measure_text_width;
+use crate::delta::{Source, StateMachine};
+use crate::handlers::diff_header::get_file_change_description_from_file_paths;
+
+#[derive(Debug, Default)]
+pub struct TableOfContents {
+    entries: Vec<TocEntry>,
+    // Whether the output was too large to be held back.
+    abandoned: bool,
+}
+
+#[derive(Debug)]
+struct TocEntry {
+    file_pair: (String, String),
+    // The file path with its file event label, e.g. "renamed: a.rs ⟶   b.rs".
+    description: String,
+    n_plus: usize,
+    n_minus: usize,
+}
+
+impl TableOfContents {
+    pub fn count_minus_line(&mut self) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.n_minus += 1;
+        }
+    }
+
+    pub fn count_plus_line(&mut self) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.n_plus += 1;
+        }
+    }
+}
+
+impl<'a> StateMachine<'a> {
+    /// Start holding back output for a table of contents, unless already doing so for this
+    /// commit.
+    pub fn handle_toc_start(&mut self) {
+        if self.config.toc && self.toc.is_none() {
+            self.painter.writer.hold();
+            self.toc = Some(TableOfContents::default());
+        }
+    }
+
+    /// Add the current file to the table of contents, if any.
+    pub fn start_file_in_toc(&mut self) {
+        let toc = match &mut self.toc {
+            Some(toc) => toc,
+            None => return,
+        };
+        let file_pair = (self.minus_file.clone(), self.plus_file.clone());
+        let description = get_file_change_description_from_file_paths(
+            &self.minus_file,
+            &self.plus_file,
+            self.source == Source::DiffUnified,
+            &self.minus_file_event,
+            &self.plus_file_event,
+            self.config,
+        );
+        match toc.entries.last_mut() {
+            // The file was already started, e.g. at its "rename to" line.
+            Some(entry) if entry.file_pair == file_pair => entry.description = description,
+            _ => toc.entries.push(TocEntry {
+                file_pair,
+                description,
+                n_plus: 0,
+                n_minus: 0,
+            }),
+        }
+    }
+
+    /// Stop holding back output, without a table of contents, if too much output has been held
+    /// back.
+    pub fn check_toc_buffer_size(&mut self) -> std::io::Result<()> {
+        if let Some(toc) = &mut self.toc {
+            if !toc.abandoned
+                && self.painter.writer.total_held_len() > self.config.toc_max_buffer_size
+            {
+                toc.abandoned = true;
+                self.painter.emit()?;
+                self.painter.writer.release_outermost()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the table of contents, followed by the output held back since it started.
+    pub fn handle_toc_end(&mut self) -> std::io::Result<()> {
+        let toc = match self.toc.take() {
+            Some(toc) if !toc.abandoned => toc,
+            _ => return Ok(()),
+        };
+        self.painter.emit()?;
+        let held_output = self.painter.writer.release();
+        if toc.entries.len() > 1 {
+            let width = toc
+                .entries
+                .iter()
+                .map(|entry| measure_text_width(&entry.description))
+                .max()
+                .unwrap_or(0);
+            let style = self.config.file_style.ansi_term_style;
+            writeln!(self.painter.writer)?;
+            for entry in &toc.entries {
+                let padding = " ".repeat(width - measure_text_width(&entry.description));
+                writeln!(
+                    self.painter.writer,
+                    "{}{}  {}",
+                    style.paint(&entry.description),
+                    padding,
+                    summary::format_line_counts(entry.n_plus, entry.n_minus, self.config),
+                )?;
+            }
+        }
+        self.painter.writer.write_all(&held_output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::integration_test_utils::DeltaTest;
+
+    #[test]
+    fn test_toc() {
+        DeltaTest::with_args(&["--toc"])
+            .with_input(TWO_FILES_DIFF)
+            .expect_after_skip(
+                1,
+                r#"#partial
+                a.py         +1/−1
+                added: b.py  +1/−0"#,
+            );
+    }
+
+    #[test]
+    fn test_toc_max_buffer_size() {
+        let without_toc = DeltaTest::with_args(&[]).with_input(TWO_FILES_DIFF).output;
+        let with_toc = DeltaTest::with_args(&["--toc", "--toc-max-buffer-size", "20"])
+            .with_input(TWO_FILES_DIFF)
+            .output;
+        assert_eq!(with_toc, without_toc);
+    }
+
+    const TWO_FILES_DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+-x = 1
++x = 2
+ y = 1
+diff --git a/b.py b/b.py
+new file mode 100644
+index 0000000..2222222
+--- /dev/null
++++ b/b.py
+@@ -0,0 +1 @@
++b = 1
+";
+}
@@ -9,15 +9,20 @@ mod edits;
 mod env;
 mod features;
 mod format;
+mod full_file_highlighting;
 mod git_config;
 mod handlers;
 mod minusplus;
 mod options;
+mod output_format;
 mod paint;
 mod parse_style;
 mod parse_styles;
+mod pipeline;
 mod style;
+mod suspicious_unicode;
 mod utils;
+mod whitespace;
 mod wrapping;
 
 mod subcommands;
@@ -83,7 +88,10 @@ fn run_app() -> std::io::Result<i32> {
     );
 
     let subcommand_result = if opt.list_languages {
-        Some(list_languages())
+        Some(list_languages(
+            &opt.computed.syntax_set,
+            &opt.computed.syntax_sources,
+        ))
     } else if opt.list_syntax_themes {
         Some(subcommands::list_syntax_themes::list_syntax_themes())
     } else if opt.show_syntax_themes {
@@ -112,21 +120,34 @@ fn run_app() -> std::io::Result<i32> {
     };
 
     let _show_config = opt.show_config;
-    let config = config::Config::from(opt);
+    let _check_contrast = opt.check_contrast;
+    let (config, git_config) = config::Config::from_opt(opt);
 
     if _show_config {
         let stdout = io::stdout();
         let mut stdout = stdout.lock();
-        subcommands::show_config::show_config(&config, &mut stdout)?;
+        subcommands::show_config::show_config(&config, git_config.as_ref(), &mut stdout)?;
         return Ok(0);
     }
 
+    if _check_contrast {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        return subcommands::check_contrast::check_contrast(&config, &mut stdout);
+    }
+
     let mut output_type =
         OutputType::from_mode(&env, config.paging_mode, config.pager.clone(), &config).unwrap();
     let mut writer = output_type.handle().unwrap();
 
     if let (Some(minus_file), Some(plus_file)) = (&config.minus_file, &config.plus_file) {
-        let exit_code = subcommands::diff::diff(minus_file, plus_file, &config, &mut writer);
+        let exit_code = subcommands::diff::diff(
+            minus_file,
+            plus_file,
+            &config,
+            git_config.as_ref(),
+            &mut writer,
+        );
         return Ok(exit_code);
     }
 
@@ -140,7 +161,13 @@ fn run_app() -> std::io::Result<i32> {
         return Ok(config.error_exit_code);
     }
 
-    if let Err(error) = delta(io::stdin().lock().byte_lines(), &mut writer, &config) {
+    let input = utils::input::InputReader::new(io::stdin());
+    if let Err(error) = delta(
+        input.byte_lines(),
+        &mut writer,
+        &config,
+        git_config.as_ref(),
+    ) {
         match error.kind() {
             ErrorKind::BrokenPipe => return Ok(0),
             _ => eprintln!("{error}"),
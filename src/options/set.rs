@@ -7,6 +7,7 @@ use bat::assets::HighlightingAssets;
 use console::Term;
 
 use crate::cli;
+use crate::color::ColorDepth;
 use crate::config;
 use crate::env::DeltaEnv;
 use crate::errors::*;
@@ -48,7 +49,9 @@ macro_rules! set_options {
                 "no-gitconfig",
                 "dark",
                 "light",
+                "detect-dark-light",
                 "syntax-theme",
+                "syntaxes-dir",
             ]);
             let expected_option_names: HashSet<_> = $expected_option_name_map
                 .values()
@@ -77,6 +80,9 @@ pub fn set_options(
         }
     }
     opt.navigate = opt.navigate || opt.env.navigate.is_some();
+    // See https://no-color.org
+    opt.monochrome =
+        opt.monochrome || matches!(&opt.env.no_color, Some(no_color) if !no_color.is_empty());
     if opt.syntax_theme.is_none() {
         opt.syntax_theme = opt.env.bat_theme.clone();
     }
@@ -98,6 +104,18 @@ pub fn set_options(
     // Set light, dark, and syntax-theme.
     set__light__dark__syntax_theme__options(opt, git_config, arg_matches, &option_names);
 
+    // Light or dark mode and the syntax theme are computed before the other options are set,
+    // since the values of builtin features may depend on them.
+    opt.computed.stdout_is_term = Term::stdout().is_term();
+    theme::set__is_light_mode__syntax_theme__syntax_set(opt, assets);
+    if features.contains(&"syntax-theme-colors".to_string()) {
+        opt.computed.syntax_theme_colors = opt
+            .computed
+            .syntax_theme
+            .as_ref()
+            .map(theme::derive_syntax_theme_colors);
+    }
+
     // HACK: make minus-line styles have syntax-highlighting iff side-by-side.
     if features.contains(&"side-by-side".to_string()) {
         let prefix = "normal ";
@@ -133,7 +151,11 @@ pub fn set_options(
             blame_separator_style,
             blame_timestamp_format,
             blame_timestamp_output_format,
+            collapse_files,
+            collapse_gitattributes,
+            color_depth,
             color_only,
+            colorblind,
             config,
             commit_decoration_style,
             commit_regex,
@@ -143,10 +165,15 @@ pub fn set_options(
             file_added_label,
             file_copied_label,
             file_decoration_style,
+            file_header_format,
             file_modified_label,
             file_removed_label,
             file_renamed_label,
             file_regex_replacement,
+            fold_unchanged_context,
+            fold_unchanged_label,
+            fold_unchanged_lines,
+            fold_unchanged_style,
             right_arrow,
             hunk_label,
             file_style,
@@ -156,7 +183,13 @@ pub fn set_options(
             grep_match_line_style,
             grep_match_word_style,
             grep_separator_symbol,
+            highlight_full_files,
+            highlight_full_files_max_size,
+            hunk_header_breadcrumb_separator,
+            hunk_header_breadcrumb_style,
+            hunk_header_breadcrumbs,
             hunk_header_decoration_style,
+            hunk_header_format,
             hunk_header_file_style,
             hunk_header_line_number_style,
             hunk_header_style,
@@ -167,7 +200,9 @@ pub fn set_options(
             inspect_raw_lines,
             keep_plus_minus_markers,
             line_buffer_size,
+            map_languages,
             map_styles,
+            markdown_collapse_min_lines,
             max_line_distance,
             max_line_length,
             // Hack: minus-style must come before minus-*emph-style because the latter default
@@ -183,8 +218,10 @@ pub fn set_options(
             minus_empty_line_marker_style,
             minus_non_emph_style,
             minus_non_emph_style,
+            monochrome,
             navigate,
             navigate_regex,
+            output_format,
             line_fill_method,
             line_numbers,
             line_numbers_left_format,
@@ -196,6 +233,7 @@ pub fn set_options(
             line_numbers_zero_style,
             pager,
             paging_mode,
+            paint_threads,
             parse_ansi,
             // Hack: plus-style must come before plus-*emph-style because the latter default
             // dynamically to the value of the former.
@@ -207,16 +245,35 @@ pub fn set_options(
             relative_paths,
             show_colors,
             show_themes,
+            show_whitespace,
             side_by_side,
+            side_by_side_min_code_width,
+            side_by_side_mode,
+            side_by_side_ratio,
+            side_by_side_separator,
+            summary,
+            summary_decoration_style,
+            summary_style,
+            syntax_theme_colors,
             wrap_max_lines,
             wrap_right_prefix_symbol,
             wrap_right_percent,
             wrap_right_symbol,
             wrap_left_symbol,
             tab_width,
+            toc,
+            toc_max_buffer_size,
             tokenization_regex,
             true_color,
+            unicode_warning_style,
+            unicode_warnings,
+            whitespace_cr_symbol,
             whitespace_error_style,
+            whitespace_invisible_symbol,
+            whitespace_nbsp_symbol,
+            whitespace_space_symbol,
+            whitespace_style,
+            whitespace_tab_symbol,
             width,
             zero_style
         ],
@@ -229,9 +286,9 @@ pub fn set_options(
     );
 
     // Setting ComputedValues
-    set_widths_and_isatty(opt);
+    set_widths(opt);
     set_true_color(opt);
-    theme::set__is_light_mode__syntax_theme__syntax_set(opt, assets);
+    set_color_depth(opt);
     opt.computed.inspect_raw_lines =
         cli::InspectRawLines::from_str(&opt.inspect_raw_lines).unwrap();
     opt.computed.paging_mode = parse_paging_mode(&opt.paging_mode);
@@ -241,6 +298,11 @@ pub fn set_options(
     // See #274.
     if opt.color_only {
         opt.side_by_side = false;
+        opt.fold_unchanged_lines = 0;
+        opt.show_whitespace = false;
+        opt.summary = false;
+        opt.toc = false;
+        opt.unicode_warnings = "off".to_string();
         opt.file_decoration_style = "none".to_string();
         opt.commit_decoration_style = "none".to_string();
         opt.hunk_header_decoration_style = "none".to_string();
@@ -274,7 +336,7 @@ fn set__light__dark__syntax_theme__options(
     }
     validate_light_and_dark(opt);
     set_options!(
-        [syntax_theme],
+        [detect_dark_light, syntax_theme, syntaxes_dir],
         opt,
         &empty_builtin_features,
         git_config,
@@ -382,9 +444,25 @@ fn gather_features(
     if opt.hyperlinks {
         gather_builtin_features_recursively("hyperlinks", &mut features, builtin_features, opt);
     }
+    if opt.colorblind {
+        gather_builtin_features_recursively("colorblind", &mut features, builtin_features, opt);
+    }
+    // Features gathered earlier take precedence: the derived colors replace the defaults of
+    // line-numbers.
+    if opt.syntax_theme_colors {
+        gather_builtin_features_recursively(
+            "syntax-theme-colors",
+            &mut features,
+            builtin_features,
+            opt,
+        );
+    }
     if opt.line_numbers {
         gather_builtin_features_recursively("line-numbers", &mut features, builtin_features, opt);
     }
+    if opt.monochrome {
+        gather_builtin_features_recursively("monochrome", &mut features, builtin_features, opt);
+    }
     if opt.navigate {
         gather_builtin_features_recursively("navigate", &mut features, builtin_features, opt);
     }
@@ -596,9 +674,8 @@ fn parse_width_specifier(width_arg: &str, terminal_width: usize) -> Result<usize
     Ok(width)
 }
 
-fn set_widths_and_isatty(opt: &mut cli::Opt) {
+fn set_widths(opt: &mut cli::Opt) {
     let term_stdout = Term::stdout();
-    opt.computed.stdout_is_term = term_stdout.is_term();
 
     // If one extra character for e.g. `less --status-column` is required use "-1"
     // as an argument, also see #41, #10, #115 and #727.
@@ -635,7 +712,10 @@ fn set_true_color(opt: &mut cli::Opt) {
     opt.computed.true_color = match opt.true_color.as_ref() {
         "always" => true,
         "never" => false,
-        "auto" => is_truecolor_terminal(&opt.env),
+        // Browsers display 24-bit color.
+        "auto" => {
+            matches!(opt.output_format.as_str(), "html" | "svg") || is_truecolor_terminal(&opt.env)
+        }
         _ => {
             fatal(format!(
                 "Invalid value for --true-color option: {} (valid values are \"always\", \"never\", and \"auto\")",
@@ -645,6 +725,47 @@ fn set_true_color(opt: &mut cli::Opt) {
     };
 }
 
+fn set_color_depth(opt: &mut cli::Opt) {
+    opt.computed.color_depth = match opt.color_depth.as_ref() {
+        "16" => ColorDepth::Ansi16,
+        "256" => ColorDepth::Ansi256,
+        "truecolor" => ColorDepth::TrueColor,
+        "auto" if opt.computed.true_color => ColorDepth::TrueColor,
+        "auto" if is_16_color_terminal(&opt.env) => ColorDepth::Ansi16,
+        "auto" => ColorDepth::Ansi256,
+        _ => {
+            fatal(format!(
+                "Invalid value for --color-depth option: {} (valid values are \"auto\", \"16\", \"256\", and \"truecolor\")",
+                opt.color_depth
+            ));
+        }
+    };
+    opt.computed.true_color = opt.computed.color_depth == ColorDepth::TrueColor;
+}
+
+// Terminals whose terminfo entries have no more than 16 colors, and which are not commonly
+// emulated by terminals with more colors, as "xterm" and "screen" are.
+fn is_16_color_terminal(env: &DeltaEnv) -> bool {
+    match env.term.as_deref() {
+        Some(term) => {
+            matches!(
+                term,
+                "ansi"
+                    | "cons25"
+                    | "cygwin"
+                    | "linux"
+                    | "vt100"
+                    | "vt102"
+                    | "vt220"
+                    | "vt320"
+                    | "xterm-color"
+            ) || term.ends_with("-16color")
+                || term.ends_with("-8color")
+        }
+        None => false,
+    }
+}
+
 fn is_truecolor_terminal(env: &DeltaEnv) -> bool {
     env.colorterm
         .as_ref()
@@ -657,6 +778,8 @@ pub mod tests {
     use std::fs::remove_file;
 
     use crate::cli;
+    use crate::color::ColorDepth;
+    use crate::env::DeltaEnv;
     use crate::tests::integration_test_utils;
     use crate::utils::bat::output::PagingMode;
 
@@ -845,4 +968,54 @@ pub mod tests {
         assert_eq!(parse_width_specifier(" - 12 ", term_width).unwrap(), 0);
         assert_eq!(parse_width_specifier(" 2 - 2 ", term_width).unwrap(), 0);
     }
+
+    #[test]
+    fn test_color_depth() {
+        let color_depth = |term: Option<&str>, colorterm: Option<&str>, args: &[&str]| {
+            let env = DeltaEnv {
+                term: term.map(str::to_string),
+                colorterm: colorterm.map(str::to_string),
+                ..DeltaEnv::default()
+            };
+            let args: Vec<String> = std::iter::once(&"delta")
+                .chain(args)
+                .map(|arg| arg.to_string())
+                .collect();
+            let opt = cli::Opt::from_iter_and_git_config(env, args, None);
+            (opt.computed.color_depth, opt.computed.true_color)
+        };
+        assert_eq!(
+            color_depth(Some("xterm-256color"), None, &[]),
+            (ColorDepth::Ansi256, false)
+        );
+        assert_eq!(
+            color_depth(Some("xterm-256color"), Some("truecolor"), &[]),
+            (ColorDepth::TrueColor, true)
+        );
+        assert_eq!(
+            color_depth(Some("linux"), None, &[]),
+            (ColorDepth::Ansi16, false)
+        );
+        assert_eq!(
+            color_depth(Some("rxvt-16color"), None, &[]),
+            (ColorDepth::Ansi16, false)
+        );
+        assert_eq!(color_depth(None, None, &[]), (ColorDepth::Ansi256, false));
+        assert_eq!(
+            color_depth(Some("linux"), None, &["--true-color", "always"]),
+            (ColorDepth::TrueColor, true)
+        );
+        assert_eq!(
+            color_depth(
+                Some("xterm-256color"),
+                Some("truecolor"),
+                &["--color-depth", "16"]
+            ),
+            (ColorDepth::Ansi16, false)
+        );
+        assert_eq!(
+            color_depth(Some("linux"), None, &["--color-depth", "truecolor"]),
+            (ColorDepth::TrueColor, true)
+        );
+    }
 }
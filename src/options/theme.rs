@@ -4,12 +4,23 @@
 ///     default color choices
 /// This module sets those options. If the light/dark background mode choice is not made explicitly
 /// by the user, it is determined by the classification of the syntax theme into light-background
-/// vs dark-background syntax themes. If the user didn't choose a syntax theme, a dark-background
-/// default is selected.
+/// vs dark-background syntax themes. If the user didn't choose a syntax theme, the terminal is asked
+/// for its background color (see --detect-dark-light), and a dark-background default is selected
+/// if it does not answer.
+use std::time::Duration;
+
 use bat;
 use bat::assets::HighlightingAssets;
+use palette::{FromColor, Lch, Srgb};
+use syntect::highlighting::{Color as SyntectColor, Highlighter, Theme as SyntaxTheme};
+use syntect::parsing::Scope;
 
 use crate::cli;
+use crate::color;
+use crate::fatal;
+use crate::output_format::rgb_to_hex;
+use crate::tests::TESTING;
+use crate::utils::{custom_assets, terminal_background};
 
 #[allow(non_snake_case)]
 pub fn set__is_light_mode__syntax_theme__syntax_set(
@@ -17,19 +28,152 @@ pub fn set__is_light_mode__syntax_theme__syntax_set(
     assets: HighlightingAssets,
 ) {
     let syntax_theme_name_from_bat_theme = &opt.env.bat_theme;
+    let light_mode_arg = opt.light
+        || (!opt.dark
+            && opt.syntax_theme.is_none()
+            && syntax_theme_name_from_bat_theme.is_none()
+            && terminal_has_light_background(opt));
     let (is_light_mode, syntax_theme_name) = get_is_light_mode_and_syntax_theme_name(
         opt.syntax_theme.as_ref(),
         syntax_theme_name_from_bat_theme.as_ref(),
-        opt.light,
+        light_mode_arg,
     );
     opt.computed.is_light_mode = is_light_mode;
 
+    // The user directory is only read in tests that name it.
+    let custom_assets = if opt.syntaxes_dir.is_some() || !TESTING {
+        custom_assets::syntaxes_dir(opt.syntaxes_dir.as_deref())
+            .and_then(|dir| custom_assets::load(&dir, &assets))
+    } else {
+        None
+    };
     opt.computed.syntax_theme = if is_no_syntax_highlighting_syntax_theme_name(&syntax_theme_name) {
         None
     } else {
-        Some(assets.get_theme(&syntax_theme_name).clone())
+        custom_assets
+            .as_ref()
+            .and_then(|custom_assets| custom_assets.themes.get(&syntax_theme_name))
+            .cloned()
+            .or_else(|| Some(assets.get_theme(&syntax_theme_name).clone()))
     };
-    opt.computed.syntax_set = assets.get_syntax_set().unwrap().clone();
+    match custom_assets {
+        Some(custom_assets) => {
+            opt.computed.syntax_set = custom_assets.syntax_set;
+            opt.computed.syntax_sources = custom_assets.syntax_sources;
+        }
+        None => opt.computed.syntax_set = assets.get_syntax_set().unwrap().clone(),
+    }
+}
+
+/// Colors derived from a syntax theme by --syntax-theme-colors, as "#rrggbb" color strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxThemeColors {
+    pub minus_background: String,
+    pub minus_emph_background: String,
+    pub plus_background: String,
+    pub plus_emph_background: String,
+    pub minus_foreground: String,
+    pub plus_foreground: String,
+    pub gutter_foreground: String,
+    pub accent: String,
+}
+
+/// Derive diff colors from the colors of deleted and inserted text in `theme`, blended into its
+/// background color: the backgrounds of removed and added lines keep the hue of those colors, but
+/// take a lightness close to that of the theme background, and a reduced chroma.
+pub fn derive_syntax_theme_colors(theme: &SyntaxTheme) -> SyntaxThemeColors {
+    let highlighter = Highlighter::new(theme);
+    let default_foreground = highlighter.get_default().foreground;
+    let scope_foreground = |scopes: &[&str]| {
+        scopes
+            .iter()
+            .filter_map(|scope| Scope::new(scope).ok())
+            .map(|scope| highlighter.style_for_stack(&[scope]).foreground)
+            .find(|color| *color != default_foreground)
+    };
+    let deleted = scope_foreground(&["diff.deleted", "markup.deleted.diff", "markup.deleted"])
+        .map(to_lch)
+        .unwrap_or_else(|| {
+            to_lch(SyntectColor {
+                r: 0xd7,
+                g: 0x3a,
+                b: 0x49,
+                a: 0xff,
+            })
+        });
+    let inserted = scope_foreground(&["diff.inserted", "markup.inserted.diff", "markup.inserted"])
+        .map(to_lch)
+        .unwrap_or_else(|| {
+            to_lch(SyntectColor {
+                r: 0x28,
+                g: 0xa7,
+                b: 0x45,
+                a: 0xff,
+            })
+        });
+
+    let settings = &theme.settings;
+    let background = to_lch(settings.background.unwrap_or(SyntectColor::BLACK));
+    let foreground = to_lch(settings.foreground.unwrap_or(default_foreground));
+    // Shade away from the background: lighter on dark backgrounds, darker on light ones.
+    let direction = if background.l < 50.0 { 1.0 } else { -1.0 };
+    let shade = |color: Lch, lightness_offset: f32, chroma_factor: f32| {
+        to_hex(Lch::new(
+            background.l + direction * lightness_offset,
+            color.chroma * chroma_factor,
+            color.hue,
+        ))
+    };
+    let gutter_foreground = match settings.gutter_foreground {
+        Some(color) => rgb_to_hex(color.r, color.g, color.b),
+        None => to_hex(Lch::new(
+            (background.l + foreground.l) / 2.0,
+            0.0,
+            foreground.hue,
+        )),
+    };
+    SyntaxThemeColors {
+        minus_background: shade(deleted, 8.0, 0.3),
+        minus_emph_background: shade(deleted, 18.0, 0.55),
+        plus_background: shade(inserted, 8.0, 0.3),
+        plus_emph_background: shade(inserted, 18.0, 0.55),
+        minus_foreground: to_hex(deleted),
+        plus_foreground: to_hex(inserted),
+        accent: settings
+            .accent
+            .map(|color| rgb_to_hex(color.r, color.g, color.b))
+            .unwrap_or_else(|| gutter_foreground.clone()),
+        gutter_foreground,
+    }
+}
+
+fn to_lch(color: SyntectColor) -> Lch {
+    Lch::from_color(Srgb::new(color.r, color.g, color.b).into_format::<f32>())
+}
+
+fn to_hex(color: Lch) -> String {
+    // Colors outside the sRGB gamut are clamped.
+    let color = Srgb::from_color(color).into_format::<u8>();
+    rgb_to_hex(color.red, color.green, color.blue)
+}
+
+/// Return true if the terminal reports a background color whose lightness is above the middle
+/// gray, see --detect-dark-light.
+fn terminal_has_light_background(opt: &cli::Opt) -> bool {
+    let should_query = match opt.detect_dark_light.as_ref() {
+        "always" => true,
+        "auto" => opt.computed.stdout_is_term && !TESTING,
+        "never" => false,
+        _ => fatal(format!(
+            "Invalid value for --detect-dark-light option: {} (valid values are \"auto\", \"always\", and \"never\")",
+            opt.detect_dark_light
+        )),
+    };
+    should_query
+        && matches!(
+            terminal_background::query_background_color(Duration::from_millis(100)),
+            Some(rgb) if color::rgb_to_lab(rgb)[0] > 50.0
+        )
 }
 
 pub fn is_light_syntax_theme(theme: &str) -> bool {
@@ -100,7 +244,7 @@ fn get_is_light_mode_and_syntax_theme_name(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::color;
+    use crate::color::{self, ColorDepth};
     use crate::tests::integration_test_utils;
 
     // TODO: Test influence of BAT_THEME env var. E.g. see utils::process::tests::FakeParentArgs.
@@ -148,6 +292,11 @@ mod tests {
                 args.push(syntax_theme);
             }
             let is_true_color = true;
+            let color_depth = if is_true_color {
+                ColorDepth::TrueColor
+            } else {
+                ColorDepth::Ansi256
+            };
             if is_true_color {
                 args.push("--true-color");
                 args.push("always");
@@ -185,28 +334,25 @@ mod tests {
                 config.minus_style.ansi_term_style.background.unwrap(),
                 color::get_minus_background_color_default(
                     expected_mode == Mode::Light,
-                    is_true_color
+                    color_depth
                 )
             );
             assert_eq!(
                 config.minus_emph_style.ansi_term_style.background.unwrap(),
                 color::get_minus_emph_background_color_default(
                     expected_mode == Mode::Light,
-                    is_true_color
+                    color_depth
                 )
             );
             assert_eq!(
                 config.plus_style.ansi_term_style.background.unwrap(),
-                color::get_plus_background_color_default(
-                    expected_mode == Mode::Light,
-                    is_true_color
-                )
+                color::get_plus_background_color_default(expected_mode == Mode::Light, color_depth)
             );
             assert_eq!(
                 config.plus_emph_style.ansi_term_style.background.unwrap(),
                 color::get_plus_emph_background_color_default(
                     expected_mode == Mode::Light,
-                    is_true_color
+                    color_depth
                 )
             );
         }
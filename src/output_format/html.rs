@@ -0,0 +1,564 @@
+// The HTML output written by --output-format=html.
+//
+// Hunk lines are painted as HTML in the paint path (see `paint_lines`): each line is a <div>
+// element with classes derived from its state, containing <span> elements for its line numbers,
+// its prefix and its style sections, which carry the styles as inline CSS. The rest of delta's
+// output, e.g. file and hunk headers, is ANSI-styled text, which `HtmlWriter` converts into <pre>
+// elements: styled text becomes <span> elements and OSC 8 hyperlinks become <a> elements.
+// In side-by-side mode, the lines are the cells of table rows instead, whose long lines wrap.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use itertools::Itertools;
+use syntect::highlighting::Style as SyntectStyle;
+
+use crate::ansi::{AnsiElementIterator, Element};
+use crate::config::Config;
+use crate::delta::State;
+use crate::features::line_numbers::{self, LineNumbersData};
+use crate::features::side_by_side::{Left, LeftRight, PanelSide, Right};
+use crate::output_format::{color_to_hex, escape_xml, rgb_to_hex};
+use crate::paint::superimpose_style_sections::superimpose_style_sections;
+use crate::paint::{painted_prefix, BgFillMethod, BgShouldFill, LineSections, Painter};
+use crate::style::Style;
+use crate::whitespace;
+
+/// The prefix of a line of output which has been painted as HTML, and which `HtmlWriter` writes as
+/// it is rather than converting it. It is an OSC sequence which terminals do not use.
+pub const HTML_LINE_MARKER: &str = "\x1b]delta-html\x07";
+
+pub struct HtmlWriter<'a> {
+    writer: &'a mut dyn Write,
+    // Bytes written since the last newline.
+    pending: Vec<u8>,
+    converter: AnsiToHtml,
+    // Whether converted lines are being written in a <pre> element.
+    in_pre: bool,
+}
+
+impl<'a> HtmlWriter<'a> {
+    /// Write the start of the HTML page to `writer`.
+    pub fn new(writer: &'a mut dyn Write, config: &Config) -> io::Result<Self> {
+        let mut body_style = String::new();
+        if let Some(settings) = config.syntax_theme.as_ref().map(|theme| &theme.settings) {
+            if let Some(color) = settings.background {
+                write!(
+                    body_style,
+                    " background-color: {};",
+                    rgb_to_hex(color.r, color.g, color.b)
+                )
+                .unwrap();
+            }
+            if let Some(color) = settings.foreground {
+                write!(
+                    body_style,
+                    " color: {};",
+                    rgb_to_hex(color.r, color.g, color.b)
+                )
+                .unwrap();
+            }
+        }
+        writeln!(
+            writer,
+            "\
+<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>delta</title>
+<style>
+body {{{body_style} font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; line-height: 1.3; }}
+pre {{ font-family: inherit; margin: 0; }}
+.delta-line {{ white-space: pre; min-height: 1.3em; }}
+table.delta-side-by-side {{ width: 100%; table-layout: fixed; border-collapse: collapse; }}
+table.delta-side-by-side td {{ width: 50%; padding: 0; vertical-align: top; white-space: pre-wrap; overflow-wrap: anywhere; }}
+a {{ color: inherit; }}
+</style>
+</head>
+<body>"
+        )?;
+        Ok(Self {
+            writer,
+            pending: Vec::new(),
+            converter: AnsiToHtml::default(),
+            in_pre: false,
+        })
+    }
+
+    /// Write any incomplete last line and the end of the HTML page.
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.write_line(&String::from_utf8_lossy(&line))?;
+        }
+        self.end_pre()?;
+        writeln!(self.writer, "</body>\n</html>")?;
+        self.writer.flush()
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if let Some(html) = line.strip_prefix(HTML_LINE_MARKER) {
+            self.end_pre()?;
+            return writeln!(self.writer, "{html}");
+        }
+        let mut html = String::new();
+        if !self.in_pre {
+            html.push_str("<pre>");
+            self.in_pre = true;
+        }
+        self.converter.convert(line, &mut html);
+        writeln!(self.writer, "{html}")
+    }
+
+    fn end_pre(&mut self) -> io::Result<()> {
+        if self.in_pre {
+            let mut html = String::new();
+            self.converter.end_hyperlink(&mut html);
+            writeln!(self.writer, "{html}</pre>")?;
+            self.in_pre = false;
+        }
+        Ok(())
+    }
+}
+
+impl Write for HtmlWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(i) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=i).collect();
+            self.write_line(&String::from_utf8_lossy(&line[..i]))?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// The conversion of ANSI-styled text to HTML, whose state may carry over from a line to the next.
+#[derive(Default)]
+struct AnsiToHtml {
+    // The style in effect.
+    style: ansi_term::Style,
+    in_hyperlink: bool,
+}
+
+impl AnsiToHtml {
+    fn convert(&mut self, text: &str, html: &mut String) {
+        // Git writes a reset as an SGR sequence without parameters, which the ANSI parser skips.
+        let text = text.replace("\x1b[m", "\x1b[0m");
+        for element in AnsiElementIterator::new(&text) {
+            match element {
+                Element::Sgr(style, _, _) => self.style = style,
+                Element::Osc(start, end) => {
+                    if let Some(url) = osc8_hyperlink_url(&text[start..end]) {
+                        self.end_hyperlink(html);
+                        self.in_hyperlink = !url.is_empty();
+                        if self.in_hyperlink {
+                            write!(html, "<a href=\"{}\">", escape_xml(url)).unwrap();
+                        }
+                    }
+                }
+                Element::Text(start, end) => push_span(html, None, &self.style, &text[start..end]),
+                Element::Csi(_, _) | Element::Esc(_, _) => {}
+            }
+        }
+    }
+
+    fn end_hyperlink(&mut self, html: &mut String) {
+        if self.in_hyperlink {
+            html.push_str("</a>");
+            self.in_hyperlink = false;
+        }
+    }
+}
+
+/// Paint hunk lines as HTML to `output_buffer`: this is the counterpart of `Painter::paint_lines`.
+#[allow(clippy::too_many_arguments)]
+pub fn paint_lines<'a>(
+    lines: &'a [(String, State)],
+    syntax_style_sections: &[LineSections<'a, SyntectStyle>],
+    diff_style_sections: &[LineSections<'a, Style>],
+    lines_have_homolog: &[bool],
+    output_buffer: &mut String,
+    config: &Config,
+    line_numbers_data: &mut Option<&mut LineNumbersData>,
+    background_color_extends_to_terminal_width: BgShouldFill,
+) {
+    for ((((_, state), syntax_sections), diff_sections), &line_has_homolog) in lines
+        .iter()
+        .zip_eq(syntax_style_sections)
+        .zip_eq(diff_style_sections)
+        .zip_eq(lines_have_homolog)
+    {
+        output_buffer.push_str(HTML_LINE_MARKER);
+        paint_line(
+            output_buffer,
+            "div",
+            syntax_sections,
+            diff_sections,
+            state,
+            line_has_homolog,
+            line_numbers_data,
+            None,
+            background_color_extends_to_terminal_width,
+            config,
+        );
+        output_buffer.push('\n');
+    }
+}
+
+/// Paint removed and added lines as the rows of a table, with the removed lines in the left
+/// column and the added lines aligned with them in the right column: this is the counterpart of
+/// `side_by_side::paint_minus_and_plus_lines_side_by_side`. Each row is a table of its own, and
+/// the columns of all of them have the same width.
+#[allow(clippy::too_many_arguments)]
+pub fn paint_minus_and_plus_lines_side_by_side(
+    lines: LeftRight<&Vec<(String, State)>>,
+    syntax_sections: LeftRight<Vec<LineSections<SyntectStyle>>>,
+    diff_sections: LeftRight<Vec<LineSections<Style>>>,
+    lines_have_homolog: LeftRight<Vec<bool>>,
+    line_alignment: Vec<(Option<usize>, Option<usize>)>,
+    line_numbers_data: &mut Option<LineNumbersData>,
+    output_buffer: &mut String,
+    config: &Config,
+) {
+    for (minus_line_index, plus_line_index) in line_alignment {
+        output_buffer.push_str(HTML_LINE_MARKER);
+        output_buffer.push_str(SIDE_BY_SIDE_ROW_START);
+        for (panel_side, line_index) in [(Left, minus_line_index), (Right, plus_line_index)] {
+            match line_index {
+                Some(i) => paint_line(
+                    output_buffer,
+                    "td",
+                    &syntax_sections[panel_side][i],
+                    &diff_sections[panel_side][i],
+                    &lines[panel_side][i].1,
+                    lines_have_homolog[panel_side][i],
+                    &mut line_numbers_data.as_mut(),
+                    Some(panel_side),
+                    BgShouldFill::With(BgFillMethod::Spaces),
+                    config,
+                ),
+                None => output_buffer.push_str("<td class=\"delta-line delta-empty\"></td>"),
+            }
+        }
+        output_buffer.push_str(SIDE_BY_SIDE_ROW_END);
+        output_buffer.push('\n');
+    }
+}
+
+/// Paint an unchanged line in both columns of a table row, see
+/// [`paint_minus_and_plus_lines_side_by_side`].
+pub fn paint_zero_line_side_by_side<'a>(
+    line: &'a (String, State),
+    syntax_sections: &[(SyntectStyle, &'a str)],
+    diff_sections: &[(Style, &'a str)],
+    output_buffer: &mut String,
+    config: &Config,
+    line_numbers_data: &mut Option<&mut LineNumbersData>,
+) {
+    output_buffer.push_str(HTML_LINE_MARKER);
+    output_buffer.push_str(SIDE_BY_SIDE_ROW_START);
+    for panel_side in [Left, Right] {
+        paint_line(
+            output_buffer,
+            "td",
+            syntax_sections,
+            diff_sections,
+            &line.1,
+            false,
+            line_numbers_data,
+            Some(panel_side),
+            BgShouldFill::With(BgFillMethod::Spaces),
+            config,
+        );
+    }
+    output_buffer.push_str(SIDE_BY_SIDE_ROW_END);
+    output_buffer.push('\n');
+}
+
+const SIDE_BY_SIDE_ROW_START: &str = "<table class=\"delta-side-by-side\"><tr>";
+const SIDE_BY_SIDE_ROW_END: &str = "</tr></table>";
+
+/// Paint a hunk line as an HTML `element` with classes derived from its state. In a side-by-side
+/// panel, only the line number of that panel is shown.
+#[allow(clippy::too_many_arguments)]
+fn paint_line(
+    output_buffer: &mut String,
+    element: &str,
+    syntax_sections: &[(SyntectStyle, &str)],
+    diff_sections: &[(Style, &str)],
+    state: &State,
+    line_has_homolog: bool,
+    line_numbers_data: &mut Option<&mut LineNumbersData>,
+    side_by_side_panel: Option<PanelSide>,
+    background_color_extends_to_terminal_width: BgShouldFill,
+    config: &Config,
+) {
+    let class = match state {
+        State::HunkMinus(_, _) | State::HunkMinusWrapped => "delta-line delta-minus",
+        State::HunkPlus(_, _) | State::HunkPlusWrapped => "delta-line delta-plus",
+        _ => "delta-line delta-zero",
+    };
+    write!(output_buffer, "<{element} class=\"{class}\"").unwrap();
+    let (bg_fill_mode, fill_style) = Painter::get_should_right_fill_background_color_and_fill_style(
+        diff_sections,
+        Some(line_has_homolog),
+        state,
+        background_color_extends_to_terminal_width,
+        config,
+    );
+    if bg_fill_mode.is_some() {
+        write!(
+            output_buffer,
+            " style=\"{}\"",
+            css_style(&fill_style.ansi_term_style)
+        )
+        .unwrap();
+    }
+    output_buffer.push('>');
+
+    if let Some(line_numbers_data) = line_numbers_data.as_mut() {
+        // An unchanged line is painted in both panels, but counted once.
+        let increment =
+            !(side_by_side_panel == Some(Left) && matches!(state, State::HunkZero(_, _)));
+        if let Some((line_numbers, styles)) =
+            line_numbers::linenumbers_and_styles(line_numbers_data, state, config, increment)
+        {
+            let line_numbers = line_numbers::format_and_paint_line_numbers(
+                line_numbers_data,
+                side_by_side_panel,
+                styles,
+                line_numbers,
+                config,
+            );
+            push_ansi_span(
+                output_buffer,
+                "delta-line-numbers",
+                &ansi_term::ANSIStrings(&line_numbers).to_string(),
+            );
+        }
+    }
+    if let Some(prefix) = painted_prefix(state.clone(), config) {
+        push_ansi_span(output_buffer, "delta-prefix", &prefix.to_string());
+    }
+
+    let superimposed = superimpose_style_sections(
+        syntax_sections,
+        diff_sections,
+        config.color_depth,
+        config.null_syntect_style,
+    );
+    let show_whitespace = whitespace::is_shown(state, config);
+    let changed_ranges = if show_whitespace {
+        whitespace::changed_ranges(diff_sections, state)
+    } else {
+        Vec::new()
+    };
+    let mut offset = 0;
+    for (section_style, text) in &superimposed {
+        let class = Some("delta-emph").filter(|_| section_style.is_emph);
+        if show_whitespace {
+            for painted in whitespace::paint_showing_whitespace(
+                text,
+                offset,
+                section_style.ansi_term_style,
+                &changed_ranges,
+                config,
+            ) {
+                push_span(output_buffer, class, painted.style_ref(), &painted);
+            }
+        } else {
+            push_span(output_buffer, class, &section_style.ansi_term_style, text);
+        }
+        offset += text.len();
+    }
+    write!(output_buffer, "</{element}>").unwrap();
+}
+
+/// Write `text` as a <span> element with `class` and `style`, or as it is if it has neither.
+fn push_span(html: &mut String, class: Option<&str>, style: &ansi_term::Style, text: &str) {
+    let text = text.trim_end_matches('\n');
+    if text.is_empty() {
+        return;
+    }
+    let css = css_style(style);
+    if class.is_none() && css.is_empty() {
+        html.push_str(&escape_xml(text));
+        return;
+    }
+    html.push_str("<span");
+    if let Some(class) = class {
+        write!(html, " class=\"{class}\"").unwrap();
+    }
+    if !css.is_empty() {
+        write!(html, " style=\"{css}\"").unwrap();
+    }
+    write!(html, ">{}</span>", escape_xml(text)).unwrap();
+}
+
+/// Write ANSI-styled `text` converted to HTML, in a <span> element with `class`.
+fn push_ansi_span(html: &mut String, class: &str, text: &str) {
+    write!(html, "<span class=\"{class}\">").unwrap();
+    let mut converter = AnsiToHtml::default();
+    converter.convert(text, html);
+    converter.end_hyperlink(html);
+    html.push_str("</span>");
+}
+
+/// Return the URL of an OSC 8 hyperlink sequence, which is empty at the end of the link.
+fn osc8_hyperlink_url(sequence: &str) -> Option<&str> {
+    let sequence = sequence.strip_prefix("\x1b]8;")?;
+    let sequence = sequence
+        .strip_suffix("\x1b\\")
+        .or_else(|| sequence.strip_suffix('\x1b'))
+        .or_else(|| sequence.strip_suffix('\x07'))
+        .unwrap_or(sequence);
+    sequence.split_once(';').map(|(_, url)| url)
+}
+
+fn css_style(style: &ansi_term::Style) -> String {
+    let (foreground, background) = if style.is_reverse {
+        (style.background, style.foreground)
+    } else {
+        (style.foreground, style.background)
+    };
+    let mut declarations = Vec::new();
+    if let Some(color) = foreground {
+        declarations.push(format!("color: {}", color_to_hex(color)));
+    }
+    if let Some(color) = background {
+        declarations.push(format!("background-color: {}", color_to_hex(color)));
+    }
+    if style.is_bold {
+        declarations.push("font-weight: bold".to_string());
+    }
+    if style.is_dimmed {
+        declarations.push("opacity: 0.6".to_string());
+    }
+    if style.is_italic {
+        declarations.push("font-style: italic".to_string());
+    }
+    match (style.is_underline, style.is_strikethrough) {
+        (true, true) => declarations.push("text-decoration: underline line-through".to_string()),
+        (true, false) => declarations.push("text-decoration: underline".to_string()),
+        (false, true) => declarations.push("text-decoration: line-through".to_string()),
+        (false, false) => {}
+    }
+    if style.is_hidden {
+        declarations.push("visibility: hidden".to_string());
+    }
+    declarations.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::{make_config_from_args, DeltaTest};
+
+    #[test]
+    fn test_html_output() {
+        let output = DeltaTest::with_args(&["--output-format", "html"])
+            .with_input(DIFF)
+            .output;
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.ends_with("</div>\n</body>\n</html>\n"));
+        assert!(output.contains("<pre>"));
+        assert!(output.contains("a.rs"));
+        assert!(output.contains("&lt;T&gt;"));
+        assert!(!output.contains('\x1b'));
+        let lines: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("<div"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("<div class=\"delta-line delta-minus\""));
+        assert!(lines[1].starts_with("<div class=\"delta-line delta-plus\""));
+        assert!(lines[0].contains("<span class=\"delta-emph\" style=\""));
+        assert!(lines[0].contains(">f</span>"));
+        assert!(lines[1].contains(">g</span>"));
+    }
+
+    #[test]
+    fn test_html_output_line_numbers() {
+        let output = DeltaTest::with_args(&["--output-format", "html", "--line-numbers"])
+            .with_input(DIFF)
+            .output;
+        let lines: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("<div"))
+            .collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            assert!(line.contains("<span class=\"delta-line-numbers\">"));
+            assert!(line.contains(">  1 </span>"));
+        }
+    }
+
+    #[test]
+    fn test_html_output_side_by_side() {
+        let output = DeltaTest::with_args(&["--output-format", "html", "--side-by-side"])
+            .with_input(
+                "\
+diff --git a/a.rs b/a.rs
+index 1111111..2222222 100644
+--- a/a.rs
++++ b/a.rs
+@@ -1,2 +1,3 @@
+ fn f() {}
+-fn g() {}
++fn h() {}
++fn i() {}
+",
+            )
+            .output;
+        let rows: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with("<table class=\"delta-side-by-side\">"))
+            .collect();
+        assert_eq!(rows.len(), 3);
+        for row in &rows {
+            assert_eq!(row.matches("<td class=\"delta-line").count(), 2, "{}", row);
+            assert!(row.ends_with("</tr></table>"), "{}", row);
+        }
+        assert_eq!(rows[0].matches(">  1 </span>").count(), 2, "{}", rows[0]);
+        let cells: Vec<&str> = rows[1].split("<td").skip(1).collect();
+        assert!(cells[0].contains("delta-minus") && cells[0].contains(">  2 </span>"));
+        assert!(cells[1].contains("delta-plus") && cells[1].contains(">  2 </span>"));
+        let cells: Vec<&str> = rows[2].split("<td").skip(1).collect();
+        assert!(cells[0].contains("delta-empty"), "{}", cells[0]);
+        assert!(cells[1].contains(">  3 </span>"), "{}", cells[1]);
+    }
+
+    #[test]
+    fn test_html_writer_styles_and_hyperlinks() {
+        let config = make_config_from_args(&["--syntax-theme", "none"]);
+        let mut output = Vec::new();
+        let mut writer = HtmlWriter::new(&mut output, &config).unwrap();
+        writeln!(
+            writer,
+            "\x1b[1;31mred\x1b[0m \x1b]8;;file:///a.rs\x1b\\a.rs\x1b]8;;\x1b\\ \x1b[48;5;22m+\x1b[m x"
+        )
+        .unwrap();
+        writer.finish().unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(
+            "<span style=\"color: #cd0000; font-weight: bold\">red</span> \
+             <a href=\"file:///a.rs\">a.rs</a> \
+             <span style=\"background-color: #005f00\">+</span> x\n"
+        ));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.rs b/a.rs
+index 1111111..2222222 100644
+--- a/a.rs
++++ b/a.rs
+@@ -1 +1 @@
+-fn f<T>() {}
++fn g<T>() {}
+";
+}
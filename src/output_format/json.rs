@@ -0,0 +1,370 @@
+// The JSON Lines output written by --output-format=json.
+//
+// Instead of painted text, delta writes one JSON object per line for each commit, file, hunk,
+// unchanged line, and group of removed and added lines. For example
+// ```
+// {"type":"file","minus_file":"a.py","plus_file":"a.py","minus_file_event":"change","plus_file_event":"change"}
+// {"type":"hunk","hunk_index":1,"minus_start":1,"minus_count":2,"plus_start":1,"plus_count":2,"code_fragment":""}
+// {"type":"changes","minus":[{"line_number":1,"text":"x = 1","sections":[...]}],"plus":[...],"alignment":[[0,0]]}
+// {"type":"context","minus_line_number":2,"plus_line_number":2,"text":"y = 1"}
+// ```
+// The sections of a removed or added line are the slices of the line, with `emph` set for the
+// slices that were edited, as inferred by the same logic used for emphasis in painted output. The
+// alignment pairs the indexes of removed and added lines which are edits of each other, with
+// `null` for a line that has no such counterpart.
+
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::delta::State;
+use crate::edits;
+use crate::handlers::diff_header::FileEvent;
+use crate::output_format::StructuredOutput;
+
+pub struct JsonWriter<'a> {
+    writer: &'a mut dyn Write,
+    // JSON lines not yet written.
+    buffer: String,
+    // The current file, which is written when its first hunk starts, since the file events may
+    // still be updated until then.
+    file: Option<FileRecord>,
+    minus_line_number: usize,
+    plus_line_number: usize,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record<'r> {
+    Commit {
+        line: &'r str,
+    },
+    File(&'r FileRecord),
+    Hunk {
+        hunk_index: usize,
+        minus_start: usize,
+        minus_count: usize,
+        plus_start: usize,
+        plus_count: usize,
+        code_fragment: &'r str,
+    },
+    Context {
+        minus_line_number: usize,
+        plus_line_number: usize,
+        text: &'r str,
+    },
+    Changes {
+        minus: Vec<Line<'r>>,
+        plus: Vec<Line<'r>>,
+        alignment: Vec<(Option<usize>, Option<usize>)>,
+    },
+}
+
+#[derive(Serialize)]
+struct FileRecord {
+    minus_file: String,
+    plus_file: String,
+    minus_file_event: FileEvent,
+    plus_file_event: FileEvent,
+}
+
+#[derive(Serialize)]
+struct Line<'l> {
+    line_number: usize,
+    text: &'l str,
+    sections: Vec<Section<'l>>,
+}
+
+#[derive(Serialize)]
+struct Section<'s> {
+    emph: bool,
+    text: &'s str,
+}
+
+impl<'a> JsonWriter<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        Self {
+            writer,
+            buffer: String::new(),
+            file: None,
+            minus_line_number: 0,
+            plus_line_number: 0,
+        }
+    }
+
+    fn write_pending_file(&mut self) {
+        if let Some(file) = self.file.take() {
+            self.push(&Record::File(&file));
+        }
+    }
+
+    fn push(&mut self, record: &Record) {
+        // Serializing these records cannot fail: they contain no maps with non-string keys.
+        self.buffer
+            .push_str(&serde_json::to_string(record).unwrap());
+        self.buffer.push('\n');
+    }
+}
+
+impl StructuredOutput for JsonWriter<'_> {
+    fn start_file(
+        &mut self,
+        minus_file: &str,
+        plus_file: &str,
+        minus_file_event: FileEvent,
+        plus_file_event: FileEvent,
+        _description: &str,
+    ) {
+        let file = FileRecord {
+            minus_file: minus_file.to_string(),
+            plus_file: plus_file.to_string(),
+            minus_file_event,
+            plus_file_event,
+        };
+        match &self.file {
+            // The file was already started, e.g. at its "rename to" line.
+            Some(pending)
+                if (&pending.minus_file, &pending.plus_file)
+                    == (&file.minus_file, &file.plus_file) => {}
+            _ => self.write_pending_file(),
+        }
+        self.file = Some(file);
+    }
+
+    fn write_commit(&mut self, line: &str) {
+        self.write_pending_file();
+        self.push(&Record::Commit { line });
+    }
+
+    fn write_hunk(
+        &mut self,
+        hunk_index: usize,
+        line_numbers_and_hunk_lengths: &[(usize, usize)],
+        code_fragment: &str,
+    ) {
+        self.write_pending_file();
+        // In a combined diff, the removed lines are numbered as in the first parent.
+        let (minus_start, minus_count) = line_numbers_and_hunk_lengths[0];
+        let (plus_start, plus_count) =
+            line_numbers_and_hunk_lengths[line_numbers_and_hunk_lengths.len() - 1];
+        self.minus_line_number = minus_start;
+        self.plus_line_number = plus_start;
+        self.push(&Record::Hunk {
+            hunk_index,
+            minus_start,
+            minus_count,
+            plus_start,
+            plus_count,
+            code_fragment: code_fragment.trim(),
+        });
+    }
+
+    fn write_context(&mut self, line: &str) {
+        let record = Record::Context {
+            minus_line_number: self.minus_line_number,
+            plus_line_number: self.plus_line_number,
+            text: line.trim_end_matches('\n'),
+        };
+        self.push(&record);
+        self.minus_line_number += 1;
+        self.plus_line_number += 1;
+    }
+
+    fn write_changes(
+        &mut self,
+        minus_lines: &[(String, State)],
+        plus_lines: &[(String, State)],
+        config: &Config,
+    ) {
+        let (minus_sections, plus_sections, alignment) = edits::infer_edits(
+            minus_lines.iter().map(|(line, _)| line.as_str()).collect(),
+            plus_lines.iter().map(|(line, _)| line.as_str()).collect(),
+            vec![false; minus_lines.len()],
+            true,
+            vec![false; plus_lines.len()],
+            true,
+            &config.tokenization_regex,
+            config.max_line_distance,
+            config.max_line_distance_for_naively_paired_lines,
+        );
+        let minus = make_lines(minus_lines, minus_sections, self.minus_line_number);
+        let plus = make_lines(plus_lines, plus_sections, self.plus_line_number);
+        self.minus_line_number += minus.len();
+        self.plus_line_number += plus.len();
+        self.push(&Record::Changes {
+            minus,
+            plus,
+            alignment,
+        });
+    }
+
+    fn emit(&mut self) -> io::Result<()> {
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_pending_file();
+        self.emit()?;
+        self.writer.flush()
+    }
+}
+
+fn make_lines<'l>(
+    lines: &'l [(String, State)],
+    sections: Vec<Vec<(bool, &'l str)>>,
+    first_line_number: usize,
+) -> Vec<Line<'l>> {
+    lines
+        .iter()
+        .zip(sections)
+        .enumerate()
+        .map(|(i, ((line, _), sections))| Line {
+            line_number: first_line_number + i,
+            text: line.trim_end_matches('\n'),
+            sections: sections
+                .into_iter()
+                .map(|(emph, text)| Section {
+                    emph,
+                    text: text.trim_end_matches('\n'),
+                })
+                .filter(|section| !section.text.is_empty())
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::integration_test_utils::DeltaTest;
+
+    #[test]
+    fn test_json_output() {
+        let output = DeltaTest::with_args(&["--output-format", "json"])
+            .with_input(DIFF)
+            .output;
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                serde_json::json!({"type": "commit", "line": "commit 1111111111111111111111111111111111111111"}),
+                serde_json::json!({
+                    "type": "file",
+                    "minus_file": "a.py",
+                    "plus_file": "a.py",
+                    "minus_file_event": "change",
+                    "plus_file_event": "change",
+                }),
+                serde_json::json!({
+                    "type": "hunk",
+                    "hunk_index": 1,
+                    "minus_start": 10,
+                    "minus_count": 3,
+                    "plus_start": 10,
+                    "plus_count": 3,
+                    "code_fragment": "def f():",
+                }),
+                serde_json::json!({
+                    "type": "context",
+                    "minus_line_number": 10,
+                    "plus_line_number": 10,
+                    "text": "    y = 1",
+                }),
+                serde_json::json!({
+                    "type": "changes",
+                    "minus": [{
+                        "line_number": 11,
+                        "text": "    x = 1",
+                        "sections": [
+                            {"emph": false, "text": "    x = "},
+                            {"emph": true, "text": "1"},
+                        ],
+                    }],
+                    "plus": [{
+                        "line_number": 11,
+                        "text": "    x = 2",
+                        "sections": [
+                            {"emph": false, "text": "    x = "},
+                            {"emph": true, "text": "2"},
+                        ],
+                    }],
+                    "alignment": [[0, 0]],
+                }),
+                serde_json::json!({
+                    "type": "context",
+                    "minus_line_number": 12,
+                    "plus_line_number": 12,
+                    "text": "    return x",
+                }),
+                serde_json::json!({
+                    "type": "file",
+                    "minus_file": "b.py",
+                    "plus_file": "c.py",
+                    "minus_file_event": "rename",
+                    "plus_file_event": "rename",
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_output_has_the_text_of_the_lines() {
+        let output = DeltaTest::with_args(&[
+            "--output-format",
+            "json",
+            "--show-whitespace",
+            "--unicode-warnings",
+            "lines",
+        ])
+        .with_input(
+            "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+ \ty = 1
+-delta_\u{3c3} = \"\"
++delta_\u{3c3} = \"\u{202e}\" 
+",
+        )
+        .output;
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[2]["text"], "\ty = 1");
+        assert_eq!(records[3]["minus"][0]["text"], "delta_\u{3c3} = \"\"");
+        assert_eq!(
+            records[3]["plus"][0]["text"],
+            "delta_\u{3c3} = \"\u{202e}\" "
+        );
+    }
+
+    const DIFF: &str = "\
+commit 1111111111111111111111111111111111111111
+Author: A <a@example.com>
+Date:   Mon Jan 1 00:00:00 2024 +0000
+
+    Message
+
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -10,3 +10,3 @@ def f():
+     y = 1
+-    x = 1
++    x = 2
+     return x
+diff --git a/b.py b/c.py
+similarity index 100%
+rename from b.py
+rename to c.py
+";
+}
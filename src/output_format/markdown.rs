@@ -0,0 +1,300 @@
+// The Markdown output written by --output-format=markdown, e.g. for pasting into a pull request
+// comment.
+//
+// Each commit starts with a heading, followed by its metadata. Each file has a heading with its
+// file label and path, followed by its hunks in a fenced `diff` block. With
+// --markdown-collapse-min-lines, the block of a file with many changed lines is collapsed in a
+// <details> element.
+
+use std::io::{self, Write};
+
+use crate::config::Config;
+use crate::delta::State;
+use crate::handlers::diff_header::FileEvent;
+use crate::output_format::{escape_xml, StructuredOutput};
+
+pub struct MarkdownWriter<'a> {
+    writer: &'a mut dyn Write,
+    // Markdown not yet written.
+    buffer: String,
+    // The current file, which is written when it ends, since whether it is collapsed depends on
+    // its number of changed lines.
+    file: Option<MarkdownFile>,
+    collapse_min_lines: usize,
+    // Whether the last line written was blank, so that a blank line would start a paragraph.
+    after_blank_line: bool,
+}
+
+struct MarkdownFile {
+    file_pair: (String, String),
+    description: String,
+    // The lines of the diff block.
+    diff: String,
+    n_minus: usize,
+    n_plus: usize,
+}
+
+impl<'a> MarkdownWriter<'a> {
+    pub fn new(writer: &'a mut dyn Write, config: &Config) -> Self {
+        Self {
+            writer,
+            buffer: String::new(),
+            file: None,
+            collapse_min_lines: config.markdown_collapse_min_lines,
+            after_blank_line: true,
+        }
+    }
+
+    fn write_file(&mut self) {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return,
+        };
+        self.after_blank_line = true;
+        if file.diff.is_empty() {
+            self.buffer
+                .push_str(&format!("### {}\n\n", escape_markdown(&file.description)));
+            return;
+        }
+        let fence = make_fence(&file.diff);
+        let diff_block = format!("{fence}diff\n{}{fence}\n\n", file.diff);
+        if self.collapse_min_lines > 0 && file.n_minus + file.n_plus >= self.collapse_min_lines {
+            self.buffer.push_str(&format!(
+                "<details>\n<summary>{} (+{}/−{})</summary>\n\n{diff_block}</details>\n\n",
+                escape_xml(&file.description),
+                file.n_plus,
+                file.n_minus,
+            ));
+        } else {
+            self.buffer.push_str(&format!(
+                "### {}\n\n{diff_block}",
+                escape_markdown(&file.description)
+            ));
+        }
+    }
+}
+
+impl StructuredOutput for MarkdownWriter<'_> {
+    fn start_file(
+        &mut self,
+        minus_file: &str,
+        plus_file: &str,
+        _minus_file_event: FileEvent,
+        _plus_file_event: FileEvent,
+        description: &str,
+    ) {
+        let file_pair = (minus_file.to_string(), plus_file.to_string());
+        match &mut self.file {
+            // The file was already started, e.g. at its "rename to" line.
+            Some(file) if file.file_pair == file_pair => {
+                file.description = description.to_string();
+            }
+            _ => {
+                self.write_file();
+                self.file = Some(MarkdownFile {
+                    file_pair,
+                    description: description.to_string(),
+                    diff: String::new(),
+                    n_minus: 0,
+                    n_plus: 0,
+                });
+            }
+        }
+    }
+
+    fn write_commit(&mut self, line: &str) {
+        self.write_file();
+        self.buffer
+            .push_str(&format!("## {}\n\n", escape_markdown(line.trim())));
+        self.after_blank_line = true;
+    }
+
+    fn write_commit_meta_line(&mut self, line: &str) {
+        // The commit message is indented, which would make it a code block.
+        let line = line.strip_prefix("    ").unwrap_or(line).trim_end();
+        if line.is_empty() {
+            if !self.after_blank_line {
+                self.buffer.push('\n');
+                self.after_blank_line = true;
+            }
+        } else {
+            // A leading "-", "+" or "=" could start a list, or make the previous line a heading.
+            if line.starts_with(['-', '+', '=']) {
+                self.buffer.push('\\');
+            }
+            // End the line with a hard line break, so that e.g. "Author:" and "Date:" lines
+            // are not joined.
+            self.buffer
+                .push_str(&format!("{}  \n", escape_markdown(line)));
+            self.after_blank_line = false;
+        }
+    }
+
+    fn write_hunk(
+        &mut self,
+        _hunk_index: usize,
+        line_numbers_and_hunk_lengths: &[(usize, usize)],
+        code_fragment: &str,
+    ) {
+        if let Some(file) = &mut self.file {
+            let at_signs = "@".repeat(line_numbers_and_hunk_lengths.len());
+            let (plus_ranges, minus_ranges) = line_numbers_and_hunk_lengths.split_last().unwrap();
+            let mut ranges: Vec<String> = minus_ranges
+                .iter()
+                .map(|(start, length)| format!("-{start},{length}"))
+                .collect();
+            ranges.push(format!("+{},{}", plus_ranges.0, plus_ranges.1));
+            let code_fragment = code_fragment.trim();
+            file.diff.push_str(&format!(
+                "{at_signs} {} {at_signs}{}{code_fragment}\n",
+                ranges.join(" "),
+                if code_fragment.is_empty() { "" } else { " " },
+            ));
+        }
+    }
+
+    fn write_context(&mut self, line: &str) {
+        if let Some(file) = &mut self.file {
+            file.diff.push(' ');
+            file.diff.push_str(line);
+        }
+    }
+
+    fn write_changes(
+        &mut self,
+        minus_lines: &[(String, State)],
+        plus_lines: &[(String, State)],
+        _config: &Config,
+    ) {
+        if let Some(file) = &mut self.file {
+            for (line, _) in minus_lines {
+                file.diff.push('-');
+                file.diff.push_str(line);
+            }
+            for (line, _) in plus_lines {
+                file.diff.push('+');
+                file.diff.push_str(line);
+            }
+            file.n_minus += minus_lines.len();
+            file.n_plus += plus_lines.len();
+        }
+    }
+
+    fn emit(&mut self) -> io::Result<()> {
+        self.writer.write_all(self.buffer.as_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_file();
+        // Do not end the document with a blank line.
+        if self.buffer.ends_with("\n\n") {
+            self.buffer.pop();
+        }
+        self.emit()?;
+        self.writer.flush()
+    }
+}
+
+/// Return a code fence longer than any run of backticks in `text`.
+fn make_fence(text: &str) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    "`".repeat(std::cmp::max(3, longest_run + 1))
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::integration_test_utils::DeltaTest;
+
+    #[test]
+    fn test_markdown_output() {
+        let output = DeltaTest::with_args(&["--output-format", "markdown"])
+            .with_input(DIFF)
+            .output;
+        assert_eq!(
+            output,
+            "\
+## commit 1111111111111111111111111111111111111111
+
+Author: A \\<a@example.com\\>  
+Date:   Mon Jan 1 00:00:00 2024 +0000  
+
+Message with \\`code\\`  
+
+### a\\_b.py
+
+```diff
+@@ -10,3 +10,3 @@ def f():
+     y = 1
+-    x = 1
++    x = 2
+     return x
+```
+
+### renamed: b.py ⟶   c.py
+"
+        );
+    }
+
+    #[test]
+    fn test_markdown_collapse_min_lines() {
+        let output = DeltaTest::with_args(&[
+            "--output-format",
+            "markdown",
+            "--markdown-collapse-min-lines",
+            "2",
+        ])
+        .with_input(DIFF)
+        .output;
+        assert!(output.contains(
+            "\
+<details>
+<summary>a_b.py (+1/−1)</summary>
+
+```diff
+@@ -10,3 +10,3 @@ def f():"
+        ));
+        assert!(output.contains("```\n\n</details>\n\n### renamed: b.py"));
+    }
+
+    const DIFF: &str = "\
+commit 1111111111111111111111111111111111111111
+Author: A <a@example.com>
+Date:   Mon Jan 1 00:00:00 2024 +0000
+
+    Message with `code`
+
+diff --git a/a_b.py b/a_b.py
+index 1111111..2222222 100644
+--- a/a_b.py
++++ b/a_b.py
+@@ -10,3 +10,3 @@ def f():
+     y = 1
+-    x = 1
++    x = 2
+     return x
+diff --git a/b.py b/c.py
+similarity index 100%
+rename from b.py
+rename to c.py
+";
+}
@@ -0,0 +1,118 @@
+// Output formats other than ANSI-styled text, selected with --output-format.
+
+use std::io;
+
+use ansi_term::Color;
+
+use crate::color;
+use crate::config::Config;
+use crate::delta::{Source, State, StateMachine};
+use crate::handlers::diff_header::{get_file_change_description_from_file_paths, FileEvent};
+
+pub mod html;
+pub mod json;
+pub mod markdown;
+pub mod monochrome;
+pub mod svg;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Ansi,
+    Html,
+    Json,
+    Markdown,
+    Svg,
+}
+
+/// An output format which is written from the parsed diff, rather than from painted text, which
+/// is discarded.
+pub trait StructuredOutput {
+    /// Start a file. This may be called more than once for the same file pair, e.g. at its
+    /// "rename to" line and at its "+++" line, with the later call being more accurate.
+    fn start_file(
+        &mut self,
+        minus_file: &str,
+        plus_file: &str,
+        minus_file_event: FileEvent,
+        plus_file_event: FileEvent,
+        description: &str,
+    );
+
+    fn write_commit(&mut self, line: &str);
+
+    /// Write a line of commit metadata following the commit line, e.g. the author or message.
+    fn write_commit_meta_line(&mut self, _line: &str) {}
+
+    fn write_hunk(
+        &mut self,
+        hunk_index: usize,
+        line_numbers_and_hunk_lengths: &[(usize, usize)],
+        code_fragment: &str,
+    );
+
+    /// Write an unchanged line, without its prefix.
+    fn write_context(&mut self, line: &str);
+
+    /// Write a group of removed and added lines, without their prefixes.
+    fn write_changes(
+        &mut self,
+        minus_lines: &[(String, State)],
+        plus_lines: &[(String, State)],
+        config: &Config,
+    );
+
+    /// Write the output recorded so far.
+    fn emit(&mut self) -> io::Result<()>;
+
+    /// Write all remaining output, e.g. for a last file without hunks.
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+impl<'a> StateMachine<'a> {
+    /// Start the current file in the structured output, if any.
+    pub fn start_file_in_structured_output(&mut self) {
+        if let Some(structured_output) = &mut self.painter.structured_output {
+            let description = get_file_change_description_from_file_paths(
+                &self.minus_file,
+                &self.plus_file,
+                self.source == Source::DiffUnified,
+                &self.minus_file_event,
+                &self.plus_file_event,
+                self.config,
+            );
+            structured_output.start_file(
+                &self.minus_file,
+                &self.plus_file,
+                self.minus_file_event.clone(),
+                self.plus_file_event.clone(),
+                &description,
+            );
+        }
+    }
+}
+
+/// Escape text for inclusion in HTML or SVG.
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn color_to_hex(color: Color) -> String {
+    let (r, g, b) = color::color_to_rgb(color);
+    rgb_to_hex(r, g, b)
+}
+
+pub fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
@@ -0,0 +1,51 @@
+// The writer used by --monochrome.
+//
+// Delta still paints its output with styles, e.g. those of decorations and of the commit and file
+// metadata that git colored itself; this writer removes every escape sequence, so that only plain
+// text reaches the terminal.
+
+use std::io::{self, Write};
+
+use crate::ansi;
+
+pub struct MonochromeWriter<'a> {
+    writer: &'a mut dyn Write,
+    // Bytes written since the last newline.
+    pending: Vec<u8>,
+}
+
+impl<'a> MonochromeWriter<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        Self {
+            writer,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Write any incomplete last line.
+    pub fn finish(mut self) -> io::Result<()> {
+        let line = std::mem::take(&mut self.pending);
+        self.write_line(&line)?;
+        self.writer.flush()
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let line = ansi::strip_ansi_codes(&String::from_utf8_lossy(line));
+        self.writer.write_all(line.as_bytes())
+    }
+}
+
+impl Write for MonochromeWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if let Some(i) = self.pending.iter().rposition(|&b| b == b'\n') {
+            let lines: Vec<u8> = self.pending.drain(..=i).collect();
+            self.write_line(&lines)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,239 @@
+// The SVG output written by --output-format=svg.
+//
+// Delta's ANSI-styled output is laid out on a grid of fixed-size character cells: each run of
+// styled text becomes a <text> element, preceded by a <rect> element for its background color if
+// it has one. Each <text> element is stretched to the width of the cells it occupies, so that the
+// layout, including double-width characters, does not depend on the font used to view the image.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::ansi::{measure_text_width, AnsiElementIterator, Element};
+use crate::config::Config;
+use crate::output_format::{color_to_hex, escape_xml, rgb_to_hex};
+
+const FONT_SIZE: f64 = 14.0;
+const CELL_WIDTH: f64 = 8.4;
+const LINE_HEIGHT: f64 = 18.0;
+const PADDING: f64 = 8.0;
+
+pub struct SvgWriter<'a> {
+    writer: &'a mut dyn Write,
+    // Bytes written since the last newline.
+    pending: Vec<u8>,
+    // The style in effect, which may carry over from a previous line.
+    style: ansi_term::Style,
+    // The runs of styled text of each line.
+    lines: Vec<Vec<(ansi_term::Style, String)>>,
+    background: String,
+    foreground: String,
+}
+
+impl<'a> SvgWriter<'a> {
+    pub fn new(writer: &'a mut dyn Write, config: &Config) -> Self {
+        let settings = config.syntax_theme.as_ref().map(|theme| &theme.settings);
+        let background = settings
+            .and_then(|settings| settings.background)
+            .map(|color| rgb_to_hex(color.r, color.g, color.b))
+            .unwrap_or_else(|| "#ffffff".to_string());
+        let foreground = settings
+            .and_then(|settings| settings.foreground)
+            .map(|color| rgb_to_hex(color.r, color.g, color.b))
+            .unwrap_or_else(|| "#000000".to_string());
+        Self {
+            writer,
+            pending: Vec::new(),
+            style: ansi_term::Style::new(),
+            lines: Vec::new(),
+            background,
+            foreground,
+        }
+    }
+
+    /// Write the image, since its size is only known once all lines have been written.
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.add_line(&String::from_utf8_lossy(&line));
+        }
+        let n_columns = self
+            .lines
+            .iter()
+            .map(|runs| {
+                runs.iter()
+                    .map(|(_, text)| measure_text_width(text))
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or(0);
+        let width = 2.0 * PADDING + n_columns as f64 * CELL_WIDTH;
+        let height = 2.0 * PADDING + self.lines.len() as f64 * LINE_HEIGHT;
+        writeln!(
+            self.writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.1}\" height=\"{height:.1}\" \
+             viewBox=\"0 0 {width:.1} {height:.1}\" xml:space=\"preserve\" \
+             font-family=\"ui-monospace, SFMono-Regular, Menlo, Consolas, monospace\" \
+             font-size=\"{FONT_SIZE}\">"
+        )?;
+        writeln!(
+            self.writer,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            self.background
+        )?;
+        for (row, runs) in self.lines.iter().enumerate() {
+            let y = PADDING + row as f64 * LINE_HEIGHT;
+            let mut column = 0;
+            let mut texts = String::new();
+            for (style, text) in runs {
+                let n_cells = measure_text_width(text);
+                let x = PADDING + column as f64 * CELL_WIDTH;
+                let cells_width = n_cells as f64 * CELL_WIDTH;
+                column += n_cells;
+                let (foreground, background) = if style.is_reverse {
+                    (
+                        style
+                            .background
+                            .map(color_to_hex)
+                            .unwrap_or_else(|| self.background.clone()),
+                        Some(
+                            style
+                                .foreground
+                                .map(color_to_hex)
+                                .unwrap_or_else(|| self.foreground.clone()),
+                        ),
+                    )
+                } else {
+                    (
+                        style
+                            .foreground
+                            .map(color_to_hex)
+                            .unwrap_or_else(|| self.foreground.clone()),
+                        style.background.map(color_to_hex),
+                    )
+                };
+                if let Some(background) = background {
+                    writeln!(
+                        self.writer,
+                        "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{cells_width:.1}\" \
+                         height=\"{LINE_HEIGHT}\" fill=\"{background}\"/>"
+                    )?;
+                }
+                if text.trim().is_empty() || style.is_hidden {
+                    continue;
+                }
+                write!(
+                    texts,
+                    "<text x=\"{x:.1}\" y=\"{:.1}\" textLength=\"{cells_width:.1}\" \
+                     lengthAdjust=\"spacingAndGlyphs\" fill=\"{foreground}\"",
+                    y + FONT_SIZE
+                )
+                .unwrap();
+                if style.is_bold {
+                    texts.push_str(" font-weight=\"bold\"");
+                }
+                if style.is_italic {
+                    texts.push_str(" font-style=\"italic\"");
+                }
+                if style.is_dimmed {
+                    texts.push_str(" opacity=\"0.6\"");
+                }
+                match (style.is_underline, style.is_strikethrough) {
+                    (true, true) => texts.push_str(" text-decoration=\"underline line-through\""),
+                    (true, false) => texts.push_str(" text-decoration=\"underline\""),
+                    (false, true) => texts.push_str(" text-decoration=\"line-through\""),
+                    (false, false) => {}
+                }
+                writeln!(texts, ">{}</text>", escape_xml(text)).unwrap();
+            }
+            write!(self.writer, "{texts}")?;
+        }
+        writeln!(self.writer, "</svg>")?;
+        self.writer.flush()
+    }
+
+    fn add_line(&mut self, line: &str) {
+        // Git writes a reset as an SGR sequence without parameters, which the ANSI parser skips.
+        let line = line.replace("\x1b[m", "\x1b[0m");
+        let mut runs = Vec::new();
+        for element in AnsiElementIterator::new(&line) {
+            match element {
+                Element::Sgr(style, _, _) => self.style = style,
+                Element::Text(start, end) => {
+                    let text: String = line[start..end]
+                        .chars()
+                        .filter(|c| !c.is_control())
+                        .collect();
+                    if !text.is_empty() {
+                        runs.push((self.style, text));
+                    }
+                }
+                Element::Csi(_, _) | Element::Esc(_, _) | Element::Osc(_, _) => {}
+            }
+        }
+        self.lines.push(runs);
+    }
+}
+
+impl Write for SvgWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(i) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=i).collect();
+            self.add_line(&String::from_utf8_lossy(&line[..i]));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::{make_config_from_args, DeltaTest};
+
+    #[test]
+    fn test_svg_output() {
+        let output = DeltaTest::with_args(&["--output-format", "svg"])
+            .with_input(DIFF)
+            .output;
+        assert!(output.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(output.ends_with("</svg>\n"));
+        assert!(output.contains("&lt;T&gt;"));
+        assert!(!output.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_svg_writer_uses_cell_widths() {
+        let config = make_config_from_args(&["--syntax-theme", "none"]);
+        let mut output = Vec::new();
+        let mut writer = SvgWriter::new(&mut output, &config);
+        writeln!(writer, "\x1b[1;31m漢字\x1b[0m ab\x1b[48;2;0;64;0m+\x1b[m").unwrap();
+        writer.finish().unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "\
+<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"83.2\" height=\"34.0\" viewBox=\"0 0 83.2 34.0\" xml:space=\"preserve\" font-family=\"ui-monospace, SFMono-Regular, Menlo, Consolas, monospace\" font-size=\"14\">
+<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>
+<rect x=\"66.8\" y=\"8.0\" width=\"8.4\" height=\"18\" fill=\"#004000\"/>
+<text x=\"8.0\" y=\"22.0\" textLength=\"33.6\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#cd0000\" font-weight=\"bold\">漢字</text>
+<text x=\"41.6\" y=\"22.0\" textLength=\"25.2\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#000000\"> ab</text>
+<text x=\"66.8\" y=\"22.0\" textLength=\"8.4\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#000000\">+</text>
+</svg>
+"
+        );
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.rs b/a.rs
+index 1111111..2222222 100644
+--- a/a.rs
++++ b/a.rs
+@@ -1 +1 @@
+-fn f<T>() {}
++fn g<T>() {}
+";
+}
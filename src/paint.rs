@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::Write;
+use std::ops::Range;
 
 use ansi_term::ANSIString;
 use itertools::Itertools;
@@ -15,21 +16,47 @@ use crate::features::hyperlinks;
 use crate::features::line_numbers::{self, LineNumbersData};
 use crate::features::side_by_side::ansifill;
 use crate::features::side_by_side::{self, PanelSide};
+use crate::full_file_highlighting::FullFileHighlighting;
 use crate::handlers::merge_conflict;
 use crate::minusplus::*;
+use crate::output_format::{html, OutputFormat, StructuredOutput};
 use crate::paint::superimpose_style_sections::superimpose_style_sections;
+use crate::pipeline::{LaneStart, Pipeline};
 use crate::style::Style;
+use crate::suspicious_unicode::{self, UnicodeWarnings};
 use crate::{ansi, style};
-use crate::{edits, utils};
+use crate::{edits, utils, whitespace};
 
 pub type LineSections<'a, S> = Vec<(S, &'a str)>;
 
+// The maximum number of unchanged lines held back at the start of a hunk until its first changed
+// lines are read, in order to choose the split of the panels with --side-by-side-ratio=auto.
+const MAX_SIDE_BY_SIDE_RATIO_LINES: usize = 20;
+
 pub struct Painter<'p> {
     pub minus_lines: Vec<(String, State)>,
     pub plus_lines: Vec<(String, State)>,
-    pub writer: &'p mut dyn Write,
+    // The byte ranges of suspicious Unicode in each of `plus_lines`, see `suspicious_unicode`.
+    pub plus_line_unicode_warnings: Vec<Vec<Range<usize>>>,
+    // Unchanged lines are only buffered if they may be folded, see `paint_buffered_zero_lines`.
+    pub zero_lines: Vec<(String, State)>,
+    // Whether the buffered unchanged lines follow a change in the current hunk.
+    pub zero_lines_follow_change: bool,
+    // The number of unchanged lines preceding the buffered ones which are already known to be
+    // folded, see `push_zero_line`.
+    n_folded_zero_lines: usize,
+    pub writer: Output<'p>,
     pub syntax: &'p SyntaxReference,
+    // Whether the syntax is unknown from the file name, so that it may be detected from the
+    // content of the file, see `detect_syntax`.
+    pub syntax_is_unknown: bool,
     pub highlighter: Option<HighlightLines<'p>>,
+    // The syntax of the highlighter, as long as it has not highlighted any line: the lines of a
+    // hunk can then be painted in the background, with a new highlighter.
+    new_highlighter_syntax: Option<&'p SyntaxReference>,
+    // Set with --highlight-full-files if the files of the current diff could be loaded, in which
+    // case lines are highlighted as part of the full files.
+    pub full_file_highlighting: Option<FullFileHighlighting>,
     pub config: &'p config::Config,
     pub output_buffer: String,
     // If config.line_numbers is true, then the following is always Some().
@@ -38,6 +65,155 @@ pub struct Painter<'p> {
     pub line_numbers_data: Option<line_numbers::LineNumbersData<'p>>,
     pub merge_conflict_lines: merge_conflict::MergeConflictLines,
     pub merge_conflict_commit_names: merge_conflict::MergeConflictCommitNames,
+    // Whether the lines of the current hunk are painted in side-by-side layout. Each hunk starts
+    // out as config.side_by_side, but with `SideBySideMode::Auto` a hunk with lines which are
+    // too long for the panels is painted in unified layout.
+    pub side_by_side: bool,
+    // The current panels in side-by-side layout. Starts out as config.side_by_side_data, but
+    // with `SideBySideRatio::Auto` the split is adapted to the minus and plus lines of each hunk.
+    pub side_by_side_data: side_by_side::SideBySideData,
+    // The lines of the current hunk, each with the output preceding them, while they are held
+    // back until the layout of the hunk is chosen, see `start_hunk_painting`.
+    held_paint_ops: Option<Vec<(String, PaintOp)>>,
+    // Set with structured output formats, e.g. --output-format=json, in which case lines are
+    // recorded instead of painted.
+    pub structured_output: Option<Box<dyn StructuredOutput + 'p>>,
+}
+
+/// The output stream of a Painter. Output can be held back, so that lines which are only known
+/// later (such as a file header reporting on the lines of the file) can be written before it.
+pub struct Output<'p> {
+    writer: &'p mut dyn Write,
+    // Output can be held back at several levels, e.g. for a commit and for a file within it.
+    held: Vec<Vec<u8>>,
+    // Set if hunk lines are painted in the background, in which case output is written in order
+    // through the pipeline.
+    pipeline: Option<Pipeline<'p>>,
+}
+
+impl<'p> Output<'p> {
+    pub fn new(writer: &'p mut dyn Write) -> Self {
+        Self {
+            writer,
+            held: Vec::new(),
+            pipeline: None,
+        }
+    }
+
+    pub fn set_pipeline(&mut self, pipeline: Pipeline<'p>) {
+        self.pipeline = Some(pipeline);
+    }
+
+    /// The pipeline to paint hunk lines in the background, unless output is held back: held
+    /// output is painted right away, since its length is needed.
+    fn pipeline_for_painting(&mut self) -> Option<&mut Pipeline<'p>> {
+        if self.held.is_empty() {
+            self.pipeline.as_mut()
+        } else {
+            None
+        }
+    }
+
+    fn finish_lane(&mut self) {
+        if let Some(pipeline) = &mut self.pipeline {
+            pipeline.finish_lane();
+        }
+    }
+
+    /// Write the output painted in the background so far, in order.
+    pub fn write_completed(&mut self) -> std::io::Result<()> {
+        match &mut self.pipeline {
+            Some(pipeline) => pipeline.write_output(self.writer, false),
+            None => Ok(()),
+        }
+    }
+
+    /// Write the lines painted so far and flush the writer. If `wait` is true, then wait for the
+    /// lines which are still painted in the background, otherwise they are written later.
+    pub fn flush_painted_lines(&mut self, wait: bool) -> std::io::Result<()> {
+        match &mut self.pipeline {
+            Some(pipeline) if wait => pipeline.write_all_output(self.writer)?,
+            Some(pipeline) => pipeline.write_painted_output(self.writer)?,
+            None => {}
+        }
+        self.writer.flush()
+    }
+
+    /// Write all output, waiting for the lines which are still painted in the background.
+    pub fn write_pending(&mut self) -> std::io::Result<()> {
+        match &mut self.pipeline {
+            Some(pipeline) => pipeline.write_all_output(self.writer),
+            None => Ok(()),
+        }
+    }
+
+    /// Write output which is not held back.
+    fn write_unheld(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match &mut self.pipeline {
+            Some(pipeline) if !pipeline.is_idle() => {
+                pipeline.push_output(buf.to_vec());
+                pipeline.write_output(self.writer, false)
+            }
+            _ => self.writer.write_all(buf),
+        }
+    }
+
+    /// Hold back all output until the matching call to `release`.
+    pub fn hold(&mut self) {
+        // Lines painted in the background are written in order, before the held output.
+        self.finish_lane();
+        self.held.push(Vec::new());
+    }
+
+    /// The number of bytes held back since the last call to `hold`, or None if output is not
+    /// held back.
+    pub fn held_len(&self) -> Option<usize> {
+        self.held.last().map(|held| held.len())
+    }
+
+    /// The number of bytes held back at all levels.
+    pub fn total_held_len(&self) -> usize {
+        self.held.iter().map(|held| held.len()).sum()
+    }
+
+    /// Stop holding back output at the level of the last call to `hold`, and return the output
+    /// held back at that level.
+    pub fn release(&mut self) -> Vec<u8> {
+        self.held.pop().unwrap_or_default()
+    }
+
+    /// Stop holding back output at the level of the first call to `hold`, writing the output
+    /// held back at that level. Output held back at other levels was written later, so it remains
+    /// held back.
+    pub fn release_outermost(&mut self) -> std::io::Result<()> {
+        if self.held.is_empty() {
+            return Ok(());
+        }
+        let held = self.held.remove(0);
+        self.write_unheld(&held)
+    }
+
+    // Allows write!(painter.writer, ...) without importing std::io::Write.
+    pub fn write_fmt(&mut self, args: std::fmt::Arguments) -> std::io::Result<()> {
+        Write::write_fmt(self, args)
+    }
+}
+
+impl Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.held.last_mut() {
+            Some(held) => held.write(buf),
+            None => {
+                self.write_unheld(buf)?;
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_pending()?;
+        self.writer.flush()
+    }
 }
 
 // How the background of a line is filled up to the end
@@ -76,10 +252,20 @@ impl<'p> Painter<'p> {
         let panel_width_fix = ansifill::UseFullPanelWidth::new(config);
 
         let line_numbers_data = if config.line_numbers {
-            Some(line_numbers::LineNumbersData::from_format_strings(
+            let line_numbers_data = line_numbers::LineNumbersData::from_format_strings(
                 &config.line_numbers_format,
                 panel_width_fix,
-            ))
+            );
+            Some(
+                if config.side_by_side
+                    && config.side_by_side_mode == side_by_side::SideBySideMode::Auto
+                {
+                    line_numbers_data
+                        .with_unified_format_strings(&config.line_numbers_unified_format)
+                } else {
+                    line_numbers_data
+                },
+            )
         } else if config.side_by_side {
             // If line numbers are disabled in side-by-side then the data is still used
             // for width calculation and to pad odd width to even, see `UseFullPanelWidth`
@@ -93,19 +279,143 @@ impl<'p> Painter<'p> {
         Self {
             minus_lines: Vec::new(),
             plus_lines: Vec::new(),
+            plus_line_unicode_warnings: Vec::new(),
+            zero_lines: Vec::new(),
+            zero_lines_follow_change: false,
+            n_folded_zero_lines: 0,
             output_buffer: String::new(),
             syntax: default_syntax,
+            syntax_is_unknown: true,
             highlighter: None,
-            writer,
+            new_highlighter_syntax: None,
+            full_file_highlighting: None,
+            writer: Output::new(writer),
             config,
             line_numbers_data,
             merge_conflict_lines: merge_conflict::MergeConflictLines::new(),
             merge_conflict_commit_names: merge_conflict::MergeConflictCommitNames::new(),
+            side_by_side: config.side_by_side,
+            side_by_side_data: config.side_by_side_data.clone(),
+            held_paint_ops: None,
+            structured_output: None,
+        }
+    }
+
+    /// Start painting the lines of a new hunk in the configured layout. With
+    /// `SideBySideMode::Auto` the layout depends on all the lines of the hunk, which are held
+    /// back until it ends, and with `SideBySideRatio::Auto` on its first changed lines, which
+    /// are held back until they are read; see `choose_hunk_layout`.
+    pub fn start_hunk_painting(&mut self) {
+        self.side_by_side = self.config.side_by_side;
+        self.side_by_side_data = self.config.side_by_side_data.clone();
+        if self.config.side_by_side
+            && (self.config.side_by_side_mode == side_by_side::SideBySideMode::Auto
+                || self.config.side_by_side_ratio == side_by_side::SideBySideRatio::Auto)
+            && self.structured_output.is_none()
+        {
+            self.held_paint_ops = Some(Vec::new());
+        }
+    }
+
+    /// Whether the layout of the current hunk can be chosen from the lines held back so far:
+    /// the split of the panels only depends on the first changed lines, or on the first
+    /// unchanged lines if there are too many of them.
+    fn can_choose_hunk_layout(&self) -> bool {
+        let held_paint_ops = match &self.held_paint_ops {
+            Some(held_paint_ops) => held_paint_ops,
+            None => return false,
+        };
+        let n_held_lines: usize = held_paint_ops.iter().map(|(_, op)| op.n_lines()).sum();
+        self.config.side_by_side_mode != side_by_side::SideBySideMode::Auto
+            && (matches!(
+                held_paint_ops.last(),
+                Some((_, PaintOp::MinusAndPlusLines { .. }))
+            ) || n_held_lines >= MAX_SIDE_BY_SIDE_RATIO_LINES)
+    }
+
+    /// Choose the layout of the current hunk from the lines held back, and paint them. With
+    /// `SideBySideRatio::Auto` the panels are split in proportion to the first changed lines,
+    /// and with `SideBySideMode::Auto` a hunk is painted in unified layout if some of its lines
+    /// do not fit into their panel, even after wrapping.
+    fn choose_hunk_layout(&mut self) {
+        let held_paint_ops = match self.held_paint_ops.take() {
+            Some(held_paint_ops) => held_paint_ops,
+            None => return,
+        };
+        if self.config.side_by_side_ratio == side_by_side::SideBySideRatio::Auto {
+            if let Some(lines) = held_paint_ops.iter().find_map(|(_, op)| match op {
+                PaintOp::MinusAndPlusLines { .. } => op.panel_lines(),
+                _ => None,
+            }) {
+                self.side_by_side_data = side_by_side::SideBySideData::for_left_percent(
+                    side_by_side::adaptive_left_percent(&lines, self.config),
+                    self.config,
+                );
+            }
+        }
+        if self.config.side_by_side_mode == side_by_side::SideBySideMode::Auto {
+            self.side_by_side = !held_paint_ops.iter().any(|(_, op)| {
+                matches!(
+                    (op.panel_lines(), &self.line_numbers_data),
+                    (Some(lines), Some(data)) if side_by_side::has_very_long_lines(
+                        &lines,
+                        &self.side_by_side_data,
+                        data,
+                        self.config
+                    )
+                )
+            });
         }
+        // The output following the held lines is written after them.
+        let output_buffer = std::mem::take(&mut self.output_buffer);
+        for (output, mut op) in held_paint_ops {
+            self.output_buffer.push_str(&output);
+            op.set_side_by_side_data(self.current_side_by_side_data());
+            self.paint(op);
+        }
+        self.output_buffer.push_str(&output_buffer);
     }
 
     pub fn set_syntax(&mut self, extension: Option<&str>) {
         self.syntax = Painter::get_syntax(&self.config.syntax_set, extension);
+        self.syntax_is_unknown = extension
+            .and_then(|extension| self.config.syntax_set.find_syntax_by_extension(extension))
+            .is_none();
+    }
+
+    /// Use the syntax of `language`, a language name or file extension. Return false if there is
+    /// no such syntax.
+    pub fn set_syntax_for_language(&mut self, language: &str) -> bool {
+        match self.config.syntax_set.find_syntax_by_token(language) {
+            Some(syntax) => {
+                self.syntax = syntax;
+                self.syntax_is_unknown = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// If the syntax is unknown from the file name, detect it from a shebang (or other first line
+    /// known to a syntax definition) if `line` is the first line of the file, or from a vim or
+    /// emacs modeline.
+    pub fn detect_syntax(&mut self, line: &str, is_first_line: bool) {
+        if !self.syntax_is_unknown {
+            return;
+        }
+        let syntax_set = &self.config.syntax_set;
+        let syntax = is_first_line
+            .then(|| syntax_set.find_syntax_by_first_line(line))
+            .flatten()
+            .or_else(|| {
+                utils::modeline::language_from_modeline(line)
+                    .and_then(|language| syntax_set.find_syntax_by_token(language))
+            });
+        if let Some(syntax) = syntax {
+            self.syntax = syntax;
+            self.syntax_is_unknown = false;
+            self.set_highlighter();
+        }
     }
 
     fn get_syntax<'a>(syntax_set: &'a SyntaxSet, extension: Option<&str>) -> &'a SyntaxReference {
@@ -120,64 +430,192 @@ impl<'p> Painter<'p> {
     }
 
     pub fn set_highlighter(&mut self) {
+        if let Some(pipeline) = self.writer.pipeline_for_painting() {
+            if pipeline.has_lane() {
+                // The syntax was detected within a hunk which is painted in the background.
+                pipeline.set_highlighter(self.syntax);
+                return;
+            }
+        }
         if let Some(ref syntax_theme) = self.config.syntax_theme {
-            self.highlighter = Some(HighlightLines::new(self.syntax, syntax_theme))
+            self.highlighter = Some(HighlightLines::new(self.syntax, syntax_theme));
+            self.new_highlighter_syntax = Some(self.syntax);
         };
     }
 
     pub fn paint_buffered_minus_and_plus_lines(&mut self) {
-        if self.minus_lines.is_empty() && self.plus_lines.is_empty() {
+        let have_changes = !(self.minus_lines.is_empty() && self.plus_lines.is_empty());
+        if let Some(structured_output) = &mut self.structured_output {
+            for (line, _) in self.zero_lines.drain(..) {
+                structured_output.write_context(&line);
+            }
+            if have_changes {
+                structured_output.write_changes(&self.minus_lines, &self.plus_lines, self.config);
+                self.minus_lines.clear();
+                self.plus_lines.clear();
+                self.plus_line_unicode_warnings.clear();
+            }
             return;
         }
-        paint_minus_and_plus_lines(
-            MinusPlus::new(&self.minus_lines, &self.plus_lines),
-            &mut self.line_numbers_data,
-            &mut self.highlighter,
-            &mut self.output_buffer,
-            self.config,
+        self.paint_buffered_zero_lines(have_changes);
+        if !have_changes {
+            return;
+        }
+        let lines = MinusPlus::new(
+            std::mem::take(&mut self.minus_lines),
+            std::mem::take(&mut self.plus_lines),
         );
-        self.minus_lines.clear();
-        self.plus_lines.clear();
+        let unicode_warnings = std::mem::take(&mut self.plus_line_unicode_warnings);
+        self.paint(PaintOp::MinusAndPlusLines {
+            lines,
+            unicode_warnings,
+            side_by_side_data: self.current_side_by_side_data(),
+        });
+        self.zero_lines_follow_change = true;
     }
 
-    pub fn paint_zero_line(&mut self, line: &str, state: State) {
-        let lines = &[(line.to_string(), state.clone())];
-        let syntax_style_sections =
-            get_syntax_style_sections_for_lines(lines, self.highlighter.as_mut(), self.config);
-        let mut diff_style_sections = vec![vec![(self.config.zero_style, lines[0].0.as_str())]]; // TODO: compute style from state
-        Painter::update_diff_style_sections(
-            lines,
-            &mut diff_style_sections,
-            None,
-            None,
-            &[false],
-            self.config,
-        );
-        if self.config.side_by_side {
-            // `lines[0].0` so the line has the '\n' already added (as in the +- case)
-            side_by_side::paint_zero_lines_side_by_side(
-                &lines[0].0,
-                syntax_style_sections,
-                diff_style_sections,
-                &mut self.output_buffer,
-                self.config,
-                &mut self.line_numbers_data.as_mut(),
-                painted_prefix(state, self.config),
-                BgShouldFill::With(BgFillMethod::Spaces),
-            );
+    /// Buffer an unchanged line which may be folded. Once there are enough lines for them to be
+    /// folded whatever follows, the lines kept before the fold are painted and the lines folded
+    /// so far are passed on, so that the buffer stays small in a long run of unchanged lines.
+    pub fn push_zero_line(&mut self, line: String, state: State) {
+        if self.structured_output.is_some() {
+            self.paint_zero_line(line, state);
+            return;
+        }
+        self.zero_lines.push((line, state));
+        let context = self.config.fold_unchanged_context;
+        if self.zero_lines.len() <= self.config.fold_unchanged_lines + 2 * context {
+            return;
+        }
+        let mut zero_lines = std::mem::take(&mut self.zero_lines).into_iter();
+        if self.n_folded_zero_lines == 0 && self.zero_lines_follow_change {
+            for (line, state) in zero_lines.by_ref().take(context) {
+                self.paint_zero_line(line, state);
+            }
+        }
+        // The last lines are kept if a change follows them.
+        let n_folded = zero_lines.len() - context;
+        self.n_folded_zero_lines += n_folded;
+        self.paint(PaintOp::FoldedZeroLines {
+            lines: zero_lines.by_ref().take(n_folded).collect(),
+            n_folded: None,
+        });
+        self.zero_lines = zero_lines.collect();
+    }
+
+    /// Paint the buffered unchanged lines. A run of more than `fold-unchanged-lines` of them
+    /// is folded into a single line, keeping `fold-unchanged-context` lines next to the
+    /// changes before and (if `followed_by_change`) after the run.
+    fn paint_buffered_zero_lines(&mut self, followed_by_change: bool) {
+        if self.zero_lines.is_empty() && self.n_folded_zero_lines == 0 {
+            return;
+        }
+        let zero_lines = std::mem::take(&mut self.zero_lines);
+        let context = self.config.fold_unchanged_context;
+        // The lines kept before the fold have been painted if some lines are already folded.
+        let n_head = if self.zero_lines_follow_change && self.n_folded_zero_lines == 0 {
+            context
         } else {
-            Painter::paint_lines(
-                lines,
-                &syntax_style_sections,
-                diff_style_sections.as_slice(),
-                &[false],
-                &mut self.output_buffer,
-                self.config,
-                &mut self.line_numbers_data.as_mut(),
-                None,
-                BgShouldFill::With(BgFillMethod::Spaces),
-            );
+            0
+        };
+        let n_tail = if followed_by_change { context } else { 0 };
+        let n_folded = zero_lines.len().saturating_sub(n_head + n_tail);
+        // Folding a single line would not save any space.
+        if self.n_folded_zero_lines == 0
+            && (zero_lines.len() <= self.config.fold_unchanged_lines || n_folded < 2)
+        {
+            for (line, state) in zero_lines {
+                self.paint_zero_line(line, state);
+            }
+            return;
+        }
+        let mut zero_lines = zero_lines.into_iter();
+        for (line, state) in zero_lines.by_ref().take(n_head) {
+            self.paint_zero_line(line, state);
+        }
+        let n_folded_before = std::mem::take(&mut self.n_folded_zero_lines);
+        self.paint(PaintOp::FoldedZeroLines {
+            lines: zero_lines.by_ref().take(n_folded).collect(),
+            n_folded: Some(n_folded_before + n_folded),
+        });
+        for (line, state) in zero_lines {
+            self.paint_zero_line(line, state);
+        }
+    }
+
+    pub fn paint_zero_line(&mut self, line: String, state: State) {
+        if let Some(structured_output) = &mut self.structured_output {
+            structured_output.write_context(&line);
+            return;
+        }
+        self.paint(PaintOp::ZeroLine {
+            line: (line, state),
+            side_by_side_data: self.current_side_by_side_data(),
+        });
+    }
+
+    /// The panels of the side-by-side layout, or None if lines are currently painted in unified
+    /// layout.
+    pub fn current_side_by_side_data(&self) -> Option<side_by_side::SideBySideData> {
+        if self.side_by_side {
+            Some(self.side_by_side_data.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Paint hunk lines: in the background if output is written through a pipeline (and not
+    /// held back), otherwise right away.
+    pub fn paint(&mut self, op: PaintOp) {
+        if let Some(held_paint_ops) = &mut self.held_paint_ops {
+            held_paint_ops.push((std::mem::take(&mut self.output_buffer), op));
+            if self.can_choose_hunk_layout() {
+                self.choose_hunk_layout();
+            }
+            return;
+        }
+        if let Some(pipeline) = self.writer.pipeline_for_painting() {
+            if !pipeline.has_lane() {
+                // The highlighter cannot be sent to a worker, so a hunk is only painted in the
+                // background if it starts with a new highlighter, which the worker recreates.
+                let syntax = match (&self.highlighter, self.new_highlighter_syntax) {
+                    (None, _) => Some(None),
+                    (Some(_), Some(syntax)) => Some(Some(syntax)),
+                    (Some(_), None) => None,
+                };
+                if let Some(syntax) = syntax {
+                    pipeline.start_lane(LaneStart {
+                        syntax,
+                        line_numbers_data: self.line_numbers_data.clone(),
+                        full_file_highlighting: self.full_file_highlighting.clone(),
+                    });
+                    self.new_highlighter_syntax = None;
+                }
+            }
+            if pipeline.has_lane() {
+                // The painted lines follow the output buffered so far.
+                pipeline.push_output(std::mem::take(&mut self.output_buffer).into_bytes());
+                pipeline.paint(op);
+                return;
+            }
         }
+        let mut paint_state = PaintState {
+            highlighter: self.highlighter.take(),
+            line_numbers_data: self.line_numbers_data.take(),
+            full_file_highlighting: self.full_file_highlighting.take(),
+        };
+        op.paint(&mut paint_state, &mut self.output_buffer, self.config);
+        self.highlighter = paint_state.highlighter;
+        self.line_numbers_data = paint_state.line_numbers_data;
+        self.full_file_highlighting = paint_state.full_file_highlighting;
+        self.new_highlighter_syntax = None;
+    }
+
+    /// Leave the lines of the current hunk to be painted in the background, without waiting for
+    /// them: the next hunk starts with a new highlighter and line numbers.
+    pub fn finish_hunk_painting(&mut self) {
+        self.choose_hunk_layout();
+        self.writer.finish_lane();
     }
 
     /// Superimpose background styles and foreground syntax
@@ -261,6 +699,8 @@ impl<'p> Painter<'p> {
         state: State,
         background_color_extends_to_terminal_width: BgShouldFill,
     ) {
+        self.finish_hunk_painting();
+        self.new_highlighter_syntax = None;
         let lines = vec![(
             expand_tabs(line.graphemes(true), self.config.tab_width),
             state,
@@ -407,10 +847,25 @@ impl<'p> Painter<'p> {
         let superimposed = superimpose_style_sections(
             syntax_sections,
             diff_sections,
-            config.true_color,
+            config.color_depth,
             config.null_syntect_style,
         );
 
+        let show_whitespace = whitespace::is_shown(state, config);
+        let changed_ranges = if show_whitespace {
+            whitespace::changed_ranges(diff_sections, state)
+        } else {
+            Vec::new()
+        };
+        // Without colors, emphasized sections are enclosed in textual markers instead.
+        let emph_markers = match state {
+            _ if !config.monochrome => None,
+            State::HunkMinus(_, _) | State::HunkMinusWrapped => Some(("[-", "-]")),
+            State::HunkPlus(_, _) | State::HunkPlusWrapped => Some(("{+", "+}")),
+            _ => None,
+        };
+        let mut in_emph = false;
+        let mut offset = 0;
         let mut handled_prefix = false;
         for (section_style, text) in &superimposed {
             // If requested re-insert the +/- prefix with proper styling.
@@ -419,22 +874,55 @@ impl<'p> Painter<'p> {
                     ansi_strings.push(painted_prefix)
                 }
             }
+            match emph_markers {
+                Some((begin, end)) if !text.is_empty() && section_style.is_emph != in_emph => {
+                    in_emph = section_style.is_emph;
+                    ansi_strings.push(section_style.paint(if in_emph { begin } else { end }));
+                }
+                _ => {}
+            }
 
-            if !text.is_empty() {
+            if show_whitespace {
+                ansi_strings.extend(whitespace::paint_showing_whitespace(
+                    text,
+                    offset,
+                    section_style.ansi_term_style,
+                    &changed_ranges,
+                    config,
+                ));
+            } else if !text.is_empty() {
                 ansi_strings.push(section_style.paint(text.as_str()));
             }
+            offset += text.len();
             handled_prefix = true;
         }
+        if in_emph {
+            ansi_strings.push(ansi_term::Style::new().paint(emph_markers.unwrap().1));
+        }
 
         // Only if syntax is empty (implies diff empty) can a line actually be empty.
         let is_empty = syntax_sections.is_empty();
         (ansi_term::ANSIStrings(&ansi_strings).to_string(), is_empty)
     }
 
+    /// Write the lines painted so far, including those still painted in the background if `wait`.
+    pub fn flush_painted_lines(&mut self, wait: bool) -> std::io::Result<()> {
+        self.emit()?;
+        self.writer.flush_painted_lines(wait)
+    }
+
     /// Write output buffer to output stream, and clear the buffer.
     pub fn emit(&mut self) -> std::io::Result<()> {
+        if self.held_paint_ops.is_some() {
+            // The output follows the lines held back.
+            return Ok(());
+        }
         write!(self.writer, "{}", self.output_buffer)?;
         self.output_buffer.clear();
+        self.writer.write_completed()?;
+        if let Some(structured_output) = &mut self.structured_output {
+            structured_output.emit()?;
+        }
         Ok(())
     }
 
@@ -495,18 +983,23 @@ impl<'p> Painter<'p> {
     ///    are going to be preserved in the output, then replace delta's
     ///    computed diff styles with these styles from the raw line. (This is
     ///    how support for git's --color-moved is implemented.)
+    /// 4. If the line contains suspicious Unicode characters, then the unicode warning style
+    ///    should be applied to the characters and to their labels.
+    #[allow(clippy::too_many_arguments)]
     fn update_diff_style_sections<'a>(
         lines: &'a [(String, State)],
         diff_style_sections: &mut Vec<LineSections<'a, Style>>,
         whitespace_error_style: Option<Style>,
+        unicode_warnings: Option<(Style, &[Vec<Range<usize>>])>,
         non_emph_style: Option<Style>,
         lines_have_homolog: &[bool],
         config: &config::Config,
     ) {
-        for (((_, state), style_sections), line_has_homolog) in lines
+        for (i, (((_, state), style_sections), line_has_homolog)) in lines
             .iter()
             .zip_eq(diff_style_sections)
             .zip_eq(lines_have_homolog)
+            .enumerate()
         {
             if let State::HunkMinus(_, Some(raw_line))
             | State::HunkZero(_, Some(raw_line))
@@ -539,6 +1032,18 @@ impl<'p> Painter<'p> {
                     *style = non_emph_style.unwrap();
                 }
             }
+            if let Some((unicode_warning_style, unicode_warnings)) = unicode_warnings {
+                match unicode_warnings.get(i) {
+                    Some(ranges) if !ranges.is_empty() => {
+                        *style_sections = suspicious_unicode::apply_style_to_ranges(
+                            style_sections,
+                            ranges,
+                            unicode_warning_style,
+                        );
+                    }
+                    _ => {}
+                }
+            }
         }
     }
 }
@@ -553,7 +1058,11 @@ pub fn prepare(line: &str, prefix_length: usize, config: &config::Config) -> Str
         // are not present during syntax highlighting or wrapping. If --keep-plus-minus-markers
         // is in effect the prefix is re-inserted in Painter::paint_line.
         let line = line.graphemes(true).skip(prefix_length);
-        format!("{}\n", expand_tabs(line, config.tab_width))
+        match &config.show_whitespace {
+            // Tabs are expanded as the whitespace is shown, when the line is painted.
+            Some(_) => format!("{}\n", line.collect::<String>()),
+            None => format!("{}\n", expand_tabs(line, config.tab_width)),
+        }
     } else {
         "\n".to_string()
     }
@@ -562,12 +1071,13 @@ pub fn prepare(line: &str, prefix_length: usize, config: &config::Config) -> Str
 // Remove initial -/+ characters, expand tabs as spaces, retaining ANSI sequences. Terminate with
 // newline character.
 pub fn prepare_raw_line(raw_line: &str, prefix_length: usize, config: &config::Config) -> String {
+    let expanded = match &config.show_whitespace {
+        Some(_) => raw_line.to_string(),
+        None => expand_tabs(raw_line.graphemes(true), config.tab_width),
+    };
     format!(
         "{}\n",
-        ansi::ansi_preserving_slice(
-            &expand_tabs(raw_line.graphemes(true), config.tab_width),
-            prefix_length
-        ),
+        ansi::ansi_preserving_slice(&expanded, prefix_length)
     )
 }
 
@@ -586,16 +1096,237 @@ where
     }
 }
 
+/// The state that the lines of a hunk are painted with, which changes from line to line.
+pub struct PaintState<'p> {
+    pub highlighter: Option<HighlightLines<'p>>,
+    pub line_numbers_data: Option<LineNumbersData<'p>>,
+    pub full_file_highlighting: Option<FullFileHighlighting>,
+}
+
+impl<'p> PaintState<'p> {
+    pub fn set_highlighter(&mut self, syntax: &'p SyntaxReference, config: &'p config::Config) {
+        if let Some(ref syntax_theme) = config.syntax_theme {
+            self.highlighter = Some(HighlightLines::new(syntax, syntax_theme));
+        }
+    }
+}
+
+/// The painting of some lines of a hunk. It depends only on the lines, on the layout chosen for
+/// them, and on the `PaintState` of the hunk, so that it can be done in the background (see
+/// `pipeline`).
+pub enum PaintOp {
+    MinusAndPlusLines {
+        lines: MinusPlus<Vec<(String, State)>>,
+        // The byte ranges of suspicious Unicode in each plus line, if any.
+        unicode_warnings: Vec<Vec<Range<usize>>>,
+        side_by_side_data: Option<side_by_side::SideBySideData>,
+    },
+    ZeroLine {
+        line: (String, State),
+        side_by_side_data: Option<side_by_side::SideBySideData>,
+    },
+    // Unchanged lines which are not shown. A long run of them may be folded in several parts: the
+    // row showing the number of lines folded, `n_folded`, is painted after the last part.
+    FoldedZeroLines {
+        lines: Vec<(String, State)>,
+        n_folded: Option<usize>,
+    },
+}
+
+impl PaintOp {
+    pub fn n_lines(&self) -> usize {
+        match self {
+            PaintOp::MinusAndPlusLines { lines, .. } => lines[Minus].len() + lines[Plus].len(),
+            PaintOp::ZeroLine { .. } => 1,
+            PaintOp::FoldedZeroLines { lines, .. } => lines.len(),
+        }
+    }
+
+    /// The lines of each panel in side-by-side layout, unchanged lines being in both, or None if
+    /// the lines are not shown.
+    fn panel_lines(&self) -> Option<MinusPlus<&[(String, State)]>> {
+        match self {
+            PaintOp::MinusAndPlusLines { lines, .. } => {
+                Some(MinusPlus::new(&lines[Minus], &lines[Plus]))
+            }
+            PaintOp::ZeroLine { line, .. } => Some(MinusPlus::new(
+                std::slice::from_ref(line),
+                std::slice::from_ref(line),
+            )),
+            PaintOp::FoldedZeroLines { .. } => None,
+        }
+    }
+
+    fn set_side_by_side_data(&mut self, data: Option<side_by_side::SideBySideData>) {
+        match self {
+            PaintOp::MinusAndPlusLines {
+                side_by_side_data, ..
+            }
+            | PaintOp::ZeroLine {
+                side_by_side_data, ..
+            } => *side_by_side_data = data,
+            PaintOp::FoldedZeroLines { .. } => {}
+        }
+    }
+
+    /// Paint the lines to `output_buffer`.
+    pub fn paint(
+        self,
+        paint_state: &mut PaintState,
+        output_buffer: &mut String,
+        config: &config::Config,
+    ) {
+        match self {
+            PaintOp::MinusAndPlusLines {
+                lines,
+                unicode_warnings,
+                side_by_side_data,
+            } => paint_minus_and_plus_lines(
+                MinusPlus::new(&lines[Minus], &lines[Plus]),
+                &unicode_warnings,
+                &mut paint_state.line_numbers_data,
+                &mut paint_state.highlighter,
+                &mut paint_state.full_file_highlighting,
+                output_buffer,
+                side_by_side_data.as_ref(),
+                config,
+            ),
+            PaintOp::ZeroLine {
+                line,
+                side_by_side_data,
+            } => paint_zero_line(
+                line,
+                side_by_side_data.as_ref(),
+                paint_state,
+                output_buffer,
+                config,
+            ),
+            PaintOp::FoldedZeroLines { lines, n_folded } => {
+                paint_folded_zero_lines(&lines, n_folded, paint_state, output_buffer, config)
+            }
+        }
+    }
+}
+
+fn paint_zero_line(
+    line: (String, State),
+    side_by_side_data: Option<&side_by_side::SideBySideData>,
+    paint_state: &mut PaintState,
+    output_buffer: &mut String,
+    config: &config::Config,
+) {
+    let state = line.1.clone();
+    let lines = &[line];
+    let syntax_style_sections = get_syntax_style_sections(
+        lines,
+        paint_state.highlighter.as_mut(),
+        paint_state.full_file_highlighting.as_mut(),
+        config,
+    );
+    let mut diff_style_sections = vec![vec![(config.zero_style, lines[0].0.as_str())]]; // TODO: compute style from state
+    Painter::update_diff_style_sections(
+        lines,
+        &mut diff_style_sections,
+        None,
+        None,
+        None,
+        &[false],
+        config,
+    );
+    if side_by_side_data.is_some() && config.output_format == OutputFormat::Html {
+        html::paint_zero_line_side_by_side(
+            &lines[0],
+            &syntax_style_sections[0],
+            &diff_style_sections[0],
+            output_buffer,
+            config,
+            &mut paint_state.line_numbers_data.as_mut(),
+        );
+    } else if let Some(side_by_side_data) = side_by_side_data {
+        // `lines[0].0` so the line has the '\n' already added (as in the +- case)
+        side_by_side::paint_zero_lines_side_by_side(
+            &lines[0].0,
+            syntax_style_sections,
+            diff_style_sections,
+            output_buffer,
+            side_by_side_data,
+            config,
+            &mut paint_state.line_numbers_data.as_mut(),
+            painted_prefix(state, config),
+            BgShouldFill::With(BgFillMethod::Spaces),
+        );
+    } else {
+        paint_hunk_lines(
+            lines,
+            &syntax_style_sections,
+            diff_style_sections.as_slice(),
+            &[false],
+            output_buffer,
+            config,
+            &mut paint_state
+                .line_numbers_data
+                .as_mut()
+                .filter(|_| config.line_numbers),
+            None,
+            BgShouldFill::With(BgFillMethod::Spaces),
+        );
+    }
+}
+
+fn paint_folded_zero_lines(
+    lines: &[(String, State)],
+    n_folded: Option<usize>,
+    paint_state: &mut PaintState,
+    output_buffer: &mut String,
+    config: &config::Config,
+) {
+    // The lines are not shown, but the highlighter needs to see them to stay in sync with
+    // the file content, e.g. when a multi-line comment starts in a folded line.
+    get_syntax_style_sections(
+        lines,
+        paint_state.highlighter.as_mut(),
+        paint_state.full_file_highlighting.as_mut(),
+        config,
+    );
+    if let Some(line_numbers_data) = paint_state.line_numbers_data.as_mut() {
+        line_numbers_data.skip_zero_lines(lines.len());
+    }
+    if let Some(n_folded) = n_folded {
+        let label = &config.fold_unchanged_label;
+        output_buffer.push_str(
+            &config
+                .fold_unchanged_style
+                .paint(format!("{label} {n_folded} unchanged lines {label}"))
+                .to_string(),
+        );
+        output_buffer.push('\n');
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn paint_minus_and_plus_lines(
     lines: MinusPlus<&Vec<(String, State)>>,
+    unicode_warnings: &[Vec<Range<usize>>],
     line_numbers_data: &mut Option<LineNumbersData>,
     highlighter: &mut Option<HighlightLines>,
+    full_file_highlighting: &mut Option<FullFileHighlighting>,
     output_buffer: &mut String,
+    side_by_side_data: Option<&side_by_side::SideBySideData>,
     config: &config::Config,
 ) {
     let syntax_style_sections = MinusPlus::new(
-        get_syntax_style_sections_for_lines(lines[Minus], highlighter.as_mut(), config),
-        get_syntax_style_sections_for_lines(lines[Plus], highlighter.as_mut(), config),
+        get_syntax_style_sections(
+            lines[Minus],
+            highlighter.as_mut(),
+            full_file_highlighting.as_mut(),
+            config,
+        ),
+        get_syntax_style_sections(
+            lines[Plus],
+            highlighter.as_mut(),
+            full_file_highlighting.as_mut(),
+            config,
+        ),
     );
     let (mut diff_style_sections, line_alignment) = get_diff_style_sections(&lines, config);
     let lines_have_homolog = edits::make_lines_have_homolog(&line_alignment);
@@ -603,6 +1334,7 @@ pub fn paint_minus_and_plus_lines(
         lines[Minus],
         &mut diff_style_sections[Minus],
         None,
+        None,
         if config.minus_non_emph_style != config.minus_emph_style {
             Some(config.minus_non_emph_style)
         } else {
@@ -615,6 +1347,11 @@ pub fn paint_minus_and_plus_lines(
         lines[Plus],
         &mut diff_style_sections[Plus],
         Some(config.whitespace_error_style),
+        if config.unicode_warnings != UnicodeWarnings::Off {
+            Some((config.unicode_warning_style, unicode_warnings))
+        } else {
+            None
+        },
         if config.plus_non_emph_style != config.plus_emph_style {
             Some(config.plus_non_emph_style)
         } else {
@@ -623,7 +1360,18 @@ pub fn paint_minus_and_plus_lines(
         &lines_have_homolog[Plus],
         config,
     );
-    if config.side_by_side {
+    if side_by_side_data.is_some() && config.output_format == OutputFormat::Html {
+        html::paint_minus_and_plus_lines_side_by_side(
+            lines,
+            syntax_style_sections,
+            diff_style_sections,
+            lines_have_homolog,
+            line_alignment,
+            line_numbers_data,
+            output_buffer,
+            config,
+        )
+    } else if let Some(side_by_side_data) = side_by_side_data {
         side_by_side::paint_minus_and_plus_lines_side_by_side(
             lines,
             syntax_style_sections,
@@ -632,32 +1380,33 @@ pub fn paint_minus_and_plus_lines(
             line_alignment,
             line_numbers_data,
             output_buffer,
+            side_by_side_data,
             config,
         )
     } else {
         // Unified diff mode:
         if !lines[Minus].is_empty() {
-            Painter::paint_lines(
+            paint_hunk_lines(
                 lines[Minus],
                 &syntax_style_sections[Minus],
                 &diff_style_sections[Minus],
                 &lines_have_homolog[Minus],
                 output_buffer,
                 config,
-                &mut line_numbers_data.as_mut(),
+                &mut line_numbers_data.as_mut().filter(|_| config.line_numbers),
                 Some(config.minus_empty_line_marker_style),
                 BgShouldFill::default(),
             );
         }
         if !lines[Plus].is_empty() {
-            Painter::paint_lines(
+            paint_hunk_lines(
                 lines[Plus],
                 &syntax_style_sections[Plus],
                 &diff_style_sections[Plus],
                 &lines_have_homolog[Plus],
                 output_buffer,
                 config,
-                &mut line_numbers_data.as_mut(),
+                &mut line_numbers_data.as_mut().filter(|_| config.line_numbers),
                 Some(config.plus_empty_line_marker_style),
                 BgShouldFill::default(),
             );
@@ -665,6 +1414,61 @@ pub fn paint_minus_and_plus_lines(
     }
 }
 
+/// Paint hunk lines as HTML elements with --output-format=html, and as ANSI-styled text
+/// otherwise.
+#[allow(clippy::too_many_arguments)]
+fn paint_hunk_lines<'a>(
+    lines: &'a [(String, State)],
+    syntax_style_sections: &[LineSections<'a, SyntectStyle>],
+    diff_style_sections: &[LineSections<'a, Style>],
+    lines_have_homolog: &[bool],
+    output_buffer: &mut String,
+    config: &config::Config,
+    line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
+    empty_line_style: Option<Style>,
+    background_color_extends_to_terminal_width: BgShouldFill,
+) {
+    if config.output_format == OutputFormat::Html {
+        html::paint_lines(
+            lines,
+            syntax_style_sections,
+            diff_style_sections,
+            lines_have_homolog,
+            output_buffer,
+            config,
+            line_numbers_data,
+            background_color_extends_to_terminal_width,
+        );
+    } else {
+        Painter::paint_lines(
+            lines,
+            syntax_style_sections,
+            diff_style_sections,
+            lines_have_homolog,
+            output_buffer,
+            config,
+            line_numbers_data,
+            empty_line_style,
+            background_color_extends_to_terminal_width,
+        );
+    }
+}
+
+/// Get the syntax style sections of `lines` from the highlighting of the full file if possible,
+/// otherwise from `highlighter`.
+fn get_syntax_style_sections<'a>(
+    lines: &'a [(String, State)],
+    highlighter: Option<&mut HighlightLines>,
+    full_file_highlighting: Option<&mut FullFileHighlighting>,
+    config: &config::Config,
+) -> Vec<LineSections<'a, SyntectStyle>> {
+    full_file_highlighting
+        .and_then(|full_file_highlighting| {
+            full_file_highlighting.syntax_style_sections(lines, config)
+        })
+        .unwrap_or_else(|| get_syntax_style_sections_for_lines(lines, highlighter, config))
+}
+
 pub fn get_syntax_style_sections_for_lines<'a>(
     lines: &'a [(String, State)],
     highlighter: Option<&mut HighlightLines>,
@@ -731,7 +1535,7 @@ fn get_diff_style_sections<'a>(
     (diff_sections, line_alignment)
 }
 
-fn painted_prefix(state: State, config: &config::Config) -> Option<ANSIString> {
+pub fn painted_prefix(state: State, config: &config::Config) -> Option<ANSIString> {
     use DiffType::*;
     use State::*;
     match (state, config.keep_plus_minus_markers) {
@@ -876,9 +1680,10 @@ fn is_whitespace_error(sections: &[(Style, &str)]) -> bool {
     false
 }
 
-mod superimpose_style_sections {
+pub mod superimpose_style_sections {
     use syntect::highlighting::Style as SyntectStyle;
 
+    use crate::color::{quantize_foreground_color, ColorDepth};
     use crate::style::Style;
     use crate::utils::bat::terminal::to_ansi_color;
 
@@ -893,7 +1698,7 @@ mod superimpose_style_sections {
     pub fn superimpose_style_sections(
         syntax_style_sections: &[(SyntectStyle, &str)],
         diff_style_sections: &[(Style, &str)],
-        true_color: bool,
+        color_depth: ColorDepth,
         null_syntect_style: SyntectStyle,
     ) -> Vec<(Style, String)> {
         coalesce(
@@ -903,7 +1708,7 @@ mod superimpose_style_sections {
                     .zip(explode(diff_style_sections))
                     .collect::<Vec<(&(SyntectStyle, char), (Style, char))>>(),
             ),
-            true_color,
+            color_depth,
             null_syntect_style,
         )
     }
@@ -940,14 +1745,21 @@ mod superimpose_style_sections {
 
     fn coalesce(
         style_sections: Vec<((SyntectStyle, Style), char)>,
-        true_color: bool,
+        color_depth: ColorDepth,
         null_syntect_style: SyntectStyle,
     ) -> Vec<(Style, String)> {
         let make_superimposed_style = |(syntect_style, style): (SyntectStyle, Style)| {
             if style.is_syntax_highlighted && syntect_style != null_syntect_style {
+                let foreground = to_ansi_color(
+                    syntect_style.foreground,
+                    color_depth == ColorDepth::TrueColor,
+                )
+                .map(|color| quantize_foreground_color(color, color_depth));
                 Style {
                     ansi_term_style: ansi_term::Style {
-                        foreground: to_ansi_color(syntect_style.foreground, true_color),
+                        foreground: foreground.map(|(color, _)| color),
+                        is_bold: style.ansi_term_style.is_bold
+                            || matches!(foreground, Some((_, true))),
                         ..style.ansi_term_style
                     },
                     ..style
@@ -1054,7 +1866,12 @@ mod superimpose_style_sections {
             let sections_2 = vec![(*SYNTAX_HIGHLIGHTED_STYLE, "ab")];
             let superimposed = vec![(*SUPERIMPOSED_STYLE, "ab".to_string())];
             assert_eq!(
-                superimpose_style_sections(&sections_1, &sections_2, true, SyntectStyle::default()),
+                superimpose_style_sections(
+                    &sections_1,
+                    &sections_2,
+                    ColorDepth::TrueColor,
+                    SyntectStyle::default()
+                ),
                 superimposed
             );
         }
@@ -1068,7 +1885,12 @@ mod superimpose_style_sections {
             ];
             let superimposed = vec![(*SUPERIMPOSED_STYLE, String::from("ab"))];
             assert_eq!(
-                superimpose_style_sections(&sections_1, &sections_2, true, SyntectStyle::default()),
+                superimpose_style_sections(
+                    &sections_1,
+                    &sections_2,
+                    ColorDepth::TrueColor,
+                    SyntectStyle::default()
+                ),
                 superimposed
             );
         }
@@ -1079,7 +1901,12 @@ mod superimpose_style_sections {
             let sections_2 = vec![(*NON_SYNTAX_HIGHLIGHTED_STYLE, "ab")];
             let superimposed = vec![(*NON_SYNTAX_HIGHLIGHTED_STYLE, "ab".to_string())];
             assert_eq!(
-                superimpose_style_sections(&sections_1, &sections_2, true, SyntectStyle::default()),
+                superimpose_style_sections(
+                    &sections_1,
+                    &sections_2,
+                    ColorDepth::TrueColor,
+                    SyntectStyle::default()
+                ),
                 superimposed
             );
         }
@@ -30,6 +30,9 @@ pub fn parse_styles(opt: &cli::Opt) -> HashMap<String, Style> {
     let mut resolved_styles = resolve_style_references(styles, opt);
     resolved_styles.get_mut("minus-emph-style").unwrap().is_emph = true;
     resolved_styles.get_mut("plus-emph-style").unwrap().is_emph = true;
+    for style in resolved_styles.values_mut() {
+        *style = style.quantize(opt.computed.color_depth);
+    }
     resolved_styles
 }
 
@@ -43,7 +46,7 @@ pub fn parse_styles_map(opt: &cli::Opt) -> Option<HashMap<style::AnsiTermStyleEq
                 let to_style = parse_as_style_or_reference_to_git_config(to_str, opt);
                 styles_map.insert(
                     style::ansi_term_style_equality_key(from_style.ansi_term_style),
-                    to_style,
+                    to_style.quantize(opt.computed.color_depth),
                 );
             }
         }
@@ -123,7 +126,7 @@ fn make_hunk_styles<'a>(opt: &'a cli::Opt, styles: &'a mut HashMap<&str, StyleRe
             None,
             Some(color::get_minus_background_color_default(
                 is_light_mode,
-                true_color,
+                opt.computed.color_depth,
             )),
         )),
         None,
@@ -137,7 +140,7 @@ fn make_hunk_styles<'a>(opt: &'a cli::Opt, styles: &'a mut HashMap<&str, StyleRe
             None,
             Some(color::get_minus_emph_background_color_default(
                 is_light_mode,
-                true_color,
+                opt.computed.color_depth,
             )),
         )),
         None,
@@ -161,7 +164,7 @@ fn make_hunk_styles<'a>(opt: &'a cli::Opt, styles: &'a mut HashMap<&str, StyleRe
             None,
             Some(color::get_minus_background_color_default(
                 is_light_mode,
-                true_color,
+                opt.computed.color_depth,
             )),
         )),
         None,
@@ -177,7 +180,7 @@ fn make_hunk_styles<'a>(opt: &'a cli::Opt, styles: &'a mut HashMap<&str, StyleRe
             None,
             Some(color::get_plus_background_color_default(
                 is_light_mode,
-                true_color,
+                opt.computed.color_depth,
             )),
         )),
         None,
@@ -191,7 +194,7 @@ fn make_hunk_styles<'a>(opt: &'a cli::Opt, styles: &'a mut HashMap<&str, StyleRe
             None,
             Some(color::get_plus_emph_background_color_default(
                 is_light_mode,
-                true_color,
+                opt.computed.color_depth,
             )),
         )),
         None,
@@ -215,7 +218,7 @@ fn make_hunk_styles<'a>(opt: &'a cli::Opt, styles: &'a mut HashMap<&str, StyleRe
             None,
             Some(color::get_plus_background_color_default(
                 is_light_mode,
-                true_color,
+                opt.computed.color_depth,
             )),
         )),
         None,
@@ -332,6 +335,26 @@ fn make_commit_file_hunk_header_styles(opt: &cli::Opt, styles: &mut HashMap<&str
                 opt.git_config(),
             ),
         ),
+        (
+            "summary-style",
+            style_from_str_with_handling_of_special_decoration_attributes(
+                &opt.summary_style,
+                None,
+                Some(&opt.summary_decoration_style),
+                true_color,
+                opt.git_config(),
+            ),
+        ),
+        (
+            "hunk-header-breadcrumb-style",
+            style_from_str_with_handling_of_special_decoration_attributes(
+                &opt.hunk_header_breadcrumb_style,
+                None,
+                None,
+                true_color,
+                opt.git_config(),
+            ),
+        ),
         (
             "hunk-header-file-style",
             style_from_str_with_handling_of_special_decoration_attributes(
@@ -461,6 +484,36 @@ fn make_misc_styles(opt: &cli::Opt, styles: &mut HashMap<&str, StyleReference>)
             opt.git_config(),
         ),
     );
+    styles.insert(
+        "unicode-warning-style",
+        style_from_str(
+            &opt.unicode_warning_style,
+            None,
+            None,
+            opt.computed.true_color,
+            opt.git_config(),
+        ),
+    );
+    styles.insert(
+        "whitespace-style",
+        style_from_str(
+            &opt.whitespace_style,
+            None,
+            None,
+            opt.computed.true_color,
+            opt.git_config(),
+        ),
+    );
+    styles.insert(
+        "fold-unchanged-style",
+        style_from_str(
+            &opt.fold_unchanged_style,
+            None,
+            None,
+            opt.computed.true_color,
+            opt.git_config(),
+        ),
+    );
     styles.insert(
         "git-minus-style",
         StyleReference::Style(
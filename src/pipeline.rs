@@ -0,0 +1,347 @@
+// Painting of hunk lines on worker threads (see --paint-threads).
+//
+// Parsing the input stays sequential, on the main thread. The lines of each hunk are painted in
+// the background: syntax highlighting, emphasis of changed words, and the layout of the lines.
+// Output is reassembled in input order, so that it is identical to sequential painting.
+//
+// The lines of a hunk depend on each other through the highlighter and the line numbers, which
+// change from line to line, but each hunk starts with a new highlighter and line numbers. The
+// painting of a hunk is therefore a "lane", which is assigned to a single worker: the lines are
+// sent to it in batches, and the worker keeps the state of the hunk from one batch to the next,
+// while different hunks are painted in parallel by different workers. The number of batches
+// waiting for a worker is bounded, so that memory use stays limited and output is written soon
+// after the input is read.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::Arc;
+use std::thread::Scope;
+
+use syntect::parsing::SyntaxReference;
+
+use crate::config::Config;
+use crate::features::line_numbers::LineNumbersData;
+use crate::full_file_highlighting::FullFileHighlighting;
+use crate::paint::{PaintOp, PaintState};
+
+// The number of lines after which the lines of a hunk are sent to the worker, without waiting
+// for the end of the hunk.
+const MAX_BATCH_LINES: usize = 256;
+
+// The number of batches which can wait for each worker.
+const JOBS_PER_WORKER: usize = 4;
+
+pub struct Pipeline<'p> {
+    workers: Vec<Worker<'p>>,
+    // The output, in input order: output written on the main thread, and the output of batches,
+    // which may not be painted yet.
+    segments: VecDeque<Segment>,
+    // The current hunk, if its lines are painted in the background.
+    lane: Option<Lane<'p>>,
+    // The number of segments beyond which writing output waits for the lines being painted.
+    max_segments: usize,
+}
+
+struct Worker<'p> {
+    jobs: SyncSender<Job<'p>>,
+    // The number of jobs sent to the worker which it has not finished yet.
+    n_pending_jobs: Arc<AtomicUsize>,
+}
+
+enum Segment {
+    Written(Vec<u8>),
+    Painted(Receiver<String>),
+}
+
+struct Lane<'p> {
+    worker: usize,
+    // The state that the hunk starts with, until the first batch is sent.
+    start: Option<LaneStart<'p>>,
+    // The lines of the hunk which have not been sent to the worker yet.
+    ops: Vec<LaneOp<'p>>,
+    n_lines: usize,
+}
+
+/// The state that the painting of a hunk starts with. The highlighter cannot be sent to another
+/// thread, so the worker creates a new one, for `syntax`; None if lines are not highlighted. The
+/// worker cannot use the git repository or the calling process either, so what depends on them,
+/// e.g. the files of `full_file_highlighting` or the path that line numbers link to, is resolved
+/// on the main thread.
+pub struct LaneStart<'p> {
+    pub syntax: Option<&'p SyntaxReference>,
+    pub line_numbers_data: Option<LineNumbersData<'p>>,
+    pub full_file_highlighting: Option<FullFileHighlighting>,
+}
+
+enum LaneOp<'p> {
+    Paint(PaintOp),
+    // The syntax was detected in the middle of the hunk.
+    SetHighlighter(&'p SyntaxReference),
+}
+
+struct Job<'p> {
+    start: Option<LaneStart<'p>>,
+    ops: Vec<LaneOp<'p>>,
+    output: Sender<String>,
+}
+
+impl<'p> Pipeline<'p> {
+    /// Start `n_workers` worker threads in `scope`. They stop when the pipeline is dropped.
+    pub fn start<'scope>(
+        scope: &'scope Scope<'scope, '_>,
+        n_workers: usize,
+        config: &'p Config,
+    ) -> Self
+    where
+        'p: 'scope,
+    {
+        let workers = (0..n_workers)
+            .map(|_| {
+                let (jobs, receiver) = mpsc::sync_channel::<Job>(JOBS_PER_WORKER);
+                let n_pending_jobs = Arc::new(AtomicUsize::new(0));
+                let n_jobs_left = Arc::clone(&n_pending_jobs);
+                scope.spawn(move || {
+                    let mut paint_state = None;
+                    for job in receiver {
+                        job.run(&mut paint_state, config);
+                        n_jobs_left.fetch_sub(1, Ordering::Relaxed);
+                    }
+                });
+                Worker {
+                    jobs,
+                    n_pending_jobs,
+                }
+            })
+            .collect();
+        Self {
+            workers,
+            segments: VecDeque::new(),
+            lane: None,
+            max_segments: 2 * n_workers * JOBS_PER_WORKER,
+        }
+    }
+
+    /// Whether all output has been written.
+    pub fn is_idle(&self) -> bool {
+        self.segments.is_empty() && self.lane.iter().all(|lane| lane.ops.is_empty())
+    }
+
+    pub fn has_lane(&self) -> bool {
+        self.lane.is_some()
+    }
+
+    /// Start painting the lines of a hunk in the background, on the least busy worker.
+    pub fn start_lane(&mut self, start: LaneStart<'p>) {
+        self.finish_lane();
+        let worker = (0..self.workers.len())
+            .min_by_key(|i| self.workers[*i].n_pending_jobs.load(Ordering::Relaxed))
+            .unwrap_or(0);
+        self.lane = Some(Lane {
+            worker,
+            start: Some(start),
+            ops: Vec::new(),
+            n_lines: 0,
+        });
+    }
+
+    /// Paint lines of the current hunk in the background.
+    pub fn paint(&mut self, op: PaintOp) {
+        let n_lines = op.n_lines();
+        self.push_op(LaneOp::Paint(op), n_lines);
+    }
+
+    /// Paint the following lines of the current hunk with a new highlighter for `syntax`.
+    pub fn set_highlighter(&mut self, syntax: &'p SyntaxReference) {
+        self.push_op(LaneOp::SetHighlighter(syntax), 0);
+    }
+
+    fn push_op(&mut self, op: LaneOp<'p>, n_lines: usize) {
+        let lane = self.lane.as_mut().expect("painting lines without a lane");
+        lane.n_lines += n_lines;
+        lane.ops.push(op);
+        if lane.n_lines >= MAX_BATCH_LINES {
+            self.send_batch();
+        }
+    }
+
+    /// Send the lines of the current hunk to its worker. The painting of the hunk may continue.
+    fn send_batch(&mut self) {
+        let lane = match &mut self.lane {
+            Some(lane) if !lane.ops.is_empty() => lane,
+            _ => return,
+        };
+        let (output, receiver) = mpsc::channel();
+        let worker = &self.workers[lane.worker];
+        worker.n_pending_jobs.fetch_add(1, Ordering::Relaxed);
+        worker
+            .jobs
+            .send(Job {
+                start: lane.start.take(),
+                ops: std::mem::take(&mut lane.ops),
+                output,
+            })
+            .expect("paint worker thread panicked");
+        lane.n_lines = 0;
+        self.segments.push_back(Segment::Painted(receiver));
+    }
+
+    /// Send the remaining lines of the current hunk to its worker: the next lines painted in the
+    /// background start a new hunk.
+    pub fn finish_lane(&mut self) {
+        self.send_batch();
+        self.lane = None;
+    }
+
+    /// Add output written on the main thread, after the lines painted so far.
+    pub fn push_output(&mut self, output: Vec<u8>) {
+        if output.is_empty() {
+            return;
+        }
+        self.send_batch();
+        match self.segments.back_mut() {
+            Some(Segment::Written(written)) => written.extend(output),
+            _ => self.segments.push_back(Segment::Written(output)),
+        }
+    }
+
+    /// Write the output which is complete, up to the first lines which are still being painted.
+    /// If `wait` is true, then wait for all lines sent to the workers to be painted. Otherwise,
+    /// only wait while there are too many segments.
+    pub fn write_output(&mut self, writer: &mut dyn Write, wait: bool) -> std::io::Result<()> {
+        while let Some(segment) = self.segments.front() {
+            match segment {
+                Segment::Written(written) => writer.write_all(written)?,
+                Segment::Painted(receiver) => {
+                    let painted = if wait || self.segments.len() > self.max_segments {
+                        receiver.recv().ok()
+                    } else {
+                        match receiver.try_recv() {
+                            Ok(painted) => Some(painted),
+                            Err(TryRecvError::Empty) => return Ok(()),
+                            Err(TryRecvError::Disconnected) => None,
+                        }
+                    };
+                    writer.write_all(painted.expect("paint worker thread panicked").as_bytes())?;
+                }
+            }
+            self.segments.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Write the output which is complete, after letting the lines of the current hunk received
+    /// so far be painted, without waiting for them.
+    pub fn write_painted_output(&mut self, writer: &mut dyn Write) -> std::io::Result<()> {
+        self.send_batch();
+        self.write_output(writer, false)
+    }
+
+    /// Write all output, waiting for the lines which are still being painted.
+    pub fn write_all_output(&mut self, writer: &mut dyn Write) -> std::io::Result<()> {
+        self.send_batch();
+        self.write_output(writer, true)
+    }
+}
+
+impl<'p> Job<'p> {
+    /// Paint the lines, with `paint_state`, the state of the worker's current hunk.
+    fn run(self, paint_state: &mut Option<PaintState<'p>>, config: &'p Config) {
+        if let Some(start) = self.start {
+            let mut state = PaintState {
+                highlighter: None,
+                line_numbers_data: start.line_numbers_data,
+                full_file_highlighting: start.full_file_highlighting,
+            };
+            if let Some(syntax) = start.syntax {
+                state.set_highlighter(syntax, config);
+            }
+            *paint_state = Some(state);
+        }
+        let paint_state = paint_state
+            .as_mut()
+            .expect("painting lines without the start of their hunk");
+        let mut output = String::new();
+        for op in self.ops {
+            match op {
+                LaneOp::Paint(op) => op.paint(paint_state, &mut output, config),
+                LaneOp::SetHighlighter(syntax) => paint_state.set_highlighter(syntax, config),
+            }
+        }
+        // The main thread may have stopped waiting for the output.
+        let _ = self.output.send(output);
+    }
+}
+
+/// The number of threads painting hunk lines for --paint-threads=`n_threads`: with 0, the number
+/// of available CPUs, up to 8. With a single thread, lines are painted on the main thread.
+pub fn n_paint_threads(n_threads: usize) -> usize {
+    match n_threads {
+        0 => std::thread::available_parallelism()
+            .map(|n| n.get().min(8))
+            .unwrap_or(1),
+        n => n,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::integration_test_utils;
+
+    // A diff of two files, with small hunks, a hunk longer than a batch, a multi-line comment,
+    // and a syntax only known from a modeline within a hunk.
+    fn make_diff() -> String {
+        let mut diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@
+ /* A comment
+-   spanning lines */
++   spanning several lines */
+ fn f() {}
+ fn g() {}
+"
+        .to_string();
+        let n_lines = 3 * super::MAX_BATCH_LINES;
+        diff.push_str(&format!("@@ -10,{n_lines} +10,{n_lines} @@ fn h() {{\n"));
+        for i in 0..n_lines / 3 {
+            diff.push_str(&format!(" let a{i} = \"unchanged\";\n"));
+            diff.push_str(&format!("-let b{i} = {i};\n"));
+            diff.push_str(&format!("+let b{i} = {};\n", i + 1));
+        }
+        diff.push_str(
+            "\
+diff --git a/script b/script
+index 3333333..4444444 100644
+--- a/script
++++ b/script
+@@ -1,3 +1,3 @@
+ # vim: set ft=python :
+-x = 'a'
++x = 'b'
+",
+        );
+        diff
+    }
+
+    #[test]
+    fn test_output_is_the_same_as_with_a_single_thread() {
+        let diff = make_diff();
+        for args in [
+            &[][..],
+            &["--syntax-theme", "GitHub", "--line-numbers"],
+            &["--side-by-side", "--width", "100"],
+            &["--fold-unchanged-lines", "5", "--line-numbers"],
+        ] {
+            let run = |n_threads: &str| {
+                let args = [args, &["--paint-threads", n_threads]].concat();
+                let config = integration_test_utils::make_config_from_args(&args);
+                integration_test_utils::run_delta(&diff, &config)
+            };
+            assert_eq!(run("4"), run("1"), "{args:?}");
+        }
+    }
+}
@@ -128,6 +128,33 @@ impl Style {
     pub fn to_painted_string(self) -> ansi_term::ANSIGenericString<'static, str> {
         self.paint(self.to_string())
     }
+
+    /// Convert the colors of the style and of its decoration to the palette of `color_depth`.
+    pub fn quantize(self, color_depth: color::ColorDepth) -> Self {
+        let quantize = |style| color::quantize_ansi_term_style(style, color_depth);
+        Self {
+            ansi_term_style: quantize(self.ansi_term_style),
+            decoration_style: match self.decoration_style {
+                DecorationStyle::Box(style) => DecorationStyle::Box(quantize(style)),
+                DecorationStyle::Underline(style) => DecorationStyle::Underline(quantize(style)),
+                DecorationStyle::Overline(style) => DecorationStyle::Overline(quantize(style)),
+                DecorationStyle::UnderOverline(style) => {
+                    DecorationStyle::UnderOverline(quantize(style))
+                }
+                DecorationStyle::BoxWithUnderline(style) => {
+                    DecorationStyle::BoxWithUnderline(quantize(style))
+                }
+                DecorationStyle::BoxWithOverline(style) => {
+                    DecorationStyle::BoxWithOverline(quantize(style))
+                }
+                DecorationStyle::BoxWithUnderOverline(style) => {
+                    DecorationStyle::BoxWithUnderOverline(quantize(style))
+                }
+                DecorationStyle::NoDecoration => DecorationStyle::NoDecoration,
+            },
+            ..self
+        }
+    }
 }
 
 /// Interpret `color_string` as a color specifier and return it painted accordingly.
@@ -0,0 +1,240 @@
+use std::io::Write;
+
+use ansi_term::Color;
+use itertools::Itertools;
+use syntect::highlighting::Color as SyntectColor;
+
+use crate::color::{color_to_rgb, contrast_ratio};
+use crate::config;
+use crate::minusplus::*;
+use crate::style::Style;
+
+type Rgb = (u8, u8, u8);
+
+// The minimum contrast ratio of normal text for WCAG level AA.
+const MIN_CONTRAST_RATIO: f64 = 4.5;
+
+/// Report the styles whose foreground and background colors have a contrast ratio below the WCAG
+/// minimum. Return the process exit code: 1 if any pair of colors fails.
+pub fn check_contrast(config: &config::Config, writer: &mut dyn Write) -> std::io::Result<i32> {
+    let (default_foreground, default_background) = default_colors(config);
+    let syntax_foregrounds = syntax_foregrounds(config);
+    let mut n_pairs = 0;
+    let mut n_failures = 0;
+    for (name, style) in styles(config) {
+        for (foreground, background, is_syntax) in color_pairs(
+            style,
+            default_foreground,
+            default_background,
+            &syntax_foregrounds,
+        ) {
+            n_pairs += 1;
+            let ratio = contrast_ratio(foreground, background);
+            if ratio >= MIN_CONTRAST_RATIO {
+                continue;
+            }
+            n_failures += 1;
+            let (fg, bg) = (rgb_color(foreground), rgb_color(background));
+            writeln!(
+                writer,
+                "{name:<30} {} {}{} on {}: {ratio:.2}:1",
+                fg.on(bg).paint(" Abc "),
+                if is_syntax { "syntax foreground " } else { "" },
+                hex(foreground),
+                hex(background),
+            )?;
+        }
+    }
+    if n_failures == 0 {
+        writeln!(
+            writer,
+            "All {n_pairs} pairs of colors have a contrast ratio of at least {MIN_CONTRAST_RATIO}:1."
+        )?;
+        Ok(0)
+    } else {
+        writeln!(
+            writer,
+            "{n_failures} of {n_pairs} pairs of colors have a contrast ratio below {MIN_CONTRAST_RATIO}:1."
+        )?;
+        Ok(1)
+    }
+}
+
+fn styles(config: &config::Config) -> Vec<(&'static str, Style)> {
+    vec![
+        ("commit-style", config.commit_style),
+        ("file-style", config.file_style),
+        ("hunk-header-style", config.hunk_header_style),
+        ("hunk-header-file-style", config.hunk_header_file_style),
+        (
+            "hunk-header-line-number-style",
+            config.hunk_header_line_number_style,
+        ),
+        ("minus-style", config.minus_style),
+        ("minus-non-emph-style", config.minus_non_emph_style),
+        ("minus-emph-style", config.minus_emph_style),
+        ("zero-style", config.zero_style),
+        ("plus-style", config.plus_style),
+        ("plus-non-emph-style", config.plus_non_emph_style),
+        ("plus-emph-style", config.plus_emph_style),
+        (
+            "line-numbers-minus-style",
+            config.line_numbers_style_minusplus[Minus],
+        ),
+        ("line-numbers-zero-style", config.line_numbers_zero_style),
+        (
+            "line-numbers-plus-style",
+            config.line_numbers_style_minusplus[Plus],
+        ),
+        ("grep-file-style", config.grep_file_style),
+        ("grep-line-number-style", config.grep_line_number_style),
+        ("whitespace-error-style", config.whitespace_error_style),
+        ("unicode-warning-style", config.unicode_warning_style),
+        ("summary-style", config.summary_style),
+    ]
+}
+
+/// The (foreground, background, is syntax foreground) colors of the text painted in a style.
+fn color_pairs(
+    style: Style,
+    default_foreground: Rgb,
+    default_background: Rgb,
+    syntax_foregrounds: &[Rgb],
+) -> Vec<(Rgb, Rgb, bool)> {
+    let ansi_term_style = style.ansi_term_style;
+    if style.is_omitted
+        || style.is_raw
+        || (ansi_term_style.foreground.is_none()
+            && ansi_term_style.background.is_none()
+            && !style.is_syntax_highlighted)
+    {
+        // The text is not shown, or is shown in the terminal's colors.
+        return vec![];
+    }
+    let foreground = ansi_term_style
+        .foreground
+        .map_or(default_foreground, color_to_rgb);
+    let background = ansi_term_style
+        .background
+        .map_or(default_background, color_to_rgb);
+    if ansi_term_style.is_reverse {
+        vec![(background, foreground, false)]
+    } else if style.is_syntax_highlighted && !syntax_foregrounds.is_empty() {
+        syntax_foregrounds
+            .iter()
+            .map(|foreground| (*foreground, background, true))
+            .collect()
+    } else {
+        vec![(foreground, background, false)]
+    }
+}
+
+/// The terminal's default colors, assumed to be those of the syntax theme.
+fn default_colors(config: &config::Config) -> (Rgb, Rgb) {
+    let (black, white) = ((0x00, 0x00, 0x00), (0xff, 0xff, 0xff));
+    let (foreground, background) = if config.is_light_mode {
+        (black, white)
+    } else {
+        (white, black)
+    };
+    match &config.syntax_theme {
+        Some(theme) => (
+            theme.settings.foreground.map_or(foreground, syntect_rgb),
+            theme.settings.background.map_or(background, syntect_rgb),
+        ),
+        None => (foreground, background),
+    }
+}
+
+/// The distinct foreground colors of the syntax theme.
+fn syntax_foregrounds(config: &config::Config) -> Vec<Rgb> {
+    match &config.syntax_theme {
+        Some(theme) => theme
+            .settings
+            .foreground
+            .into_iter()
+            .chain(
+                theme
+                    .scopes
+                    .iter()
+                    // Scopes with their own background are not painted over the style's.
+                    .filter(|item| item.style.background.is_none())
+                    .filter_map(|item| item.style.foreground),
+            )
+            .map(syntect_rgb)
+            .unique()
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn syntect_rgb(color: SyntectColor) -> Rgb {
+    (color.r, color.g, color.b)
+}
+
+fn rgb_color((r, g, b): Rgb) -> Color {
+    Color::RGB(r, g, b)
+}
+
+fn hex((r, g, b): Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_contrast;
+    use crate::ansi::strip_ansi_codes;
+    use crate::tests::integration_test_utils;
+
+    fn check(args: &[&str]) -> (i32, String) {
+        let config = integration_test_utils::make_config_from_args(args);
+        let mut writer = Vec::new();
+        let exit_code = check_contrast(&config, &mut writer).unwrap();
+        (
+            exit_code,
+            strip_ansi_codes(std::str::from_utf8(&writer).unwrap()),
+        )
+    }
+
+    #[test]
+    fn test_check_contrast() {
+        let (exit_code, output) = check(&[
+            "--syntax-theme",
+            "none",
+            "--dark",
+            "--true-color",
+            "always",
+            "--commit-style",
+            "#202020 #000000",
+            "--file-style",
+            "#ffffff #000000",
+            "--plus-style",
+            "#ffffff reverse",
+        ]);
+        assert_eq!(exit_code, 1);
+        let failures: Vec<&str> = output.lines().collect();
+        assert!(failures[0].starts_with("commit-style"));
+        assert!(failures[0].ends_with("#202020 on #000000: 1.29:1"));
+        assert!(!failures.iter().any(|line| line.starts_with("file-style")));
+        // Reverse video swaps the colors: the default black background on white passes.
+        assert!(!failures.iter().any(|line| line.starts_with("plus-style")));
+    }
+
+    #[test]
+    fn test_check_contrast_of_syntax_foregrounds() {
+        // Syntax foregrounds are checked over the background of syntax-highlighted styles.
+        let (_, output) = check(&[
+            "--syntax-theme",
+            "GitHub",
+            "--true-color",
+            "always",
+            "--plus-emph-style",
+            "syntax #000000",
+        ]);
+        assert!(output
+            .lines()
+            .any(|line| line.starts_with("plus-emph-style")
+                && line.contains("syntax foreground")
+                && line.contains("on #000000")));
+    }
+}
@@ -6,12 +6,14 @@ use bytelines::ByteLinesReader;
 
 use crate::config::{self, delta_unreachable};
 use crate::delta;
+use crate::git_config::GitConfig;
 
 /// Run `git diff` on the files provided on the command line and display the output.
 pub fn diff(
     minus_file: &Path,
     plus_file: &Path,
     config: &config::Config,
+    git_config: Option<&GitConfig>,
     writer: &mut dyn Write,
 ) -> i32 {
     use std::io::BufReader;
@@ -54,6 +56,7 @@ pub fn diff(
         BufReader::new(diff_process.stdout.take().unwrap()).byte_lines(),
         writer,
         config,
+        git_config,
     ) {
         match error.kind() {
             ErrorKind::BrokenPipe => return 0,
@@ -118,6 +121,7 @@ mod main_tests {
             &PathBuf::from(file_a),
             &PathBuf::from(file_b),
             &config,
+            None,
             &mut writer,
         );
         assert_eq!(exit_code, if expect_diff { 1 } else { 0 });
@@ -1,3 +1,4 @@
+pub mod check_contrast;
 pub mod diff;
 pub mod list_syntax_themes;
 pub mod parse_ansi;
@@ -21,7 +21,7 @@ pub fn show_colors() -> std::io::Result<()> {
         git_config::GitConfig::try_create(&env),
         assets,
     );
-    let config = config::Config::from(opt);
+    let (config, git_config) = config::Config::from_opt(opt);
 
     let mut output_type =
         OutputType::from_mode(&env, PagingMode::QuitIfOneScreen, None, &config).unwrap();
@@ -54,7 +54,7 @@ pub fn show_colors() -> std::io::Result<()> {
             }
             // Two syntax-highlighted lines with background color
             let color =
-                color::parse_color(color_name, config.true_color, config.git_config()).unwrap();
+                color::parse_color(color_name, config.true_color, git_config.as_ref()).unwrap();
             style.ansi_term_style.background = Some(color);
             for line in [
                 &format!(r#"export function color(): string {{ return "{color_name}" }}"#),
@@ -3,14 +3,21 @@ use std::io::Write;
 use itertools::Itertools;
 
 use crate::cli;
+use crate::color::ColorDepth;
 use crate::config;
-use crate::features::side_by_side::{Left, Right};
+use crate::features::side_by_side::{Left, Right, SideBySideMode, SideBySideRatio};
+use crate::git_config::GitConfig;
 use crate::minusplus::*;
 use crate::paint::BgFillMethod;
 use crate::style;
+use crate::suspicious_unicode::UnicodeWarnings;
 use crate::utils::bat::output::PagingMode;
 
-pub fn show_config(config: &config::Config, writer: &mut dyn Write) -> std::io::Result<()> {
+pub fn show_config(
+    config: &config::Config,
+    git_config: Option<&GitConfig>,
+    writer: &mut dyn Write,
+) -> std::io::Result<()> {
     // styles first
     writeln!(
         writer,
@@ -29,11 +36,14 @@ pub fn show_config(config: &config::Config, writer: &mut dyn Write) -> std::io::
     grep-file-style               = {grep_file_style}
     grep-line-number-style        = {grep_line_number_style}
     whitespace-error-style        = {whitespace_error_style}
+    whitespace-style              = {whitespace_style}
+    unicode-warning-style         = {unicode_warning_style}
+    summary-style                 = {summary_style}
     blame-palette                 = {blame_palette}",
         blame_palette = config
             .blame_palette
             .iter()
-            .map(|s| style::paint_color_string(s, config.true_color, config.git_config()))
+            .map(|s| style::paint_color_string(s, config.true_color, git_config))
             .join(" "),
         commit_style = config.commit_style.to_painted_string(),
         file_style = config.file_style.to_painted_string(),
@@ -49,18 +59,27 @@ pub fn show_config(config: &config::Config, writer: &mut dyn Write) -> std::io::
         grep_file_style = config.grep_file_style.to_painted_string(),
         grep_line_number_style = config.grep_line_number_style.to_painted_string(),
         whitespace_error_style = config.whitespace_error_style.to_painted_string(),
+        whitespace_style = config.whitespace_style.to_painted_string(),
+        unicode_warning_style = config.unicode_warning_style.to_painted_string(),
+        summary_style = config.summary_style.to_painted_string(),
         zero_style = config.zero_style.to_painted_string(),
     )?;
     // Everything else
     writeln!(
         writer,
         "    true-color                    = {true_color}
+    color-depth                   = {color_depth}
     file-added-label              = {file_added_label}
     file-modified-label           = {file_modified_label}
     file-removed-label            = {file_removed_label}
     file-renamed-label            = {file_renamed_label}
     right-arrow                   = {right_arrow}",
         true_color = config.true_color,
+        color_depth = match config.color_depth {
+            ColorDepth::Ansi16 => "16",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::TrueColor => "truecolor",
+        },
         file_added_label = format_option_value(&config.file_added_label),
         file_modified_label = format_option_value(&config.file_modified_label),
         file_removed_label = format_option_value(&config.file_removed_label),
@@ -126,9 +145,14 @@ pub fn show_config(config: &config::Config, writer: &mut dyn Write) -> std::io::
     pager                         = {pager}
     paging                        = {paging_mode}
     side-by-side                  = {side_by_side}
+    side-by-side-mode             = {side_by_side_mode}
+    side-by-side-ratio            = {side_by_side_ratio}
+    side-by-side-separator        = {side_by_side_separator}
+    show-whitespace               = {show_whitespace}
     syntax-theme                  = {syntax_theme}
     width                         = {width}
     tabs                          = {tab_width}
+    unicode-warnings              = {unicode_warnings}
     word-diff-regex               = {tokenization_regex}",
         diff_stat_align_width = config.diff_stat_align_width,
         max_line_distance = config.max_line_distance,
@@ -149,6 +173,16 @@ pub fn show_config(config: &config::Config, writer: &mut dyn Write) -> std::io::
             PagingMode::QuitIfOneScreen => "auto",
         },
         side_by_side = config.side_by_side,
+        side_by_side_mode = match config.side_by_side_mode {
+            SideBySideMode::Always => "always",
+            SideBySideMode::Auto => "auto",
+        },
+        side_by_side_ratio = match config.side_by_side_ratio {
+            SideBySideRatio::Fixed(percent) => format!("{percent}%"),
+            SideBySideRatio::Auto => "auto".to_string(),
+        },
+        side_by_side_separator = format_option_value(&config.side_by_side_separator),
+        show_whitespace = config.show_whitespace.is_some(),
         syntax_theme = config
             .syntax_theme
             .clone()
@@ -159,6 +193,11 @@ pub fn show_config(config: &config::Config, writer: &mut dyn Write) -> std::io::
             cli::Width::Variable => "variable".to_string(),
         },
         tab_width = config.tab_width,
+        unicode_warnings = match config.unicode_warnings {
+            UnicodeWarnings::Off => "off",
+            UnicodeWarnings::Lines => "lines",
+            UnicodeWarnings::Files => "files",
+        },
         tokenization_regex = format_option_value(config.tokenization_regex.to_string()),
     )?;
     Ok(())
@@ -194,7 +233,7 @@ mod tests {
     fn test_show_config() {
         let config = integration_test_utils::make_config_from_args(&[]);
         let mut writer = Cursor::new(vec![0; 1024]);
-        show_config(&config, &mut writer).unwrap();
+        show_config(&config, None, &mut writer).unwrap();
         let mut s = String::new();
         writer.rewind().unwrap();
         writer.read_to_string(&mut s).unwrap();
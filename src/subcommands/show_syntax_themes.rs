@@ -81,7 +81,7 @@ index f38589a..0f1bb83 100644
     };
 
     opt.computed.is_light_mode = is_light_mode;
-    let mut config = config::Config::from(opt);
+    let (mut config, git_config) = config::Config::from_opt(opt);
     let title_style = ansi_term::Style::new().bold();
     let assets = utils::bat::assets::load_highlighting_assets();
 
@@ -95,9 +95,12 @@ index f38589a..0f1bb83 100644
             title_style.paint(syntax_theme)
         )?;
         config.syntax_theme = Some(assets.get_theme(syntax_theme).clone());
-        if let Err(error) =
-            delta::delta(ByteLines::new(BufReader::new(&input[0..])), writer, &config)
-        {
+        if let Err(error) = delta::delta(
+            ByteLines::new(BufReader::new(&input[0..])),
+            writer,
+            &config,
+            git_config.as_ref(),
+        ) {
             match error.kind() {
                 ErrorKind::BrokenPipe => std::process::exit(0),
                 _ => eprintln!("{error}"),
@@ -46,7 +46,7 @@ pub fn show_themes(dark: bool, light: bool, computed_theme_is_light: bool) -> st
         );
         let is_dark_theme = opt.dark;
         let is_light_theme = opt.light;
-        let config = config::Config::from(opt);
+        let (config, git_config) = config::Config::from_opt(opt);
 
         if (!computed_theme_is_light && is_dark_theme)
             || (computed_theme_is_light && is_light_theme)
@@ -54,9 +54,12 @@ pub fn show_themes(dark: bool, light: bool, computed_theme_is_light: bool) -> st
         {
             writeln!(writer, "\n\nTheme: {}\n", title_style.paint(theme))?;
 
-            if let Err(error) =
-                delta::delta(ByteLines::new(BufReader::new(&input[0..])), writer, &config)
-            {
+            if let Err(error) = delta::delta(
+                ByteLines::new(BufReader::new(&input[0..])),
+                writer,
+                &config,
+                git_config.as_ref(),
+            ) {
                 match error.kind() {
                     ErrorKind::BrokenPipe => std::process::exit(0),
                     _ => eprintln!("{error}"),
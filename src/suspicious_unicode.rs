@@ -0,0 +1,275 @@
+// Detection of characters which can make the code that is reviewed differ from the code that is
+// compiled ("Trojan Source" attacks): bidirectional control characters, zero-width characters,
+// and identifiers mixing letters of confusable scripts, such as Latin and Cyrillic.
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::fatal;
+use crate::style::Style;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnicodeWarnings {
+    Off,
+    // Mark suspicious characters in added lines.
+    Lines,
+    // In addition, warn about them in the file header.
+    Files,
+}
+
+impl UnicodeWarnings {
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "off" => Self::Off,
+            "lines" => Self::Lines,
+            "files" => Self::Files,
+            _ => fatal(format!(
+                "Invalid value for unicode-warnings: {s}. Valid values are off, lines and files."
+            )),
+        }
+    }
+}
+
+/// Replace bidirectional control characters and zero-width characters in `line` by labels such
+/// as `<U+202E>`, and follow identifiers mixing confusable scripts by a label such as
+/// `<Latin+Cyrillic>`. Return the marked line, with the sorted byte ranges of the labels and of
+/// the identifiers they label, or None if the line contains nothing suspicious.
+pub fn mark_suspicious_unicode(line: &str) -> Option<(String, Vec<Range<usize>>)> {
+    if line.is_ascii() {
+        return None;
+    }
+    let mut marked = String::with_capacity(line.len());
+    let mut code_point_ranges = Vec::new();
+    for grapheme in line.graphemes(true) {
+        // Zero-width (non-)joiners are part of the spelling of words in some scripts, and of
+        // emoji sequences.
+        let joiners_are_legitimate = matches!(grapheme.chars().next(), Some(c) if !c.is_ascii());
+        for c in grapheme.chars() {
+            if is_bidi_control(c)
+                || matches!(c, '\u{200b}' | '\u{2060}' | '\u{feff}')
+                || (matches!(c, '\u{200c}' | '\u{200d}') && !joiners_are_legitimate)
+            {
+                let start = marked.len();
+                marked.push_str(&format!("<U+{:04X}>", c as u32));
+                code_point_ranges.push(start..marked.len());
+            } else {
+                marked.push(c);
+            }
+        }
+    }
+    let mut with_labels = String::with_capacity(marked.len());
+    let mut ranges = Vec::with_capacity(code_point_ranges.len());
+    let mut code_point_ranges = code_point_ranges.into_iter().peekable();
+    let mut last = 0;
+    for (range, label) in mixed_script_identifiers(&marked) {
+        // The labels of code points precede the identifier, which cannot contain them.
+        let shift = with_labels.len() - last;
+        while let Some(code_point_range) = code_point_ranges.next_if(|r| r.start < range.start) {
+            ranges.push(code_point_range.start + shift..code_point_range.end + shift);
+        }
+        with_labels.push_str(&marked[last..range.end]);
+        with_labels.push_str(&label);
+        ranges.push(range.start + shift..with_labels.len());
+        last = range.end;
+    }
+    let shift = with_labels.len() - last;
+    ranges.extend(code_point_ranges.map(|r| r.start + shift..r.end + shift));
+    with_labels.push_str(&marked[last..]);
+    if ranges.is_empty() {
+        None
+    } else {
+        Some((with_labels, ranges))
+    }
+}
+
+/// Apply `style` to the parts of `sections` which lie within `ranges`, splitting sections where
+/// necessary. `ranges` must be sorted and refer to byte offsets in the line made up by
+/// `sections`.
+pub fn apply_style_to_ranges<'a>(
+    sections: &[(Style, &'a str)],
+    ranges: &[Range<usize>],
+    style: Style,
+) -> Vec<(Style, &'a str)> {
+    let mut styled = Vec::with_capacity(sections.len() + 2 * ranges.len());
+    let mut offset = 0;
+    for (section_style, text) in sections {
+        let section = offset..offset + text.len();
+        let mut start = section.start;
+        for range in ranges
+            .iter()
+            .filter(|r| r.start < section.end && r.end > section.start)
+        {
+            let (range_start, range_end) = (range.start.max(start), range.end.min(section.end));
+            if start < range_start {
+                styled.push((*section_style, &text[start - offset..range_start - offset]));
+            }
+            styled.push((style, &text[range_start - offset..range_end - offset]));
+            start = range_end;
+        }
+        if start < section.end {
+            styled.push((*section_style, &text[start - offset..]));
+        }
+        offset = section.end;
+    }
+    styled
+}
+
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{61c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Return the byte ranges of the identifiers in `line` containing letters of more than one of
+/// the scripts whose letters are easily confused, with a label naming the scripts.
+fn mixed_script_identifiers(line: &str) -> Vec<(Range<usize>, String)> {
+    let mut identifiers = Vec::new();
+    let mut start = None;
+    for (i, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        match (start, c.is_alphanumeric() || c == '_') {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let mut scripts: Vec<&str> = Vec::new();
+                for script in line[s..i].chars().filter_map(confusable_script) {
+                    if !scripts.contains(&script) {
+                        scripts.push(script);
+                    }
+                }
+                if scripts.len() > 1 {
+                    identifiers.push((s..i, format!("<{}>", scripts.join("+"))));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    identifiers
+}
+
+/// The script of `c`, if it is one of those containing letters which look like Latin letters.
+fn confusable_script(c: char) -> Option<&'static str> {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{c0}'..='\u{24f}' | '\u{1e00}'..='\u{1eff}' => Some("Latin"),
+        '\u{370}'..='\u{3ff}' | '\u{1f00}'..='\u{1fff}' => Some("Greek"),
+        '\u{400}'..='\u{52f}' | '\u{1c80}'..='\u{1c8f}' | '\u{a640}'..='\u{a69f}' => {
+            Some("Cyrillic")
+        }
+        '\u{531}'..='\u{58f}' => Some("Armenian"),
+        '\u{13a0}'..='\u{13ff}' => Some("Cherokee"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::{make_config_from_args, run_delta, DeltaTest};
+
+    fn marked(line: &str) -> Option<String> {
+        mark_suspicious_unicode(line).map(|(marked, _)| marked)
+    }
+
+    #[test]
+    fn test_mark_suspicious_unicode() {
+        assert_eq!(marked("if x == 1 {"), None);
+        assert_eq!(marked("let ñandú = \"日本語\";"), None);
+        assert_eq!(
+            marked("/*\u{202e} } \u{2066}if (admin)\u{2069} \u{2066} begin*/"),
+            Some("/*<U+202E> } <U+2066>if (admin)<U+2069> <U+2066> begin*/".to_string())
+        );
+        assert_eq!(marked("a\u{200b}b = 1"), Some("a<U+200B>b = 1".to_string()));
+        // Cyrillic 'а' in an otherwise Latin identifier.
+        assert_eq!(
+            marked("if is_\u{430}dmin(user):"),
+            Some("if is_\u{430}dmin<Latin+Cyrillic>(user):".to_string())
+        );
+        // Joiners within emoji sequences are left alone.
+        assert_eq!(marked("s = \"👩\u{200d}💻\""), None);
+        // Text which merely looks like a label is not suspicious.
+        assert_eq!(marked("// <U+202E> reverses the text: é"), None);
+    }
+
+    #[test]
+    fn test_suspicious_ranges() {
+        let (line, ranges) =
+            mark_suspicious_unicode("x\u{202e} = is_\u{430}dmin\u{200b} // <U+202E>").unwrap();
+        let marked: Vec<&str> = ranges.into_iter().map(|range| &line[range]).collect();
+        assert_eq!(
+            marked,
+            ["<U+202E>", "is_\u{430}dmin<Latin+Cyrillic>", "<U+200B>"]
+        );
+    }
+
+    #[test]
+    fn test_unicode_warnings_in_added_lines() {
+        DeltaTest::with_args(&["--unicode-warnings", "lines"])
+            .with_input(TROJAN_SOURCE_DIFF)
+            .expect_after_header(
+                r#"
+                access_level = "user"
+                access_level = "user<U+202E> <U+2066>// Check if admin<U+2069> <U+2066>"
+                if is_аdmin<Latin+Cyrillic>(user):"#,
+            );
+    }
+
+    #[test]
+    fn test_unicode_warnings_style_only_marked_characters() {
+        let config = make_config_from_args(&["--tabs", "4", "--unicode-warnings", "lines"]);
+        let warning = |text: &str| config.unicode_warning_style.paint(text).to_string();
+        let output = run_delta(
+            "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -0,0 +1,2 @@
++# The text <U+202E> is not suspicious, é.
++\tx = 1 # \u{202e}
+",
+            &config,
+        );
+        assert_eq!(
+            output.matches(&warning("<U+202E>")).count(),
+            1,
+            "{:?}",
+            output
+        );
+        let lines: Vec<&str> = output.lines().skip(crate::config::HEADER_LEN).collect();
+        assert!(!lines[0].contains(&warning("<U+202E>")), "{:?}", lines[0]);
+        assert!(lines[1].contains(&warning("<U+202E>")), "{:?}", lines[1]);
+    }
+
+    #[test]
+    fn test_unicode_warnings_off_by_default() {
+        let output = DeltaTest::with_args(&[])
+            .with_input(TROJAN_SOURCE_DIFF)
+            .output;
+        assert!(!output.contains("<U+202E>"));
+        assert!(output.contains("\u{202e}"));
+    }
+
+    #[test]
+    fn test_unicode_warnings_in_file_header() {
+        DeltaTest::with_args(&["--unicode-warnings", "files"])
+            .with_input(TROJAN_SOURCE_DIFF)
+            .expect_after_skip(
+                1,
+                r#"#partial
+                a.py  ⚠ suspicious Unicode in 2 lines"#,
+            )
+            .expect_contains("if is_аdmin<Latin+Cyrillic>(user):");
+    }
+
+    const TROJAN_SOURCE_DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1 +1,2 @@
+-access_level = \"user\"
++access_level = \"user\u{202e} \u{2066}// Check if admin\u{2069} \u{2066}\"
++if is_\u{430}dmin(user):
+";
+}
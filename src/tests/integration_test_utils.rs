@@ -302,6 +302,7 @@ pub fn run_delta(input: &str, config: &config::Config) -> String {
         ByteLines::new(BufReader::new(input.as_bytes())),
         &mut writer,
         config,
+        None,
     )
     .unwrap();
     String::from_utf8(writer).unwrap()
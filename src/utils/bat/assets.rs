@@ -1,11 +1,14 @@
 // Based on code from https://github.com/sharkdp/bat a1b9334a44a2c652f52dddaa83dbacba57372468
 // See src/utils/bat/LICENSE
 
+use std::collections::HashMap;
 use std::io::{self, Write};
+use std::path::PathBuf;
 
 use ansi_term::Colour::Green;
 use ansi_term::Style;
 use bat;
+use syntect::parsing::SyntaxSet;
 
 use crate::utils;
 
@@ -14,15 +17,23 @@ pub fn load_highlighting_assets() -> bat::assets::HighlightingAssets {
         .unwrap_or_else(|_| bat::assets::HighlightingAssets::from_binary())
 }
 
-pub fn list_languages() -> std::io::Result<()> {
-    let assets = utils::bat::assets::load_highlighting_assets();
-    let mut languages = assets
-        .get_syntaxes()
-        .unwrap()
+/// List the languages of `syntax_set`, with the files of those loaded from --syntaxes-dir.
+pub fn list_languages(
+    syntax_set: &SyntaxSet,
+    syntax_sources: &HashMap<String, PathBuf>,
+) -> std::io::Result<()> {
+    let mut languages = syntax_set
+        .syntaxes()
         .iter()
         .filter(|syntax| !syntax.hidden && !syntax.file_extensions.is_empty())
         .collect::<Vec<_>>();
     languages.sort_by_key(|lang| lang.name.to_uppercase());
+    // A user syntax comes after the syntax of bat with the same name, which it overrides.
+    let last_indexes: HashMap<&str, usize> = languages
+        .iter()
+        .enumerate()
+        .map(|(i, lang)| (lang.name.as_str(), i))
+        .collect();
 
     let loop_through = false;
     let colored_output = true;
@@ -52,7 +63,7 @@ pub fn list_languages() -> std::io::Result<()> {
             Style::default()
         };
 
-        for lang in languages {
+        for (i, lang) in languages.iter().enumerate() {
             write!(stdout, "{:width$}{}", lang.name, separator, width = longest)?;
 
             // Number of characters on this line so far, wrap before `desired_width`
@@ -73,6 +84,12 @@ pub fn list_languages() -> std::io::Result<()> {
                     write!(stdout, "{comma_separator}")?;
                 }
             }
+            match syntax_sources.get(&lang.name) {
+                Some(path) if last_indexes[lang.name.as_str()] == i => {
+                    write!(stdout, "  ({})", path.display())?
+                }
+                _ => {}
+            }
             writeln!(stdout)?;
         }
     }
@@ -0,0 +1,207 @@
+// Syntax definitions (.sublime-syntax) and syntax themes (.tmTheme) loaded from a user directory
+// (see --syntaxes-dir), in addition to those of bat.
+//
+// Adding syntax definitions requires linking all syntaxes together, which takes long enough to be
+// noticeable at every startup. The resulting assets are therefore serialized to delta's cache
+// directory, with a fingerprint of the files that they were built from: the cache is rebuilt when
+// a file is added, removed or modified, or when bat's own assets or the delta version change.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use bat::assets::HighlightingAssets;
+use serde::{Deserialize, Serialize};
+use syntect::dumps::{dump_to_uncompressed_file, from_uncompressed_dump_file};
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxDefinition, SyntaxSet};
+
+use crate::utils::bat::dirs::PROJECT_DIRS;
+
+const SYNTAX_EXTENSION: &str = "sublime-syntax";
+const THEME_EXTENSION: &str = "tmTheme";
+
+#[derive(Serialize, Deserialize)]
+pub struct CustomAssets {
+    /// The syntaxes of bat together with those of the user directory.
+    pub syntax_set: SyntaxSet,
+    /// The themes of the user directory, by name (the file name without extension).
+    pub themes: BTreeMap<String, Theme>,
+    /// The files of the user directory that syntaxes were loaded from, by syntax name.
+    pub syntax_sources: HashMap<String, PathBuf>,
+}
+
+/// The directory of user syntaxes and themes: `dir` with a leading ~ expanded, or by default
+/// delta/syntaxes in $XDG_CONFIG_HOME or in ~/.config.
+pub fn syntaxes_dir(dir: Option<&str>) -> Option<PathBuf> {
+    match dir {
+        Some(dir) => match dir.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+            None => Some(PathBuf::from(dir)),
+        },
+        None => std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+            .map(|config_dir| config_dir.join("delta").join("syntaxes")),
+    }
+}
+
+/// Load the syntaxes and themes of `dir` together with `assets`, from the cache if it is up to
+/// date. Return None if `dir` contains no syntaxes or themes.
+pub fn load(dir: &Path, assets: &HighlightingAssets) -> Option<CustomAssets> {
+    let files = find_files(dir);
+    if files.is_empty() {
+        return None;
+    }
+    let fingerprint = fingerprint(&files);
+    // Next to bat's cache directory, e.g. ~/.cache/delta.
+    let cache_dir = PROJECT_DIRS.cache_dir().with_file_name("delta");
+    let cache_path = cache_dir.join("custom-assets.bin");
+    let fingerprint_path = cache_dir.join("custom-assets.fingerprint");
+    if fs::read_to_string(&fingerprint_path).ok().as_ref() == Some(&fingerprint) {
+        if let Ok(custom_assets) = from_uncompressed_dump_file(&cache_path) {
+            return Some(custom_assets);
+        }
+    }
+    let custom_assets = build(&files, assets);
+    // Failing to write the cache only costs time at the next startup.
+    if fs::create_dir_all(&cache_dir).is_ok()
+        && dump_to_uncompressed_file(&custom_assets, &cache_path).is_ok()
+    {
+        let _ = fs::write(&fingerprint_path, fingerprint);
+    }
+    Some(custom_assets)
+}
+
+fn build(files: &[PathBuf], assets: &HighlightingAssets) -> CustomAssets {
+    let mut builder = assets.get_syntax_set().unwrap().clone().into_builder();
+    let mut themes = BTreeMap::new();
+    let mut syntax_sources = HashMap::new();
+    for path in files {
+        let name = path.file_stem().and_then(|stem| stem.to_str());
+        if has_extension(path, THEME_EXTENSION) {
+            match ThemeSet::get_theme(path) {
+                Ok(theme) => {
+                    themes.insert(name.unwrap_or_default().to_string(), theme);
+                }
+                Err(err) => eprintln!("Failed to load syntax theme {}: {err}", path.display()),
+            }
+        } else {
+            match fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|contents| {
+                    SyntaxDefinition::load_from_str(&contents, true, name)
+                        .map_err(|err| err.to_string())
+                }) {
+                Ok(syntax) => {
+                    syntax_sources.insert(syntax.name.clone(), path.clone());
+                    builder.add(syntax);
+                }
+                Err(err) => eprintln!("Failed to load syntax {}: {err}", path.display()),
+            }
+        }
+    }
+    CustomAssets {
+        syntax_set: builder.build(),
+        themes,
+        syntax_sources,
+    }
+}
+
+/// The syntax and theme files in `dir` and its subdirectories, in a stable order.
+fn find_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            if path.is_dir() {
+                files.extend(find_files(&path));
+            } else if has_extension(&path, SYNTAX_EXTENSION)
+                || has_extension(&path, THEME_EXTENSION)
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some(extension)
+}
+
+/// Identify the inputs of the assets: the delta version, bat's cached assets, and the paths,
+/// sizes and modification times of the user files.
+fn fingerprint(files: &[PathBuf]) -> String {
+    let describe = |path: &Path| {
+        let (len, modified) = fs::metadata(path)
+            .map(|metadata| {
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |duration| duration.as_nanos());
+                (metadata.len(), modified)
+            })
+            .unwrap_or_default();
+        format!("{}\t{len}\t{modified}\n", path.display())
+    };
+    let mut fingerprint = format!("delta {}\n", env!("CARGO_PKG_VERSION"));
+    fingerprint.push_str(&describe(&PROJECT_DIRS.cache_dir().join("syntaxes.bin")));
+    for path in files {
+        fingerprint.push_str(&describe(path));
+    }
+    fingerprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYNTAX: &str = "\
+%YAML 1.2
+---
+name: Zork
+file_extensions: [zork]
+scope: source.zork
+contexts:
+  main:
+    - match: '\\b(go|take)\\b'
+      scope: keyword.control.zork
+";
+
+    #[test]
+    fn test_load_custom_assets() {
+        let dir = std::env::temp_dir().join("delta-test-custom-assets");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("zork")).unwrap();
+        let syntax_path = dir.join("zork").join("Zork.sublime-syntax");
+        fs::write(&syntax_path, SYNTAX).unwrap();
+        fs::write(dir.join("README.md"), "not a syntax").unwrap();
+
+        let assets = crate::utils::bat::assets::load_highlighting_assets();
+        let custom_assets = build(&find_files(&dir), &assets);
+        let syntax = custom_assets
+            .syntax_set
+            .find_syntax_by_extension("zork")
+            .unwrap();
+        assert_eq!(syntax.name, "Zork");
+        assert_eq!(custom_assets.syntax_sources["Zork"], syntax_path);
+        // The syntaxes of bat remain available.
+        assert!(custom_assets
+            .syntax_set
+            .find_syntax_by_extension("rs")
+            .is_some());
+        assert!(custom_assets.themes.is_empty());
+
+        let files = find_files(&dir);
+        assert_eq!(files, vec![syntax_path.clone()]);
+        let before = fingerprint(&files);
+        fs::write(&syntax_path, format!("{SYNTAX}\n")).unwrap();
+        assert_ne!(fingerprint(&files), before);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
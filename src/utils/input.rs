@@ -0,0 +1,286 @@
+// Input read on a background thread, so that delta knows when reading the next line would block.
+//
+// While a slow producer (e.g. `git log -p` over a large repository) is computing its next lines,
+// delta writes the lines it has painted so far, so that the pager shows them right away. The
+// lines are read as usual with `ByteLines`: before blocking, the reader returns an error of kind
+// `WouldBlock`, and after a short idle interval an error of kind `TimedOut`, always at the start
+// of a line, after which reading can continue (see `StateMachine::consume`).
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TryRecvError};
+use std::time::Duration;
+
+// How long to wait for more input, after the lines painted so far have been written, before
+// also writing the lines which are still being painted.
+const IDLE_INTERVAL: Duration = Duration::from_millis(50);
+
+// The size above which a chunk of input is passed on without waiting for the rest of the lines
+// already available.
+const MAX_CHUNK_SIZE: usize = 1 << 16;
+
+// The number of chunks read ahead.
+const MAX_PENDING_CHUNKS: usize = 16;
+
+pub struct InputReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    // The chunk being read, which ends with complete lines except at the end of the input.
+    chunk: Vec<u8>,
+    pos: usize,
+    waiting: Waiting,
+}
+
+#[derive(PartialEq)]
+enum Waiting {
+    No,
+    // `WouldBlock` has been returned.
+    WouldBlock,
+    // `TimedOut` has been returned: the next read blocks.
+    Idle,
+}
+
+impl InputReader {
+    /// Read `reader` on a background thread, which stops at the end of the input, or when the
+    /// `InputReader` is dropped.
+    pub fn new<R>(reader: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let (sender, chunks) = mpsc::sync_channel(MAX_PENDING_CHUNKS);
+        std::thread::spawn(move || read_chunks(BufReader::new(reader), sender));
+        Self {
+            chunks,
+            chunk: Vec::new(),
+            pos: 0,
+            waiting: Waiting::No,
+        }
+    }
+
+    fn is_at_line_start(&self) -> bool {
+        self.pos == 0 || self.chunk[self.pos - 1] == b'\n'
+    }
+}
+
+/// Send the lines of `reader` in chunks of complete lines, each one as soon as no further
+/// complete line is available without blocking.
+fn read_chunks<R>(mut reader: BufReader<R>, chunks: SyncSender<io::Result<Vec<u8>>>)
+where
+    R: Read,
+{
+    let mut chunk = Vec::new();
+    loop {
+        match reader.read_until(b'\n', &mut chunk) {
+            Ok(0) => break,
+            Ok(_) => {
+                let is_chunk_complete =
+                    chunk.len() >= MAX_CHUNK_SIZE || !reader.buffer().contains(&b'\n');
+                if is_chunk_complete && chunks.send(Ok(std::mem::take(&mut chunk))).is_err() {
+                    return;
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => {
+                if !chunk.is_empty() {
+                    let _ = chunks.send(Ok(std::mem::take(&mut chunk)));
+                }
+                let _ = chunks.send(Err(error));
+                return;
+            }
+        }
+    }
+    if !chunk.is_empty() {
+        let _ = chunks.send(Ok(chunk));
+    }
+}
+
+impl Read for InputReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for InputReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.chunk.len() {
+            let chunk = match self.chunks.try_recv() {
+                Ok(chunk) => Some(chunk),
+                Err(TryRecvError::Disconnected) => None,
+                // Within a line, or once idle, wait for the rest of the input.
+                Err(TryRecvError::Empty)
+                    if !self.is_at_line_start() || self.waiting == Waiting::Idle =>
+                {
+                    self.chunks.recv().ok()
+                }
+                Err(TryRecvError::Empty) if self.waiting == Waiting::No => {
+                    self.waiting = Waiting::WouldBlock;
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+                Err(TryRecvError::Empty) => match self.chunks.recv_timeout(IDLE_INTERVAL) {
+                    Ok(chunk) => Some(chunk),
+                    Err(RecvTimeoutError::Disconnected) => None,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.waiting = Waiting::Idle;
+                        return Err(io::ErrorKind::TimedOut.into());
+                    }
+                },
+            };
+            self.waiting = Waiting::No;
+            match chunk {
+                Some(chunk) => {
+                    self.chunk = chunk?;
+                    self.pos = 0;
+                }
+                // The end of the input.
+                None => return Ok(&[]),
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use bytelines::ByteLinesReader;
+
+    use super::InputReader;
+    use crate::ansi::strip_ansi_codes;
+    use crate::delta::delta;
+    use crate::tests::integration_test_utils;
+
+    // A reader of the chunks of input sent to it, which signals whenever it has passed on all of
+    // them and waits for the next one.
+    struct ChannelReader {
+        chunks: Receiver<Vec<u8>>,
+        chunk: Vec<u8>,
+        waiting: Sender<()>,
+    }
+
+    impl ChannelReader {
+        fn new(chunks: Receiver<Vec<u8>>) -> (Self, Receiver<()>) {
+            let (waiting, is_waiting) = mpsc::channel();
+            let reader = Self {
+                chunks,
+                chunk: Vec::new(),
+                waiting,
+            };
+            (reader, is_waiting)
+        }
+    }
+
+    impl Read for ChannelReader {
+        fn read(&mut self, mut buf: &mut [u8]) -> io::Result<usize> {
+            if self.chunk.is_empty() {
+                let _ = self.waiting.send(());
+                self.chunk = self.chunks.recv().unwrap_or_default();
+            }
+            let n = buf.write(&self.chunk)?;
+            self.chunk.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_reader_signals_when_input_would_block_at_line_starts() {
+        let (sender, receiver) = mpsc::channel();
+        let (reader, is_waiting) = ChannelReader::new(receiver);
+        let mut lines = InputReader::new(reader).byte_lines();
+        is_waiting.recv().unwrap();
+        // Wait until the input has been read in the background.
+        let send = |input: &[u8]| {
+            sender.send(input.to_vec()).unwrap();
+            is_waiting.recv().unwrap();
+        };
+        send(b"a\nb");
+        assert_eq!(lines.next().unwrap().unwrap(), b"a");
+        let error = lines.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        let error = lines.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        send(b"c\nd");
+        assert_eq!(lines.next().unwrap().unwrap(), b"bc");
+        // The last line, without a newline, is only passed on at the end of the input.
+        let error = lines.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+        drop(sender);
+        let line = loop {
+            match lines.next().unwrap() {
+                // The end of the input may not have been read in the background yet.
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                line => break line,
+            }
+        };
+        assert_eq!(line.unwrap(), b"d");
+        assert!(lines.next().is_none());
+    }
+
+    // A writer whose output can be inspected while delta is running, and which signals each write.
+    #[derive(Clone)]
+    struct SharedWriter(Arc<Mutex<Vec<u8>>>, Sender<()>);
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = self.0.lock().unwrap().write(buf)?;
+            let _ = self.1.send(());
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_painted_lines_are_written_while_waiting_for_input() {
+        let config = integration_test_utils::make_config_from_args(&["--paint-threads", "2"]);
+        let (sender, receiver) = mpsc::channel();
+        let (reader, _) = ChannelReader::new(receiver);
+        let lines = InputReader::new(reader).byte_lines();
+        let (has_written, writes) = mpsc::channel();
+        let output = SharedWriter(Arc::default(), has_written);
+        std::thread::scope(|scope| {
+            let mut writer = output.clone();
+            let config = &config;
+            let delta = scope.spawn(move || delta(lines, &mut writer, config, None));
+            sender
+                .send(
+                    b"\
+diff --git a/a.rs b/a.rs
+index 1111111..2222222 100644
+--- a/a.rs
++++ b/a.rs
+@@ -1,2 +1,2 @@
+-let first = 1;
++let first = 2;
+ let second = 3;
+"
+                    .to_vec(),
+                )
+                .unwrap();
+            // The input is still open: the lines are written while delta waits for more of it.
+            let written = || strip_ansi_codes(&String::from_utf8_lossy(&output.0.lock().unwrap()));
+            while !written().contains("let second = 3;") {
+                assert!(
+                    writes.recv_timeout(Duration::from_secs(10)).is_ok(),
+                    "{}",
+                    written()
+                );
+            }
+            drop(sender);
+            delta.join().unwrap().unwrap();
+        });
+    }
+}
@@ -1,8 +1,12 @@
 #[cfg(not(tarpaulin_include))]
 pub mod bat;
+pub mod custom_assets;
+pub mod input;
+pub mod modeline;
 pub mod path;
 pub mod process;
 pub mod regex_replacement;
 pub mod round_char_boundary;
 pub mod syntect;
+pub mod terminal_background;
 pub mod workarounds;
@@ -0,0 +1,64 @@
+// Vim and emacs modelines, which name the language of a file in a comment, e.g.
+// "# vim: set ft=python :" or "// -*- mode: c++ -*-".
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref VIM_MODELINE_REGEX: Regex = Regex::new(
+        r"(?:^|\s)(?:vi|vim|Vim|ex)(?:[<=>]?\d+)?:.*?\b(?:ft|filetype|syntax)=([\w+#.-]+)"
+    )
+    .unwrap();
+    static ref EMACS_MODELINE_REGEX: Regex = Regex::new(r"-\*-(.*?)-\*-").unwrap();
+    static ref EMACS_MODE_REGEX: Regex = Regex::new(r"(?:^|;)\s*mode:\s*([\w+#.-]+)").unwrap();
+}
+
+// Emacs modes whose names are neither language names nor file extensions.
+const EMACS_MODE_ALIASES: &[(&str, &str)] = &[("emacs-lisp", "el"), ("shell-script", "sh")];
+
+/// Return the language named by a vim or emacs modeline in `line`: a language name or a file
+/// extension.
+pub fn language_from_modeline(line: &str) -> Option<&str> {
+    if let Some(captures) = VIM_MODELINE_REGEX.captures(line) {
+        return captures.get(1).map(|m| m.as_str());
+    }
+    let variables = EMACS_MODELINE_REGEX.captures(line)?.get(1)?.as_str();
+    let mode = if variables.contains(':') {
+        EMACS_MODE_REGEX.captures(variables)?.get(1)?.as_str()
+    } else {
+        // E.g. "-*- python -*-".
+        variables.trim()
+    };
+    if mode.is_empty() || mode.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(
+        EMACS_MODE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == mode)
+            .map_or(mode, |(_, language)| language),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::language_from_modeline;
+
+    #[test]
+    fn test_language_from_modeline() {
+        for (line, expected) in [
+            ("# vim: set ft=python :", Some("python")),
+            ("/* vim: set ts=4 filetype=c sw=4: */", Some("c")),
+            ("// vi: syntax=javascript", Some("javascript")),
+            ("# -*- mode: ruby; coding: utf-8 -*-", Some("ruby")),
+            ("# -*- coding: utf-8; mode: python -*-", Some("python")),
+            (";; -*- emacs-lisp -*-", Some("el")),
+            ("// -*- c++ -*-", Some("c++")),
+            ("# -*- coding: utf-8 -*-", None),
+            ("let vim = 1; let ft=2;", None),
+            ("plain text", None),
+        ] {
+            assert_eq!(language_from_modeline(line), expected, "{line}");
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// Query the terminal for its background color.
+//
+// The query is the OSC 11 control sequence, which most terminal emulators answer with the
+// background color as e.g. "\x1b]11;rgb:ffff/ffff/ffff\x1b\\". It is followed by a request for the
+// primary device attributes (DA1), which virtually all terminals answer, so that we need not wait
+// for the timeout when a terminal ignores OSC 11.
+
+use std::time::Duration;
+
+/// Return the background color of the terminal as RGB, or None if it does not report it within
+/// `timeout`.
+#[cfg(unix)]
+pub fn query_background_color(timeout: Duration) -> Option<(u8, u8, u8)> {
+    let response = query_terminal("\x1b]11;?\x1b\\\x1b[c", timeout)?;
+    parse_osc_11_response(&response)
+}
+
+#[cfg(not(unix))]
+pub fn query_background_color(_timeout: Duration) -> Option<(u8, u8, u8)> {
+    None
+}
+
+// How long to wait, after the timeout, for the rest of a late response: it would otherwise be
+// left in the terminal's input, to be read by e.g. the shell or the pager.
+#[cfg(unix)]
+const LATE_RESPONSE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Write `query` to the terminal and return its response, up to the end of its DA1 response.
+#[cfg(unix)]
+fn query_terminal(query: &str, timeout: Duration) -> Option<String> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+
+    // Read the response unbuffered and without echoing it.
+    let mut termios = std::mem::MaybeUninit::uninit();
+    if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let original_termios = unsafe { termios.assume_init() };
+    let mut raw_termios = original_termios;
+    raw_termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw_termios) } != 0 {
+        return None;
+    }
+
+    let mut response = Vec::new();
+    let mut is_late = false;
+    if tty.write_all(query.as_bytes()).is_ok() && tty.flush().is_ok() {
+        read_response(&mut tty, &mut response, Instant::now() + timeout);
+        if !is_complete_response(&response) {
+            // The response is discarded, but it must still be read.
+            is_late = true;
+            read_response(
+                &mut tty,
+                &mut response,
+                Instant::now() + LATE_RESPONSE_TIMEOUT,
+            );
+        }
+    }
+
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original_termios) };
+    if is_late {
+        return None;
+    }
+    String::from_utf8(response).ok()
+}
+
+/// Read from `tty` into `response` until the response is complete or `deadline` has passed.
+#[cfg(unix)]
+fn read_response(tty: &mut std::fs::File, response: &mut Vec<u8>, deadline: std::time::Instant) {
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    use std::time::Instant;
+
+    let mut buffer = [0; 64];
+    while !is_complete_response(response) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut pollfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if remaining.is_zero()
+            || unsafe { libc::poll(&mut pollfd, 1, remaining.as_millis() as libc::c_int) } <= 0
+        {
+            break;
+        }
+        match tty.read(&mut buffer) {
+            Ok(n) if n > 0 => response.extend_from_slice(&buffer[..n]),
+            _ => break,
+        }
+    }
+}
+
+// The DA1 response is "\x1b[?" followed by parameters and "c".
+#[cfg(unix)]
+fn is_complete_response(response: &[u8]) -> bool {
+    match response.windows(3).position(|w| w == b"\x1b[?") {
+        Some(i) => response[i..].contains(&b'c'),
+        None => false,
+    }
+}
+
+/// Parse an OSC 11 response such as "\x1b]11;rgb:ffff/ffff/ffff\x1b\\", in which each component
+/// has one to four hexadecimal digits.
+fn parse_osc_11_response(response: &str) -> Option<(u8, u8, u8)> {
+    let start = response.find("\x1b]11;rgb:")? + "\x1b]11;rgb:".len();
+    let end = response[start..].find(['\x1b', '\x07'])? + start;
+    let mut components = response[start..end].split('/').map(|component| {
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = match component.len() {
+            1..=4 => (1 << (4 * component.len())) - 1,
+            _ => return None,
+        };
+        Some((value * 255 / max) as u8)
+    });
+    let rgb = (
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+    match components.next() {
+        None => Some(rgb),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_osc_11_response;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_response_consumes_late_response() {
+        use std::io::Write;
+        use std::os::unix::io::FromRawFd;
+        use std::time::{Duration, Instant};
+
+        use super::read_response;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (mut reader, mut writer) = unsafe {
+            (
+                std::fs::File::from_raw_fd(fds[0]),
+                std::fs::File::from_raw_fd(fds[1]),
+            )
+        };
+        let mut response = Vec::new();
+        writer
+            .write_all(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\")
+            .unwrap();
+        read_response(
+            &mut reader,
+            &mut response,
+            Instant::now() + Duration::from_millis(10),
+        );
+        assert_eq!(response, b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\");
+
+        // The DA1 response arrives after the deadline, and is read up to its end by the next call.
+        writer.write_all(b"\x1b[?62;c").unwrap();
+        read_response(
+            &mut reader,
+            &mut response,
+            Instant::now() + Duration::from_secs(10),
+        );
+        assert_eq!(response, b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;c");
+    }
+
+    #[test]
+    fn test_parse_osc_11_response() {
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;c"),
+            Some((255, 255, 255))
+        );
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:00/2b/36\x07"),
+            Some((0x00, 0x2b, 0x36))
+        );
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:fdfd/f6f6/e3e3\x1b\\"),
+            Some((0xfd, 0xf6, 0xe3))
+        );
+        assert_eq!(
+            parse_osc_11_response("\x1b]11;rgb:f/8/0\x1b\\"),
+            Some((255, 136, 0))
+        );
+        assert_eq!(parse_osc_11_response("\x1b[?62;c"), None);
+        assert_eq!(parse_osc_11_response("\x1b]11;rgb:ff/ff\x1b\\"), None);
+        assert_eq!(parse_osc_11_response("\x1b]11;rgb:ff/ff/ff/ff\x1b\\"), None);
+    }
+}
@@ -0,0 +1,355 @@
+use std::ops::Range;
+
+use ansi_term::ANSIString;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::cli;
+use crate::config::Config;
+use crate::delta::State;
+use crate::style::Style;
+use crate::wrapping::ensure_display_width_1;
+
+/// The symbols used by --show-whitespace.
+#[derive(Clone, Debug)]
+pub struct ShowWhitespaceConfig {
+    pub tab_symbol: String,
+    pub space_symbol: String,
+    pub cr_symbol: String,
+    pub nbsp_symbol: String,
+    pub invisible_symbol: String,
+}
+
+impl ShowWhitespaceConfig {
+    pub fn from_opt(opt: &cli::Opt) -> Option<Self> {
+        if !opt.show_whitespace {
+            return None;
+        }
+        Some(Self {
+            tab_symbol: ensure_display_width_1(
+                "whitespace-tab-symbol",
+                opt.whitespace_tab_symbol.clone(),
+            ),
+            space_symbol: ensure_display_width_1(
+                "whitespace-space-symbol",
+                opt.whitespace_space_symbol.clone(),
+            ),
+            cr_symbol: ensure_display_width_1(
+                "whitespace-cr-symbol",
+                opt.whitespace_cr_symbol.clone(),
+            ),
+            nbsp_symbol: ensure_display_width_1(
+                "whitespace-nbsp-symbol",
+                opt.whitespace_nbsp_symbol.clone(),
+            ),
+            invisible_symbol: ensure_display_width_1(
+                "whitespace-invisible-symbol",
+                opt.whitespace_invisible_symbol.clone(),
+            ),
+        })
+    }
+}
+
+/// Whether whitespace is shown in a line in `state`: only in the lines of a hunk, which are
+/// prepared with their tabs kept, see [`crate::paint::prepare`].
+pub fn is_shown(state: &State, config: &Config) -> bool {
+    config.show_whitespace.is_some()
+        && matches!(
+            state,
+            State::HunkZero(_, _)
+                | State::HunkMinus(_, _)
+                | State::HunkPlus(_, _)
+                | State::HunkZeroWrapped
+                | State::HunkMinusWrapped
+                | State::HunkPlusWrapped
+        )
+}
+
+/// Expand tabs as the tab symbol followed by spaces, and replace carriage returns, non-breaking
+/// spaces, other invisible characters and the spaces at the byte offsets for which `show_space`
+/// is true by their symbols. Return the text and the byte ranges of the symbols in it.
+///
+/// Since every symbol has a display width of 1, the text can then be measured, wrapped and
+/// padded like any other.
+pub fn expand_tabs_and_show_whitespace(
+    text: &str,
+    show_space: impl Fn(usize) -> bool,
+    tab_width: usize,
+    symbols: &ShowWhitespaceConfig,
+) -> (String, Vec<Range<usize>>) {
+    let mut shown = String::with_capacity(text.len());
+    let mut symbol_ranges: Vec<Range<usize>> = Vec::new();
+    let mut push_symbol = |shown: &mut String, symbol: &str| {
+        if symbol.is_empty() {
+            return;
+        }
+        let start = shown.len();
+        shown.push_str(symbol);
+        match symbol_ranges.last_mut() {
+            Some(range) if range.end == start => range.end = shown.len(),
+            _ => symbol_ranges.push(start..shown.len()),
+        }
+    };
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        if grapheme == "\t" {
+            push_symbol(&mut shown, &symbols.tab_symbol);
+            push_symbol(&mut shown, &" ".repeat(tab_width.saturating_sub(1)));
+            continue;
+        }
+        if grapheme == " " && show_space(offset) {
+            push_symbol(&mut shown, &symbols.space_symbol);
+            continue;
+        }
+        // Zero-width (non-)joiners are part of the spelling of words in some scripts, and of
+        // emoji sequences.
+        let joiners_are_visible = matches!(grapheme.chars().next(), Some(c) if !c.is_ascii());
+        for c in grapheme.chars() {
+            match c {
+                '\r' => push_symbol(&mut shown, &symbols.cr_symbol),
+                '\u{a0}' | '\u{2007}' | '\u{202f}' => push_symbol(&mut shown, &symbols.nbsp_symbol),
+                '\u{200c}' | '\u{200d}' if joiners_are_visible => shown.push(c),
+                c if is_invisible(c) => push_symbol(&mut shown, &symbols.invisible_symbol),
+                c => shown.push(c),
+            }
+        }
+    }
+    (shown, symbol_ranges)
+}
+
+/// The display width of `text` in a line of a hunk once it is painted, with its whitespace shown
+/// if --show-whitespace is in effect.
+pub fn display_width(text: &str, config: &Config) -> usize {
+    match &config.show_whitespace {
+        Some(symbols) => {
+            expand_tabs_and_show_whitespace(text, |_| false, config.tab_width, symbols)
+                .0
+                .width()
+        }
+        _ => text.width(),
+    }
+}
+
+/// True iff `c` is a control or format character which is not displayed by the terminal.
+fn is_invisible(c: char) -> bool {
+    matches!(c,
+        '\u{ad}'
+        | '\u{180e}'
+        | '\u{200b}'..='\u{200f}'
+        | '\u{202a}'..='\u{202e}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{2066}'..='\u{2069}'
+        | '\u{feff}'
+    ) || (c.is_control() && c != '\n' && c != '\t')
+}
+
+/// Return the byte ranges of the line represented by `diff_sections` in which spaces are shown:
+/// the changed sections of minus and plus lines, or the whole line if it has no within-line
+/// changes. This is the same rule as is used for whitespace errors.
+pub fn changed_ranges(diff_sections: &[(Style, &str)], state: &State) -> Vec<Range<usize>> {
+    if !matches!(
+        state,
+        State::HunkMinus(_, _)
+            | State::HunkPlus(_, _)
+            | State::HunkMinusWrapped
+            | State::HunkPlusWrapped
+    ) {
+        return Vec::new();
+    }
+    let line_has_emph_sections = diff_sections.iter().any(|(style, _)| style.is_emph)
+        && diff_sections.iter().any(|(style, _)| !style.is_emph);
+    let mut ranges = Vec::new();
+    let mut offset = 0;
+    for (style, text) in diff_sections {
+        if style.is_emph || !line_has_emph_sections {
+            ranges.push(offset..offset + text.len());
+        }
+        offset += text.len();
+    }
+    ranges
+}
+
+/// Paint `text`, which starts at byte `offset` of its line, in `style`, showing its whitespace
+/// in whitespace-style. Spaces within `changed` byte ranges of the line are painted as the space
+/// symbol.
+pub fn paint_showing_whitespace(
+    text: &str,
+    offset: usize,
+    style: ansi_term::Style,
+    changed: &[Range<usize>],
+    config: &Config,
+) -> Vec<ANSIString<'static>> {
+    let symbols = config.show_whitespace.as_ref().unwrap();
+    let whitespace_style = overlay(style, config.whitespace_style.ansi_term_style);
+    let show_space = |i: usize| changed.iter().any(|range| range.contains(&(offset + i)));
+    let (shown, symbol_ranges) =
+        expand_tabs_and_show_whitespace(text, show_space, config.tab_width, symbols);
+    let mut painted = Vec::new();
+    let mut plain_start = 0;
+    for range in symbol_ranges {
+        if plain_start < range.start {
+            painted.push(style.paint(shown[plain_start..range.start].to_string()));
+        }
+        painted.push(whitespace_style.paint(shown[range.clone()].to_string()));
+        plain_start = range.end;
+    }
+    if plain_start < shown.len() {
+        painted.push(style.paint(shown[plain_start..].to_string()));
+    }
+    painted
+}
+
+/// Apply the colors and attributes of `top` to `base`, keeping the colors of `base` which `top`
+/// does not set.
+fn overlay(base: ansi_term::Style, top: ansi_term::Style) -> ansi_term::Style {
+    ansi_term::Style {
+        foreground: top.foreground.or(base.foreground),
+        background: top.background.or(base.background),
+        is_bold: base.is_bold || top.is_bold,
+        is_dimmed: base.is_dimmed || top.is_dimmed,
+        is_italic: base.is_italic || top.is_italic,
+        is_underline: base.is_underline || top.is_underline,
+        is_blink: base.is_blink || top.is_blink,
+        is_reverse: base.is_reverse || top.is_reverse,
+        is_hidden: base.is_hidden || top.is_hidden,
+        is_strikethrough: base.is_strikethrough || top.is_strikethrough,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::measure_text_width;
+    use crate::ansi::strip_ansi_codes;
+    use crate::tests::integration_test_utils::{make_config_from_args, run_delta, DeltaTest};
+    use unicode_segmentation::UnicodeSegmentation;
+
+    fn show(line: &str) -> String {
+        let config = make_config_from_args(&["--show-whitespace", "--tabs", "4"]);
+        expand_tabs_and_show_whitespace(
+            line,
+            |_| false,
+            config.tab_width,
+            config.show_whitespace.as_ref().unwrap(),
+        )
+        .0
+    }
+
+    #[test]
+    fn test_expand_tabs_and_show_whitespace() {
+        assert_eq!(show("\tx = 1;\r"), "→   x = 1;␍");
+        assert_eq!(show("a\u{a0}b"), "a⍽b");
+        assert_eq!(show("a\u{200b}b\u{feff}"), "a¤b¤");
+        assert_eq!(show("if\u{202e} x"), "if¤ x");
+        // Joiners within emoji sequences are left alone.
+        assert_eq!(show("👩\u{200d}💻"), "👩\u{200d}💻");
+        assert_eq!(show("a\u{200d}b"), "a¤b");
+    }
+
+    #[test]
+    fn test_shown_symbol_ranges() {
+        let config = make_config_from_args(&["--show-whitespace", "--tabs", "2"]);
+        let (shown, ranges) = expand_tabs_and_show_whitespace(
+            "\tx = \"→ \"\r",
+            |offset| offset == 2,
+            config.tab_width,
+            config.show_whitespace.as_ref().unwrap(),
+        );
+        let symbols: Vec<&str> = ranges.into_iter().map(|range| &shown[range]).collect();
+        assert_eq!(symbols, ["→ ", "·", "␍"]);
+    }
+
+    #[test]
+    fn test_show_whitespace_width() {
+        for line in ["\tx\r", "a\u{200b}\u{200b}b", "x\u{a0}= 1"] {
+            let shown = show(line);
+            assert_eq!(measure_text_width(&shown), shown.graphemes(true).count());
+        }
+    }
+
+    #[test]
+    fn test_show_whitespace_unified() {
+        DeltaTest::with_args(&["--show-whitespace", "--tabs", "2"])
+            .with_input(WHITESPACE_DIFF)
+            .expect_after_header(
+                r#"
+                → x = 1
+                → x = 2␍
+                ·y⍽·=·3
+                → y a b"#,
+            );
+    }
+
+    #[test]
+    fn test_show_whitespace_side_by_side() {
+        let config = make_config_from_args(&[
+            "--show-whitespace",
+            "--side-by-side",
+            "--width",
+            "40",
+            "--tabs",
+            "2",
+        ]);
+        let output = strip_ansi_codes(&run_delta(WHITESPACE_DIFF, &config));
+        let lines: Vec<&str> = output.lines().skip(crate::config::HEADER_LEN).collect();
+        assert_eq!(lines[0], "│  1 │→ x = 1       │  1 │→ x = 2␍");
+        assert_eq!(lines[1], "│    │              │  2 │·y⍽·=·3");
+        assert_eq!(lines[2], "│  2 │→ y a b       │  3 │→ y a b");
+    }
+
+    #[test]
+    fn test_show_whitespace_in_raw_lines() {
+        // Lines colored by git --color-moved are emitted raw, with their escape sequences.
+        let config = make_config_from_args(&["--show-whitespace", "--tabs", "2"]);
+        let output = run_delta(
+            "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+\x1b[2m-\tx = 1\x1b[m
+ y = 2
+\x1b[2m+\x1b[m\x1b[2m  x = 1\x1b[m
+",
+            &config,
+        );
+        assert!(output.contains("\x1b[2m"), "{:?}", output);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().skip(crate::config::HEADER_LEN).collect();
+        assert_eq!(lines, ["→ x·=·1", "y = 2", "··x·=·1"]);
+    }
+
+    #[test]
+    fn test_show_whitespace_leaves_symbols_in_the_input_alone() {
+        let config = make_config_from_args(&["--show-whitespace", "--tabs", "2"]);
+        let output = run_delta(
+            "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -0,0 +1 @@
++x = \"→  ␍\"
+",
+            &config,
+        );
+        let whitespace_style = config.whitespace_style.ansi_term_style;
+        assert!(!output.contains(&whitespace_style.paint("→").to_string()));
+        assert!(!output.contains(&whitespace_style.paint("␍").to_string()));
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().skip(crate::config::HEADER_LEN).collect();
+        assert_eq!(lines, ["x·=·\"→··␍\""]);
+    }
+
+    const WHITESPACE_DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 1111111..2222222 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,3 @@
+-\tx = 1
++\tx = 2\r\x1b[m
++ y\u{a0} = 3
+ \ty a b
+";
+}
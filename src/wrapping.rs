@@ -10,11 +10,14 @@ use crate::config::Config;
 use crate::delta::DiffType;
 use crate::delta::State;
 use crate::features::line_numbers::{self, SideBySideLineWidth};
-use crate::features::side_by_side::{available_line_width, line_is_too_long, Left, Right};
+use crate::features::side_by_side::{
+    available_line_width, line_is_too_long, Left, Right, SideBySideData,
+};
 use crate::minusplus::*;
 use crate::paint::LineSections;
 use crate::style::Style;
 use crate::utils::syntect::FromDeltaStyle;
+use crate::whitespace;
 
 /// See [`wrap_line`] for documentation.
 #[derive(Clone, Debug)]
@@ -97,7 +100,7 @@ fn remove_percent_suffix(arg: &str) -> &str {
     }
 }
 
-fn ensure_display_width_1(what: &str, arg: String) -> String {
+pub fn ensure_display_width_1(what: &str, arg: String) -> String {
     match arg.grapheme_indices(true).count() {
         INLINE_SYMBOL_WIDTH_1 => arg,
         width => fatal(format!(
@@ -207,7 +210,7 @@ where
                     style,
                     text,
                     text.graphemes(true)
-                        .map(|item| (item.len(), item.width()))
+                        .map(|item| (item.len(), whitespace::display_width(item, config)))
                         .collect::<Vec<_>>(),
                 )
             })
@@ -578,6 +581,7 @@ pub fn wrap_minusplus_block<'c: 'a, 'a>(
 #[allow(clippy::comparison_chain, clippy::type_complexity)]
 pub fn wrap_zero_block<'c: 'a, 'a>(
     config: &'c Config,
+    panels: &SideBySideData,
     line: &str,
     mut states: Vec<State>,
     syntax_style_sections: Vec<LineSections<'a, SyntectStyle>>,
@@ -592,20 +596,17 @@ pub fn wrap_zero_block<'c: 'a, 'a>(
     // but in rare cases the remaining panel width might differ due to the space the line
     // numbers take up.
     let line_width = if let Some(line_numbers_data) = line_numbers_data {
-        let width = available_line_width(config, line_numbers_data);
+        let width = available_line_width(config, panels, line_numbers_data);
         std::cmp::min(width[Left], width[Right])
     } else {
-        std::cmp::min(
-            config.side_by_side_data[Left].width,
-            config.side_by_side_data[Right].width,
-        )
+        std::cmp::min(panels[Left].width, panels[Right].width)
     };
 
     // Called with a single line, so no need to use the 1-sized bool vector.
     // If that changes the wrapping logic should be updated as well.
     debug_assert_eq!(diff_style_sections.len(), 1);
 
-    let should_wrap = line_is_too_long(line, line_width);
+    let should_wrap = line_is_too_long(line, line_width, config);
 
     if should_wrap {
         let syntax_style = wrap_line(